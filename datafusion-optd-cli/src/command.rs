@@ -28,6 +28,7 @@ use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::common::exec_err;
 use datafusion::error::{DataFusionError, Result};
 use datafusion::prelude::SessionContext;
+use optd_datafusion_bridge::OptdQueryPlanner;
 use std::fs::File;
 use std::io::BufReader;
 use std::str::FromStr;
@@ -46,6 +47,9 @@ pub enum Command {
     SearchFunctions(String),
     QuietMode(Option<bool>),
     OutputFormat(Option<String>),
+    ListRules,
+    EnableRule(String),
+    DisableRule(String),
 }
 
 pub enum OutputFormat {
@@ -111,6 +115,24 @@ impl Command {
             Self::OutputFormat(_) => {
                 exec_err!("Unexpected change output format, this should be handled outside")
             }
+            Self::ListRules => {
+                let planner = optd_planner(ctx)?;
+                print_options.print_batches(&[all_rules_info(&planner)], now)
+            }
+            Self::EnableRule(name) => {
+                optd_planner(ctx)?
+                    .enable_rule(name)
+                    .map_err(|e| DataFusionError::External(e.into()))?;
+                println!("Rule {} enabled", name);
+                Ok(())
+            }
+            Self::DisableRule(name) => {
+                optd_planner(ctx)?
+                    .disable_rule(name)
+                    .map_err(|e| DataFusionError::External(e.into()))?;
+                println!("Rule {} disabled", name);
+                Ok(())
+            }
         }
     }
 
@@ -125,11 +147,14 @@ impl Command {
             Self::SearchFunctions(_) => ("\\h function", "search function"),
             Self::QuietMode(_) => ("\\quiet (true|false)?", "print or set quiet mode"),
             Self::OutputFormat(_) => ("\\pset [NAME [VALUE]]", "set table output option\n(format)"),
+            Self::ListRules => ("\\rules", "list optimizer rules"),
+            Self::EnableRule(_) => ("\\enable_rule name", "enable an optimizer rule"),
+            Self::DisableRule(_) => ("\\disable_rule name", "disable an optimizer rule"),
         }
     }
 }
 
-const ALL_COMMANDS: [Command; 9] = [
+const ALL_COMMANDS: [Command; 12] = [
     Command::ListTables,
     Command::DescribeTableStmt(String::new()),
     Command::Quit,
@@ -139,6 +164,9 @@ const ALL_COMMANDS: [Command; 9] = [
     Command::SearchFunctions(String::new()),
     Command::QuietMode(None),
     Command::OutputFormat(None),
+    Command::ListRules,
+    Command::EnableRule(String::new()),
+    Command::DisableRule(String::new()),
 ];
 
 fn all_commands_info() -> RecordBatch {
@@ -160,6 +188,45 @@ fn all_commands_info() -> RecordBatch {
     .expect("This should not fail")
 }
 
+fn optd_planner(ctx: &SessionContext) -> Result<Arc<OptdQueryPlanner>> {
+    match ctx.state().config().get_extension::<OptdQueryPlanner>() {
+        Some(planner) => Ok(planner),
+        None => exec_err!("optd query planner is not registered"),
+    }
+}
+
+fn all_rules_info(planner: &OptdQueryPlanner) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("Rule", DataType::Utf8, false),
+        Field::new("Category", DataType::Utf8, false),
+        Field::new("Type", DataType::Utf8, false),
+        Field::new("Enabled", DataType::Utf8, false),
+        Field::new("Description", DataType::Utf8, false),
+    ]));
+    let rules = planner.rules_info();
+    let columns = [
+        rules.iter().map(|x| x.name).collect::<Vec<_>>(),
+        rules.iter().map(|x| x.category).collect(),
+        rules
+            .iter()
+            .map(|x| if x.is_impl_rule { "impl" } else { "logical" })
+            .collect(),
+        rules
+            .iter()
+            .map(|x| if x.enabled { "true" } else { "false" })
+            .collect(),
+        rules.iter().map(|x| x.description).collect(),
+    ];
+    RecordBatch::try_new(
+        schema,
+        columns
+            .into_iter()
+            .map(|i| Arc::new(StringArray::from(i)) as ArrayRef)
+            .collect::<Vec<_>>(),
+    )
+    .expect("This should not fail")
+}
+
 impl FromStr for Command {
     type Err = ();
 
@@ -183,6 +250,9 @@ impl FromStr for Command {
             ("quiet", None) => Self::QuietMode(None),
             ("pset", Some(subcommand)) => Self::OutputFormat(Some(subcommand.to_string())),
             ("pset", None) => Self::OutputFormat(None),
+            ("rules", None) => Self::ListRules,
+            ("enable_rule", Some(name)) => Self::EnableRule(name.to_string()),
+            ("disable_rule", Some(name)) => Self::DisableRule(name.to_string()),
             _ => return Err(()),
         })
     }
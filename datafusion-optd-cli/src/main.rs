@@ -35,7 +35,7 @@ use std::collections::HashMap;
 use std::env;
 use std::path::Path;
 use std::str::FromStr;
-use std::sync::{Arc, OnceLock};
+use std::sync::{Arc, Mutex, OnceLock};
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
@@ -185,8 +185,14 @@ pub async fn main() -> Result<()> {
     let runtime_env = create_runtime_env(rn_config.clone())?;
 
     let mut ctx = {
-        let mut state =
-            SessionState::new_with_config_rt(session_config.clone(), Arc::new(runtime_env));
+        // the planner is registered as a config extension, so that CLI commands can reach the optimizer
+        let optd_planner = Arc::new(OptdQueryPlanner {
+            optimizer: Arc::new(Mutex::new(None)),
+        });
+        let mut state = SessionState::new_with_config_rt(
+            session_config.clone().with_extension(optd_planner.clone()),
+            Arc::new(runtime_env),
+        );
         // clean up optimizer rules so that we can plug in our own optimizer
         // state = state.with_optimizer_rules(vec![]);
         // state = state.with_physical_optimizer_rules(vec![]);
@@ -194,7 +200,8 @@ pub async fn main() -> Result<()> {
         let optimizer = DatafusionOptimizer::new_physical(Box::new(DatafusionCatalog::new(
             state.catalog_list(),
         )));
-        state = state.with_query_planner(Arc::new(OptdQueryPlanner::new(optimizer)));
+        *optd_planner.optimizer.lock().unwrap() = Some(Box::new(optimizer));
+        state = state.with_query_planner(optd_planner);
         SessionContext::new_with_state(state)
     };
     ctx.refresh_catalogs().await?;
@@ -169,8 +169,12 @@ async fn main() -> Result<()> {
         {
             let mut guard = perfect_optimizer.optimizer.lock().unwrap();
             let opt = guard.as_mut().unwrap();
-            opt.optd_optimizer_mut().disable_rule(1);
-            opt.optd_optimizer_mut().disable_rule(2);
+            opt.optd_optimizer_mut()
+                .disable_rule_by_name("join_assoc_rule")
+                .unwrap();
+            opt.optd_optimizer_mut()
+                .disable_rule_by_name("projection_pull_up_join")
+                .unwrap();
         }
 
         // derive the best order using the alternative optimizer
@@ -219,8 +223,12 @@ async fn main() -> Result<()> {
         {
             let mut guard = perfect_optimizer.optimizer.lock().unwrap();
             let opt = guard.as_mut().unwrap();
-            opt.optd_optimizer_mut().enable_rule(1);
-            opt.optd_optimizer_mut().enable_rule(2);
+            opt.optd_optimizer_mut()
+                .enable_rule_by_name("join_assoc_rule")
+                .unwrap();
+            opt.optd_optimizer_mut()
+                .enable_rule_by_name("projection_pull_up_join")
+                .unwrap();
         }
 
         let result =
@@ -4,6 +4,10 @@ mod memo;
 mod optimizer;
 mod tasks;
 
+pub use memo::ExprCostStatus;
 use memo::Memo;
-pub use optimizer::{CascadesOptimizer, GroupId, OptimizerProperties, RelNodeContext};
+pub use optimizer::{
+    CascadesOptimizer, ExprChoice, ExprId, GroupId, OptimizerPhase, OptimizerProperties,
+    ParetoConfig, RelNodeContext, RuleId, RuleInfo,
+};
 use tasks::Task;
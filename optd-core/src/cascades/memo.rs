@@ -1,6 +1,10 @@
 use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
+    collections::{
+        hash_map::{DefaultHasher, Entry},
+        BTreeSet, HashMap, HashSet,
+    },
     fmt::Display,
+    hash::{BuildHasherDefault, Hash, Hasher},
     sync::Arc,
 };
 
@@ -39,6 +43,67 @@ impl<T: RelNodeTyp> std::fmt::Display for RelMemoNode<T> {
     }
 }
 
+/// A hash-consed node in the memo, i.e., a memo node or an interned scalar expression. The hash is computed once and
+/// is stored together with the node in both the id -> node and the node -> id mappings. Therefore, a stored node is
+/// never hashed again, e.g., when the table is resized or when the node is evicted, and a lookup only hashes the node
+/// being looked up.
+struct Hashed<N> {
+    hash: u64,
+    node: Arc<N>,
+}
+
+impl<N: Hash> Hashed<N> {
+    fn new(node: Arc<N>) -> Self {
+        let mut hasher = DefaultHasher::new();
+        node.hash(&mut hasher);
+        Self {
+            hash: hasher.finish(),
+            node,
+        }
+    }
+}
+
+impl<N> Clone for Hashed<N> {
+    fn clone(&self) -> Self {
+        Self {
+            hash: self.hash,
+            node: self.node.clone(),
+        }
+    }
+}
+
+impl<N> Hash for Hashed<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl<N: PartialEq> PartialEq for Hashed<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && (Arc::ptr_eq(&self.node, &other.node) || self.node == other.node)
+    }
+}
+
+impl<N: Eq> Eq for Hashed<N> {}
+
+/// Passes through the precomputed hash of `Hashed`.
+#[derive(Default)]
+struct PrecomputedHasher(u64);
+
+impl Hasher for PrecomputedHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, _bytes: &[u8]) {
+        unreachable!("only the precomputed hash of a hashed node can be written")
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.0 = i;
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct Winner {
     pub impossible: bool,
@@ -46,15 +111,109 @@ pub struct Winner {
     pub cost: Cost,
 }
 
+/// The result of costing a physical expression, kept for explaining the choice of the winner.
+#[derive(Debug, Clone)]
+pub enum ExprCostStatus {
+    /// The expression is fully costed.
+    Costed(Cost),
+    /// The expression is pruned because its partial cost already exceeds the upper bound.
+    Pruned(Cost),
+    /// Some of the children of the expression cannot be optimized.
+    Impossible,
+}
+
+/// A plan kept on the Pareto frontier of a group in multi-objective mode. Unlike `Winner`, the entry remembers
+/// which frontier entries of the children it was built from, so that the full plan can be recovered later.
+#[derive(Debug, Clone)]
+pub struct ParetoWinner {
+    pub expr_id: ExprId,
+    pub cost: Cost,
+    pub children: Vec<Arc<ParetoWinner>>,
+}
+
 #[derive(Default, Debug, Clone)]
 pub struct GroupInfo {
     pub winner: Option<Winner>,
+    /// Non-dominated plans of the group. Only maintained when `pareto` is set in the optimizer.
+    pub pareto_frontier: Vec<Arc<ParetoWinner>>,
+}
+
+impl GroupInfo {
+    /// Add a plan to the Pareto frontier if it is not dominated by (or identical to) an existing entry, and remove
+    /// the entries dominated by it. If the frontier grows beyond `max_size`, the plan in the most crowded part of the
+    /// frontier is dropped, so that the best plans on each objective are kept. Returns whether the plan was added.
+    pub fn add_to_pareto_frontier(
+        &mut self,
+        candidate: Arc<ParetoWinner>,
+        objectives: &[usize],
+        max_size: usize,
+    ) -> bool {
+        if self.pareto_frontier.iter().any(|x| {
+            x.cost.dominates(&candidate.cost, objectives)
+                || x.cost.same_objectives(&candidate.cost, objectives)
+        }) {
+            return false;
+        }
+        self.pareto_frontier
+            .retain(|x| !candidate.cost.dominates(&x.cost, objectives));
+        self.pareto_frontier.push(candidate.clone());
+        if self.pareto_frontier.len() > max_size {
+            let distances = self.crowding_distances(objectives);
+            let (idx, _) = distances
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .unwrap();
+            let removed = self.pareto_frontier.remove(idx);
+            return !Arc::ptr_eq(&removed, &candidate);
+        }
+        true
+    }
+
+    /// The crowding distance of each plan on the frontier: the sum over the objectives of the distance between its
+    /// neighbors on that objective, relative to the range of the objective. The best and worst plans on each
+    /// objective have an infinite distance.
+    fn crowding_distances(&self, objectives: &[usize]) -> Vec<f64> {
+        let frontier = &self.pareto_frontier;
+        let mut distances = vec![0.0; frontier.len()];
+        for &objective in objectives {
+            let value = |idx: usize| frontier[idx].cost.0[objective];
+            let mut order = (0..frontier.len()).collect::<Vec<_>>();
+            order.sort_by(|&a, &b| value(a).total_cmp(&value(b)));
+            let (first, last) = (order[0], order[order.len() - 1]);
+            distances[first] = f64::INFINITY;
+            distances[last] = f64::INFINITY;
+            let range = value(last) - value(first);
+            if range <= 0.0 {
+                continue;
+            }
+            for window in order.windows(3) {
+                distances[window[1]] += (value(window[2]) - value(window[0])) / range;
+            }
+        }
+        distances
+    }
+}
+
+/// A simple hash function to derive a pseudo-random but reproducible order from the exploration seed.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
 }
 
 pub(crate) struct Group {
-    pub(crate) group_exprs: HashSet<ExprId>,
+    pub(crate) group_exprs: BTreeSet<ExprId>,
+    /// Expressions that became their own descendants when groups were merged. They are no longer explored, but are
+    /// kept with the group so that adding them again finds the group, and they are evicted together with it.
+    pub(crate) cyclic_exprs: BTreeSet<ExprId>,
     pub(crate) info: GroupInfo,
     pub(crate) properties: Arc<[Box<dyn Any + Send + Sync + 'static>]>,
+    /// The last iteration in which the group is reachable from an optimized plan, used for garbage collection.
+    pub(crate) last_used: usize,
+    /// Estimated memory used by the group and its expressions (in bytes).
+    pub(crate) memory_usage: usize,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Hash)]
@@ -74,23 +233,51 @@ impl Display for ReducedGroupId {
 
 pub struct Memo<T: RelNodeTyp> {
     expr_id_to_group_id: HashMap<ExprId, GroupId>,
-    expr_id_to_expr_node: HashMap<ExprId, RelMemoNodeRef<T>>,
-    expr_node_to_expr_id: HashMap<RelMemoNode<T>, ExprId>,
+    expr_id_to_expr_node: HashMap<ExprId, Hashed<RelMemoNode<T>>>,
+    expr_node_to_expr_id:
+        HashMap<Hashed<RelMemoNode<T>>, ExprId, BuildHasherDefault<PrecomputedHasher>>,
+    /// Scalar expression subtrees interned as a whole, only used when scalar expressions are not explored.
+    expr_id_to_scalar: HashMap<ExprId, Hashed<RelNode<T>>>,
+    scalar_to_expr_id: HashMap<Hashed<RelNode<T>>, ExprId, BuildHasherDefault<PrecomputedHasher>>,
+    explore_scalar_expressions: bool,
+    iteration: usize,
+    /// If set, expressions in a group are visited in a pseudo-random order determined by the seed, instead of in
+    /// the order they are added.
+    exploration_seed: Option<u64>,
+    expr_cost_status: HashMap<ExprId, ExprCostStatus>,
     groups: HashMap<ReducedGroupId, Group>,
     group_expr_counter: usize,
     merged_groups: HashMap<GroupId, GroupId>,
+    /// The groups that are merged into each (reduced) group and still have an entry in `groups`, including the group
+    /// itself.
+    group_entries: HashMap<ReducedGroupId, Vec<ReducedGroupId>>,
+    /// Estimated memory used by the memo table (in bytes), updated as groups and expressions are added, merged and
+    /// evicted.
+    memory_usage: usize,
     property_builders: Arc<[Box<dyn PropertyBuilderAny<T>>]>,
 }
 
 impl<T: RelNodeTyp> Memo<T> {
-    pub fn new(property_builders: Arc<[Box<dyn PropertyBuilderAny<T>>]>) -> Self {
+    pub fn new(
+        property_builders: Arc<[Box<dyn PropertyBuilderAny<T>>]>,
+        explore_scalar_expressions: bool,
+        exploration_seed: Option<u64>,
+    ) -> Self {
         Self {
             expr_id_to_group_id: HashMap::new(),
             expr_id_to_expr_node: HashMap::new(),
-            expr_node_to_expr_id: HashMap::new(),
+            expr_node_to_expr_id: HashMap::default(),
+            expr_id_to_scalar: HashMap::new(),
+            scalar_to_expr_id: HashMap::default(),
+            explore_scalar_expressions,
+            iteration: 0,
+            exploration_seed,
+            expr_cost_status: HashMap::new(),
             groups: HashMap::new(),
             group_expr_counter: 0,
             merged_groups: HashMap::new(),
+            group_entries: HashMap::new(),
+            memory_usage: 0,
             property_builders,
         }
     }
@@ -115,9 +302,80 @@ impl<T: RelNodeTyp> Memo<T> {
         }
         self.merged_groups
             .insert(group_a.as_group_id(), group_b.as_group_id());
+        self.memory_usage += 2 * std::mem::size_of::<GroupId>();
+        let entries_a = self.group_entries.remove(&group_a).unwrap_or_default();
+        self.group_entries
+            .entry(group_b)
+            .or_default()
+            .extend(entries_a);
         group_b
     }
 
+    /// Merge the group `from` into the group `into` when a rule finds that they are equivalent, e.g., when a
+    /// projection group is the same as its child. The expressions of `from` are moved into `into`, except those that
+    /// can reach the merged group through their children, which would otherwise form a cycle. The cheaper of the two
+    /// winners is kept.
+    pub fn merge_group_into(&mut self, from: GroupId, into: GroupId) {
+        let from = self.get_reduced_group_id(from);
+        let into = self.get_reduced_group_id(into);
+        if from == into {
+            return;
+        }
+        let from_group = self.groups.remove(&from).expect("group not found");
+        self.group_entries
+            .get_mut(&from)
+            .unwrap()
+            .retain(|x| *x != from);
+        // the expressions are moved to `into`, and only the group itself is freed
+        let group_overhead = self.group_memory_overhead();
+        self.memory_usage -= group_overhead;
+        let into_group = self.groups.get_mut(&into).expect("group not found");
+        into_group.memory_usage += from_group.memory_usage - group_overhead;
+        into_group.group_exprs.extend(from_group.group_exprs);
+        into_group.cyclic_exprs.extend(from_group.cyclic_exprs);
+        into_group.last_used = into_group.last_used.max(from_group.last_used);
+        self.merge_group(from, into);
+
+        // the cycles can go through any number of groups, e.g., `into` can be the child of a child of a projection
+        // in `from`, so the reachability is checked on the merged group
+        let exprs = self.groups[&into].group_exprs.iter().copied().collect_vec();
+        for expr_id in exprs {
+            let children = self.expr_id_to_expr_node[&expr_id]
+                .node
+                .children
+                .iter()
+                .map(|x| self.get_reduced_group_id(*x))
+                .collect_vec();
+            if self.reachable_groups(children).contains(&into) {
+                let into_group = self.groups.get_mut(&into).unwrap();
+                into_group.group_exprs.remove(&expr_id);
+                into_group.cyclic_exprs.insert(expr_id);
+            }
+        }
+
+        let into_group = self.groups.get_mut(&into).unwrap();
+        let group_exprs = &into_group.group_exprs;
+        let is_valid =
+            |winner: &Winner| !winner.impossible && group_exprs.contains(&winner.expr_id);
+        let info = &mut into_group.info;
+        info.pareto_frontier
+            .retain(|x| group_exprs.contains(&x.expr_id));
+        if let Some(winner) = &info.winner {
+            if !winner.impossible && !is_valid(winner) {
+                info.winner = None;
+            }
+        }
+        if let Some(from_winner) = from_group.info.winner.filter(is_valid) {
+            let is_cheaper = match &info.winner {
+                Some(winner) => winner.impossible || winner.cost > from_winner.cost,
+                None => true,
+            };
+            if is_cheaper {
+                info.winner = Some(from_winner);
+            }
+        }
+    }
+
     fn get_group_id_of_expr_id(&self, expr_id: ExprId) -> GroupId {
         self.expr_id_to_group_id[&expr_id]
     }
@@ -147,6 +405,14 @@ impl<T: RelNodeTyp> Memo<T> {
     }
 
     pub fn get_expr_info(&self, rel_node: RelNodeRef<T>) -> (GroupId, ExprId) {
+        if self.is_interned_scalar(&rel_node.typ) {
+            let scalar = Hashed::new(self.materialize_scalar(rel_node));
+            let Some(&expr_id) = self.scalar_to_expr_id.get(&scalar) else {
+                unreachable!("not found {}", scalar.node)
+            };
+            let group_id = self.get_group_id_of_expr_id(expr_id);
+            return (group_id, expr_id);
+        }
         let children_group_ids = rel_node
             .children
             .iter()
@@ -158,21 +424,89 @@ impl<T: RelNodeTyp> Memo<T> {
                 }
             })
             .collect::<Vec<_>>();
-        let memo_node = RelMemoNode {
+        let memo_node = Hashed::new(Arc::new(RelMemoNode {
             typ: rel_node.typ.clone(),
             children: children_group_ids,
             data: rel_node.data.clone(),
-        };
+        }));
         let Some(&expr_id) = self.expr_node_to_expr_id.get(&memo_node) else {
-            unreachable!("not found {}", memo_node)
+            unreachable!("not found {}", memo_node.node)
         };
         let group_id = self.get_group_id_of_expr_id(expr_id);
         return (group_id, expr_id);
     }
 
+    fn is_interned_scalar(&self, typ: &T) -> bool {
+        !self.explore_scalar_expressions && typ.is_scalar()
+    }
+
+    /// Replace the group placeholders in a scalar expression with the scalar expressions they refer to.
+    fn materialize_scalar(&self, rel_node: RelNodeRef<T>) -> RelNodeRef<T> {
+        if let Some(group_id) = rel_node.typ.extract_group() {
+            let group_id = self.get_reduced_group_id(group_id);
+            let group = self.groups.get(&group_id).expect("group not found");
+            let Some(scalar) = group
+                .group_exprs
+                .iter()
+                .find_map(|expr_id| self.expr_id_to_scalar.get(expr_id))
+            else {
+                unreachable!("group {} is not a scalar expression", group_id)
+            };
+            return scalar.node.clone();
+        }
+        fn has_placeholder<T: RelNodeTyp>(rel_node: &RelNode<T>) -> bool {
+            rel_node.typ.extract_group().is_some()
+                || rel_node.children.iter().any(|x| has_placeholder(x))
+        }
+        if !has_placeholder(&rel_node) {
+            return rel_node;
+        }
+        Arc::new(RelNode {
+            typ: rel_node.typ.clone(),
+            children: rel_node
+                .children
+                .iter()
+                .map(|child| self.materialize_scalar(child.clone()))
+                .collect(),
+            data: rel_node.data.clone(),
+        })
+    }
+
+    /// Get the interned scalar expression of an expression, if it is a scalar expression.
+    pub fn get_scalar_expr(&self, expr_id: ExprId) -> Option<RelNodeRef<T>> {
+        self.expr_id_to_scalar
+            .get(&expr_id)
+            .map(|scalar| scalar.node.clone())
+    }
+
+    fn infer_scalar_properties(
+        &self,
+        rel_node: &RelNode<T>,
+    ) -> Vec<Box<dyn Any + 'static + Send + Sync>> {
+        let child_properties = rel_node
+            .children
+            .iter()
+            .map(|child| self.infer_scalar_properties(child))
+            .collect_vec();
+        let mut props = Vec::with_capacity(self.property_builders.len());
+        for (id, builder) in self.property_builders.iter().enumerate() {
+            let child_properties = child_properties
+                .iter()
+                .map(|x| x[id].as_ref() as &dyn std::any::Any)
+                .collect::<Vec<_>>();
+            let prop = builder.derive_any(
+                rel_node.typ.clone(),
+                rel_node.data.clone(),
+                child_properties.as_slice(),
+            );
+            props.push(prop);
+        }
+        props
+    }
+
     fn infer_properties(
         &self,
-        memo_node: RelMemoNode<T>,
+        memo_node: &RelMemoNode<T>,
     ) -> Vec<Box<dyn Any + 'static + Send + Sync>> {
         let child_properties = memo_node
             .children
@@ -202,23 +536,72 @@ impl<T: RelNodeTyp> Memo<T> {
         &mut self,
         expr_id: ExprId,
         group_id: ReducedGroupId,
-        memo_node: RelMemoNode<T>,
+        memo_node: &RelMemoNode<T>,
     ) {
+        let expr_memory_usage = self.expr_memory_usage(expr_id);
+        self.memory_usage += expr_memory_usage;
         match self.groups.entry(group_id) {
             Entry::Occupied(mut entry) => {
                 let group = entry.get_mut();
                 group.group_exprs.insert(expr_id);
+                group.memory_usage += expr_memory_usage;
                 return;
             }
             _ => {}
         }
+        let properties = if let Some(scalar) = self.expr_id_to_scalar.get(&expr_id) {
+            self.infer_scalar_properties(&scalar.node)
+        } else {
+            self.infer_properties(memo_node)
+        };
         let mut group = Group {
-            group_exprs: HashSet::new(),
+            group_exprs: BTreeSet::new(),
+            cyclic_exprs: BTreeSet::new(),
             info: GroupInfo::default(),
-            properties: self.infer_properties(memo_node).into(),
+            properties: properties.into(),
+            last_used: self.iteration,
+            memory_usage: self.group_memory_overhead() + expr_memory_usage,
         };
         group.group_exprs.insert(expr_id);
+        self.memory_usage += self.group_memory_overhead();
         self.groups.insert(group_id, group);
+        self.group_entries.insert(group_id, vec![group_id]);
+    }
+
+    /// Add a scalar expression subtree into the memo as a single expression.
+    fn add_new_scalar_expr_inner(
+        &mut self,
+        rel_node: RelNodeRef<T>,
+        add_to_group_id: Option<ReducedGroupId>,
+    ) -> (ReducedGroupId, ExprId) {
+        let scalar = Hashed::new(self.materialize_scalar(rel_node));
+        if let Some(&expr_id) = self.scalar_to_expr_id.get(&scalar) {
+            let group_id = self.get_group_id_of_expr_id(expr_id);
+            let group_id = self.get_reduced_group_id(group_id);
+            if let Some(add_to_group_id) = add_to_group_id {
+                self.merge_group(add_to_group_id, group_id);
+            }
+            return (group_id, expr_id);
+        }
+        let expr_id = self.next_expr_id();
+        let group_id = if let Some(group_id) = add_to_group_id {
+            group_id
+        } else {
+            self.next_group_id()
+        };
+        let node = Arc::new(RelMemoNode {
+            typ: scalar.node.typ.clone(),
+            children: vec![],
+            data: scalar.node.data.clone(),
+        });
+        self.expr_id_to_expr_node
+            .insert(expr_id, Hashed::new(node.clone()));
+        self.expr_id_to_group_id
+            .insert(expr_id, group_id.as_group_id());
+        self.expr_id_to_scalar.insert(expr_id, scalar.clone());
+        self.scalar_to_expr_id.insert(scalar, expr_id);
+        self.add_expr_to_group(expr_id, group_id, &node);
+        (group_id, expr_id)
     }
 
     fn add_new_group_expr_inner(
@@ -226,6 +609,9 @@ impl<T: RelNodeTyp> Memo<T> {
         rel_node: RelNodeRef<T>,
         add_to_group_id: Option<ReducedGroupId>,
     ) -> (ReducedGroupId, ExprId) {
+        if self.is_interned_scalar(&rel_node.typ) {
+            return self.add_new_scalar_expr_inner(rel_node, add_to_group_id);
+        }
         let children_group_ids = rel_node
             .children
             .iter()
@@ -237,11 +623,11 @@ impl<T: RelNodeTyp> Memo<T> {
                 }
             })
             .collect::<Vec<_>>();
-        let memo_node = RelMemoNode {
+        let memo_node = Hashed::new(Arc::new(RelMemoNode {
             typ: rel_node.typ.clone(),
             children: children_group_ids,
             data: rel_node.data.clone(),
-        };
+        }));
         if let Some(&expr_id) = self.expr_node_to_expr_id.get(&memo_node) {
             let group_id = self.get_group_id_of_expr_id(expr_id);
             let group_id = self.get_reduced_group_id(group_id);
@@ -256,12 +642,12 @@ impl<T: RelNodeTyp> Memo<T> {
         } else {
             self.next_group_id()
         };
-        self.expr_id_to_expr_node
-            .insert(expr_id, memo_node.clone().into());
+        let node = memo_node.node.clone();
+        self.expr_id_to_expr_node.insert(expr_id, memo_node.clone());
         self.expr_id_to_group_id
             .insert(expr_id, group_id.as_group_id());
-        self.expr_node_to_expr_id.insert(memo_node.clone(), expr_id);
-        self.add_expr_to_group(expr_id, group_id, memo_node);
+        self.expr_node_to_expr_id.insert(memo_node, expr_id);
+        self.add_expr_to_group(expr_id, group_id, &node);
         (group_id, expr_id)
     }
 
@@ -280,6 +666,7 @@ impl<T: RelNodeTyp> Memo<T> {
         self.expr_id_to_expr_node
             .get(&expr_id)
             .expect("expr not found in expr mapping")
+            .node
             .clone()
     }
 
@@ -292,13 +679,10 @@ impl<T: RelNodeTyp> Memo<T> {
         exclude_placeholder: bool,
         level: Option<usize>,
     ) -> Vec<RelNodeRef<T>> {
-        let group_id = self.get_reduced_group_id(group_id);
-        let group = self.groups.get(&group_id).expect("group not found");
-        group
-            .group_exprs
-            .iter()
-            .filter(|x| !physical_only || !self.get_expr_memoed(**x).typ.is_logical())
-            .map(|&expr_id| {
+        self.get_all_exprs_in_group(group_id)
+            .into_iter()
+            .filter(|x| !physical_only || !self.get_expr_memoed(*x).typ.is_logical())
+            .map(|expr_id| {
                 self.get_all_expr_bindings(expr_id, physical_only, exclude_placeholder, level)
             })
             .concat()
@@ -313,6 +697,9 @@ impl<T: RelNodeTyp> Memo<T> {
         exclude_placeholder: bool,
         level: Option<usize>,
     ) -> Vec<RelNodeRef<T>> {
+        if let Some(scalar) = self.expr_id_to_scalar.get(&expr_id) {
+            return vec![scalar.node.clone()];
+        }
         let expr = self.get_expr_memoed(expr_id);
         if let Some(level) = level {
             if level == 0 {
@@ -368,7 +755,9 @@ impl<T: RelNodeTyp> Memo<T> {
         let group_id = self.get_reduced_group_id(group_id);
         let group = self.groups.get(&group_id).expect("group not found");
         let mut exprs = group.group_exprs.iter().copied().collect_vec();
-        exprs.sort();
+        if let Some(seed) = self.exploration_seed {
+            exprs.sort_by_cached_key(|x| splitmix64(seed ^ x.0 as u64));
+        }
         exprs
     }
 
@@ -422,6 +811,9 @@ impl<T: RelNodeTyp> Memo<T> {
         if let Some(winner) = info.winner {
             if !winner.impossible {
                 let expr_id = winner.expr_id;
+                if let Some(scalar) = self.expr_id_to_scalar.get(&expr_id) {
+                    return Ok(on_produce(scalar.node.clone(), group_id));
+                }
                 let expr = self.get_expr_memoed(expr_id);
                 let mut children = Vec::new();
                 children.reserve(expr.children.len());
@@ -439,14 +831,501 @@ impl<T: RelNodeTyp> Memo<T> {
         bail!("no best group binding for group {}", group_id)
     }
 
+    /// Pick a plan from the Pareto frontier of a group. `preference` maps the cost of each plan on the frontier to
+    /// a score, and the plan with the lowest score is returned.
+    pub fn get_pareto_group_binding(
+        &self,
+        group_id: GroupId,
+        preference: &dyn Fn(&Cost) -> f64,
+        on_produce: &mut impl FnMut(RelNodeRef<T>, GroupId) -> RelNodeRef<T>,
+    ) -> Result<RelNodeRef<T>> {
+        let info = self.get_group_info(group_id);
+        let Some(winner) = info
+            .pareto_frontier
+            .iter()
+            .min_by(|a, b| preference(&a.cost).total_cmp(&preference(&b.cost)))
+        else {
+            bail!("no pareto frontier for group {}", group_id)
+        };
+        Ok(self.get_pareto_binding_inner(group_id, winner, on_produce))
+    }
+
+    fn get_pareto_binding_inner(
+        &self,
+        group_id: GroupId,
+        winner: &ParetoWinner,
+        on_produce: &mut impl FnMut(RelNodeRef<T>, GroupId) -> RelNodeRef<T>,
+    ) -> RelNodeRef<T> {
+        if let Some(scalar) = self.expr_id_to_scalar.get(&winner.expr_id) {
+            return on_produce(scalar.node.clone(), group_id);
+        }
+        let expr = self.get_expr_memoed(winner.expr_id);
+        let mut children = Vec::with_capacity(expr.children.len());
+        for (child, child_winner) in expr.children.iter().zip(winner.children.iter()) {
+            children.push(self.get_pareto_binding_inner(*child, child_winner, on_produce));
+        }
+        let node = Arc::new(RelNode {
+            typ: expr.typ.clone(),
+            children,
+            data: expr.data.clone(),
+        });
+        on_produce(node, group_id)
+    }
+
+    /// Take a copy of the winners of all groups.
+    pub fn snapshot_group_info(&self) -> HashMap<GroupId, GroupInfo> {
+        self.groups
+            .iter()
+            .map(|(group_id, group)| (group_id.as_group_id(), group.info.clone()))
+            .collect()
+    }
+
+    /// Restore the winners taken by `snapshot_group_info`. Groups created after the snapshot are not affected.
+    pub fn restore_group_info(&mut self, snapshot: HashMap<GroupId, GroupInfo>) {
+        for (group_id, info) in snapshot {
+            if let Some(group) = self.groups.get_mut(&ReducedGroupId(group_id.0)) {
+                group.info = info;
+            }
+        }
+    }
+
     pub fn clear_winner(&mut self) {
         for group in self.groups.values_mut() {
             group.info.winner = None;
+            group.info.pareto_frontier.clear();
         }
+        self.expr_cost_status.clear();
+    }
+
+    pub fn update_expr_cost_status(&mut self, expr_id: ExprId, status: ExprCostStatus) {
+        self.expr_cost_status.insert(expr_id, status);
+    }
+
+    /// Get the cost status of an expression, `None` if it has not been costed since the winners are cleared.
+    pub fn get_expr_cost_status(&self, expr_id: ExprId) -> Option<ExprCostStatus> {
+        self.expr_cost_status.get(&expr_id).cloned()
     }
 
     /// Return number of expressions in the memo table.
     pub fn compute_plan_space(&self) -> usize {
         self.expr_id_to_expr_node.len()
     }
+
+    /// Start a new iteration. Groups created from now on are stamped with the new iteration.
+    pub fn next_iteration(&mut self) {
+        self.iteration += 1;
+    }
+
+    /// Mark all groups reachable from the group as used in the current iteration.
+    pub fn mark_used(&mut self, group_id: GroupId) {
+        let reachable = self.reachable_groups([self.get_reduced_group_id(group_id)]);
+        let iteration = self.iteration;
+        for group_id in reachable {
+            for entry in self.group_entries.get(&group_id).into_iter().flatten() {
+                self.groups.get_mut(entry).unwrap().last_used = iteration;
+            }
+        }
+    }
+
+    fn reachable_groups(
+        &self,
+        roots: impl IntoIterator<Item = ReducedGroupId>,
+    ) -> HashSet<ReducedGroupId> {
+        let mut visited = HashSet::new();
+        let mut stack = roots.into_iter().collect_vec();
+        while let Some(group_id) = stack.pop() {
+            if !visited.insert(group_id) {
+                continue;
+            }
+            for entry in self.group_entries.get(&group_id).into_iter().flatten() {
+                for expr_id in &self.groups[entry].group_exprs {
+                    for &child in &self.expr_id_to_expr_node[expr_id].node.children {
+                        stack.push(self.get_reduced_group_id(child));
+                    }
+                }
+            }
+        }
+        visited
+    }
+
+    /// Estimate the memory used by an expression (in bytes).
+    fn expr_memory_usage(&self, expr_id: ExprId) -> usize {
+        fn value_memory_usage(value: &Option<Value>) -> usize {
+            match value {
+                Some(Value::String(x)) => x.len(),
+                Some(Value::Serialized(x)) => x.len(),
+                Some(Value::List(x)) => x.len() * std::mem::size_of::<Value>(),
+                _ => 0,
+            }
+        }
+        fn scalar_memory_usage<T: RelNodeTyp>(node: &RelNode<T>) -> usize {
+            std::mem::size_of::<RelNode<T>>()
+                + value_memory_usage(&node.data)
+                + node
+                    .children
+                    .iter()
+                    .map(|x| scalar_memory_usage(x))
+                    .sum::<usize>()
+        }
+        let expr = &self.expr_id_to_expr_node[&expr_id].node;
+        let mut usage = std::mem::size_of::<RelMemoNode<T>>()
+            + expr.children.len() * std::mem::size_of::<GroupId>()
+            + value_memory_usage(&expr.data)
+            // entries in the expr mappings
+            + std::mem::size_of::<Hashed<RelMemoNode<T>>>()
+            + 3 * std::mem::size_of::<ExprId>()
+            + std::mem::size_of::<GroupId>();
+        if let Some(scalar) = self.expr_id_to_scalar.get(&expr_id) {
+            usage += scalar_memory_usage(&scalar.node);
+        }
+        usage
+    }
+
+    /// Estimate the memory used by a group without its expressions (in bytes).
+    fn group_memory_overhead(&self) -> usize {
+        std::mem::size_of::<Group>()
+            + self.property_builders.len() * std::mem::size_of::<Box<dyn Any + Send + Sync>>()
+    }
+
+    /// Estimate the memory used by the memo table (in bytes).
+    pub fn estimated_memory_usage(&self) -> usize {
+        self.memory_usage
+    }
+
+    /// Evict least recently used groups until the memo table fits in `memory_cap` bytes. Groups reachable from
+    /// `pinned` are never evicted. A group is only kept together with all groups reachable from it, so that the
+    /// remaining memo table is always consistent. Returns the evicted groups and expressions.
+    pub fn gc(&mut self, pinned: &[GroupId], memory_cap: usize) -> (Vec<GroupId>, Vec<ExprId>) {
+        if self.estimated_memory_usage() <= memory_cap {
+            return (vec![], vec![]);
+        }
+        let pinned = pinned
+            .iter()
+            .map(|x| self.get_reduced_group_id(*x))
+            .collect_vec();
+        let mut last_used: HashMap<ReducedGroupId, usize> = HashMap::new();
+        for (group_id, group) in &self.groups {
+            let entry = last_used
+                .entry(self.get_reduced_group_id(group_id.as_group_id()))
+                .or_default();
+            *entry = (*entry).max(group.last_used);
+        }
+        let mut thresholds = last_used.values().copied().unique().sorted().collect_vec();
+        // if nothing else fits, only keep the pinned groups
+        thresholds.push(usize::MAX);
+        let mut retained = HashSet::new();
+        for threshold in thresholds.into_iter().skip(1) {
+            retained = self.reachable_groups(
+                last_used
+                    .iter()
+                    .filter(|(_, used)| **used >= threshold)
+                    .map(|(group_id, _)| *group_id)
+                    .chain(pinned.iter().copied()),
+            );
+            let usage = retained
+                .iter()
+                .flat_map(|x| self.group_entries.get(x).into_iter().flatten())
+                .map(|x| self.groups[x].memory_usage)
+                .sum::<usize>();
+            if usage <= memory_cap {
+                break;
+            }
+        }
+
+        let mut evicted_groups = vec![];
+        let mut evicted_exprs = vec![];
+        let evicted = self
+            .group_entries
+            .keys()
+            .copied()
+            .filter(|x| !retained.contains(x))
+            .collect_vec();
+        for group_id in evicted {
+            for entry in self.group_entries.remove(&group_id).unwrap() {
+                let group = self.groups.remove(&entry).unwrap();
+                self.memory_usage -= group.memory_usage;
+                for expr_id in group.group_exprs.into_iter().chain(group.cyclic_exprs) {
+                    let node = self.expr_id_to_expr_node.remove(&expr_id).unwrap();
+                    self.expr_id_to_group_id.remove(&expr_id);
+                    if let Some(scalar) = self.expr_id_to_scalar.remove(&expr_id) {
+                        self.scalar_to_expr_id.remove(&scalar);
+                    } else {
+                        self.expr_node_to_expr_id.remove(&node);
+                    }
+                    self.expr_cost_status.remove(&expr_id);
+                    evicted_exprs.push(expr_id);
+                }
+                evicted_groups.push(entry.as_group_id());
+            }
+        }
+        let evicted_aliases = self
+            .merged_groups
+            .keys()
+            .copied()
+            .filter(|x| !retained.contains(&self.get_reduced_group_id(*x)))
+            .collect_vec();
+        for alias in evicted_aliases {
+            self.merged_groups.remove(&alias);
+            self.memory_usage -= 2 * std::mem::size_of::<GroupId>();
+            evicted_groups.push(alias);
+        }
+        evicted_groups.sort();
+        evicted_groups.dedup();
+        evicted_exprs.sort();
+        (evicted_groups, evicted_exprs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{GroupInfo, Memo, ParetoWinner, Winner};
+    use crate::{
+        cascades::{ExprId, GroupId},
+        cost::Cost,
+        rel_node::RelNode,
+        testing::{parse, TestTyp},
+    };
+
+    fn new_memo(explore_scalar_expressions: bool) -> Memo<TestTyp> {
+        Memo::new(Arc::new([]), explore_scalar_expressions, None)
+    }
+
+    /// A memo with a group of eight scans, returning the group and its expressions in insertion order.
+    fn memo_with_scans(exploration_seed: Option<u64>) -> (Memo<TestTyp>, GroupId, Vec<ExprId>) {
+        let mut memo = Memo::new(Arc::new([]), false, exploration_seed);
+        let (group_id, expr_id) = memo.add_new_group_expr(parse(r#"(Scan "t0")"#), None);
+        let mut exprs = vec![expr_id];
+        for i in 1..8 {
+            let (_, expr_id) =
+                memo.add_new_group_expr(parse(&format!(r#"(Scan "t{}")"#, i)), Some(group_id));
+            exprs.push(expr_id);
+        }
+        (memo, group_id, exprs)
+    }
+
+    fn winner(expr_id: usize, cost: &[f64]) -> Arc<ParetoWinner> {
+        Arc::new(ParetoWinner {
+            expr_id: ExprId(expr_id),
+            cost: Cost(cost.to_vec()),
+            children: vec![],
+        })
+    }
+
+    fn frontier(info: &GroupInfo) -> Vec<usize> {
+        let mut exprs = info
+            .pareto_frontier
+            .iter()
+            .map(|x| x.expr_id.0)
+            .collect::<Vec<_>>();
+        exprs.sort();
+        exprs
+    }
+
+    #[test]
+    fn pareto_frontier() {
+        let objectives = [1, 2];
+        let mut info = GroupInfo::default();
+        assert!(info.add_to_pareto_frontier(winner(1, &[3.0, 1.0, 2.0]), &objectives, 10));
+        assert!(info.add_to_pareto_frontier(winner(2, &[3.0, 2.0, 1.0]), &objectives, 10));
+        // dominated by 1
+        assert!(!info.add_to_pareto_frontier(winner(3, &[1.0, 1.0, 3.0]), &objectives, 10));
+        // identical to 2 on the objectives
+        assert!(!info.add_to_pareto_frontier(winner(4, &[1.0, 2.0, 1.0]), &objectives, 10));
+        assert_eq!(frontier(&info), vec![1, 2]);
+        // dominates 1 and 2
+        assert!(info.add_to_pareto_frontier(winner(5, &[3.0, 1.0, 1.0]), &objectives, 10));
+        assert_eq!(frontier(&info), vec![5]);
+    }
+
+    #[test]
+    fn pareto_frontier_cap() {
+        let objectives = [1, 2];
+        let mut info = GroupInfo::default();
+        assert!(info.add_to_pareto_frontier(winner(1, &[3.0, 1.0, 4.0]), &objectives, 3));
+        assert!(info.add_to_pareto_frontier(winner(2, &[0.0, 2.0, 3.0]), &objectives, 3));
+        assert!(info.add_to_pareto_frontier(winner(3, &[4.0, 5.0, 1.0]), &objectives, 3));
+        // the plan in the most crowded part of the frontier is dropped when the frontier is full, whatever the slots
+        // of the cost vector outside the objectives
+        assert!(info.add_to_pareto_frontier(winner(4, &[9.0, 3.0, 2.5]), &objectives, 3));
+        assert_eq!(frontier(&info), vec![1, 3, 4]);
+        // a plan close to the others on the frontier is not kept
+        assert!(!info.add_to_pareto_frontier(winner(5, &[0.0, 4.0, 1.5]), &objectives, 3));
+        assert_eq!(frontier(&info), vec![1, 3, 4]);
+        // the plans with the best value on an objective are always kept
+        assert!(info.add_to_pareto_frontier(winner(6, &[9.0, 6.0, 0.0]), &objectives, 3));
+        assert_eq!(frontier(&info), vec![1, 4, 6]);
+    }
+
+    #[test]
+    fn hash_consing() {
+        let mut memo = new_memo(false);
+        let plan = parse(r#"(Join (Scan "t1") (Scan "t2") (Eq (Column 0) (Column 1)))"#);
+        let (group_id, expr_id) = memo.add_new_group_expr(plan.clone(), None);
+        assert_eq!(
+            memo.add_new_group_expr(plan.clone(), None),
+            (group_id, expr_id)
+        );
+        assert_eq!(memo.get_expr_info(plan), (group_id, expr_id));
+        // the same expression with the children replaced by their groups
+        let join = memo.get_expr_memoed(expr_id);
+        let with_groups = Arc::new(RelNode {
+            typ: TestTyp::Join,
+            children: join
+                .children
+                .iter()
+                .map(|x| Arc::new(RelNode::new_group(*x)))
+                .collect(),
+            data: None,
+        });
+        assert_eq!(
+            memo.add_new_group_expr(with_groups, None),
+            (group_id, expr_id)
+        );
+        // different data
+        let (other_group_id, _) = memo.add_new_group_expr(
+            parse(r#"(Join (Scan "t1") (Scan "t3") (Eq (Column 0) (Column 1)))"#),
+            None,
+        );
+        assert_ne!(other_group_id, group_id);
+        assert_eq!(
+            memo.get_expr_info(parse(r#"(Scan "t1")"#)).0,
+            join.children[0]
+        );
+    }
+
+    #[test]
+    fn gc() {
+        let mut memo = new_memo(false);
+        let old_plan = parse(r#"(Join (Scan "t1") (Scan "t2") (Eq (Column 0) (Column 1)))"#);
+        let (old_root, _) = memo.add_new_group_expr(old_plan.clone(), None);
+        memo.mark_used(old_root);
+        memo.next_iteration();
+        let plan = parse(r#"(Filter (Scan "t2") (Eq (Column 1) (Column 1)))"#);
+        let (root, _) = memo.add_new_group_expr(plan.clone(), None);
+        memo.mark_used(root);
+        let (t1, _) = memo.get_expr_info(parse(r#"(Scan "t1")"#));
+        let (t2, _) = memo.get_expr_info(parse(r#"(Scan "t2")"#));
+
+        let mut expected = new_memo(false);
+        expected.add_new_group_expr(plan.clone(), None);
+        assert!(memo.estimated_memory_usage() > expected.estimated_memory_usage());
+
+        // nothing is evicted below the memory cap
+        let (evicted_groups, _) = memo.gc(&[root], memo.estimated_memory_usage());
+        assert!(evicted_groups.is_empty());
+
+        // the old plan is evicted, except for the scan of t2 which is reachable from the new plan
+        let (evicted_groups, _) = memo.gc(&[root], 0);
+        assert!(evicted_groups.contains(&old_root));
+        assert!(evicted_groups.contains(&t1));
+        assert!(!evicted_groups.contains(&t2));
+        assert!(!evicted_groups.contains(&root));
+        assert_eq!(memo.get_expr_info(plan).0, root);
+        assert_eq!(
+            memo.estimated_memory_usage(),
+            expected.estimated_memory_usage()
+        );
+
+        // an evicted plan can be added again
+        let (old_root, _) = memo.add_new_group_expr(old_plan.clone(), None);
+        assert_eq!(memo.get_expr_info(old_plan).0, old_root);
+    }
+
+    #[test]
+    fn exploration_order() {
+        // expressions are visited in insertion order by default
+        let (memo, group_id, exprs) = memo_with_scans(None);
+        assert_eq!(memo.get_all_exprs_in_group(group_id), exprs);
+
+        // the same seed always produces the same permutation
+        let (memo, group_id, exprs) = memo_with_scans(Some(1));
+        let order = memo.get_all_exprs_in_group(group_id);
+        assert_ne!(order, exprs);
+        let (other_memo, other_group_id, _) = memo_with_scans(Some(1));
+        assert_eq!(other_memo.get_all_exprs_in_group(other_group_id), order);
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, exprs);
+
+        let (memo, group_id, _) = memo_with_scans(Some(2));
+        assert_ne!(memo.get_all_exprs_in_group(group_id), order);
+    }
+
+    #[test]
+    fn merge_identity_projection() {
+        let mut memo = new_memo(false);
+        let plan = parse(r#"(Projection (Scan "t1") (List))"#);
+        let (projection, projection_expr) = memo.add_new_group_expr(plan.clone(), None);
+        let (scan, scan_expr) = memo.get_expr_info(parse(r#"(Scan "t1")"#));
+        let memory_usage = memo.estimated_memory_usage();
+        memo.merge_group_into(projection, scan);
+        // the projection would be its own child in the merged group
+        assert_eq!(memo.get_all_exprs_in_group(scan), vec![scan_expr]);
+        assert_eq!(memo.get_all_exprs_in_group(projection), vec![scan_expr]);
+        assert_eq!(memo.get_group_id(projection_expr), scan);
+        assert!(memo.estimated_memory_usage() < memory_usage);
+    }
+
+    #[test]
+    fn merge_nested_projection() {
+        let mut memo = new_memo(false);
+        let plan = parse(r#"(Projection (Projection (Scan "t1") (List)) (List))"#);
+        let (outer, _) = memo.add_new_group_expr(plan, None);
+        let (inner, _) = memo.get_expr_info(parse(r#"(Projection (Scan "t1") (List))"#));
+        let (scan, scan_expr) = memo.get_expr_info(parse(r#"(Scan "t1")"#));
+        // the outer projection only reaches the scan through the inner projection
+        memo.merge_group_into(outer, scan);
+        assert_eq!(memo.get_all_exprs_in_group(scan), vec![scan_expr]);
+        memo.merge_group_into(inner, scan);
+        assert_eq!(memo.get_all_exprs_in_group(scan), vec![scan_expr]);
+        assert_eq!(memo.get_all_group_ids().len(), 2);
+    }
+
+    #[test]
+    fn merge_keeps_cheaper_winner() {
+        let mut memo = new_memo(false);
+        let (t1, t1_expr) = memo.add_new_group_expr(parse(r#"(Scan "t1")"#), None);
+        let (t2, t2_expr) = memo.add_new_group_expr(parse(r#"(Scan "t2")"#), None);
+        for (group_id, expr_id, cost) in [(t1, t1_expr, 5.0), (t2, t2_expr, 10.0)] {
+            memo.update_group_info(
+                group_id,
+                GroupInfo {
+                    winner: Some(Winner {
+                        impossible: false,
+                        expr_id,
+                        cost: Cost(vec![cost, 1.0]),
+                    }),
+                    pareto_frontier: vec![],
+                },
+            );
+        }
+        memo.merge_group_into(t1, t2);
+        assert_eq!(memo.get_all_exprs_in_group(t2), vec![t1_expr, t2_expr]);
+        let winner = memo.get_group_info(t2).winner.unwrap();
+        assert_eq!(winner.expr_id, t1_expr);
+        assert_eq!(winner.cost.0[0], 5.0);
+    }
+
+    #[test]
+    fn reinsert_cyclic_expr() {
+        let mut memo = new_memo(false);
+        let plan = parse(r#"(Projection (Scan "t1") (List))"#);
+        let (projection, projection_expr) = memo.add_new_group_expr(plan.clone(), None);
+        let (scan, scan_expr) = memo.get_expr_info(parse(r#"(Scan "t1")"#));
+        memo.merge_group_into(projection, scan);
+        // the dropped expression is still known to be in the merged group, but is not explored again
+        assert_eq!(
+            memo.add_new_group_expr(plan.clone(), None),
+            (scan, projection_expr)
+        );
+        assert_eq!(memo.get_all_exprs_in_group(scan), vec![scan_expr]);
+        // and it is evicted together with the group
+        let (_, evicted_exprs) = memo.gc(&[], 0);
+        assert!(evicted_exprs.contains(&projection_expr));
+        assert_eq!(memo.estimated_memory_usage(), 0);
+        let (group_id, expr_id) = memo.add_new_group_expr(plan, None);
+        assert_eq!(memo.get_all_exprs_in_group(group_id), vec![expr_id]);
+    }
 }
@@ -4,24 +4,35 @@ use std::{
     sync::Arc,
 };
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 
 use crate::{
-    cost::CostModel,
+    cost::{Cost, CostModel},
     optimizer::Optimizer,
     property::{PropertyBuilder, PropertyBuilderAny},
-    rel_node::{RelNodeRef, RelNodeTyp},
+    rel_node::{RelNode, RelNodeRef, RelNodeTyp},
     rules::Rule,
 };
 
 use super::{
-    memo::{GroupInfo, RelMemoNodeRef},
-    tasks::OptimizeGroupTask,
+    memo::{ExprCostStatus, GroupInfo, RelMemoNodeRef},
+    tasks::{match_and_pick_group, OptimizeExpressionTask, OptimizeGroupTask},
     Memo, Task,
 };
 
 pub type RuleId = usize;
 
+/// The metadata of a rule registered in the optimizer.
+#[derive(Clone, Debug)]
+pub struct RuleInfo {
+    pub id: RuleId,
+    pub name: &'static str,
+    pub category: &'static str,
+    pub is_impl_rule: bool,
+    pub description: &'static str,
+    pub enabled: bool,
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct OptimizerContext {
     pub upper_bound: Option<f64>,
@@ -29,12 +40,67 @@ pub struct OptimizerContext {
     pub rules_applied: usize,
 }
 
+/// A physical expression considered for a group, as returned by `explain_choice`.
+#[derive(Clone, Debug)]
+pub struct ExprChoice<T: RelNodeTyp> {
+    pub expr_id: ExprId,
+    pub typ: T,
+    /// The memo representation of the expression.
+    pub expr: String,
+    /// `None` if the expression has not been costed.
+    pub status: Option<ExprCostStatus>,
+    pub is_winner: bool,
+    /// How much the weighted cost of the expression exceeds the cost of the winner.
+    pub cost_over_winner: Option<f64>,
+}
+
+/// A phase of the optimization process. Each phase only applies its own subset of rules, with its own exploration
+/// budget.
+#[derive(Clone, Debug)]
+pub struct OptimizerPhase {
+    pub name: String,
+    /// The names of the rules to apply in this phase.
+    pub rules: Vec<String>,
+    /// Same as `OptimizerProperties::partial_explore_iter`, but only for this phase.
+    pub partial_explore_iter: Option<usize>,
+    /// Same as `OptimizerProperties::partial_explore_space`, but only for this phase.
+    pub partial_explore_space: Option<usize>,
+    /// Skip the remaining phases if the cost of the plan after this phase is below this value.
+    pub stop_if_cost_below: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ParetoConfig {
+    /// The slots of the cost vector the plans are compared on.
+    pub objectives: Vec<usize>,
+    /// The maximum number of plans kept on the Pareto frontier of each group. The plans of a group are built from
+    /// all combinations of the frontiers of its children, so the frontiers are always bounded to keep this tractable.
+    pub frontier_cap: usize,
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct OptimizerProperties {
     /// If the number of rules applied exceeds this number, we stop applying logical rules.
     pub partial_explore_iter: Option<usize>,
     /// Plan space can be expanded by this number of times before we stop applying logical rules.
     pub partial_explore_space: Option<usize>,
+    /// If set, each group additionally keeps a Pareto frontier of non-dominated plans. The final plan can then be
+    /// picked with `step_get_optimize_rel_pareto`.
+    pub pareto: Option<ParetoConfig>,
+    /// If set, every node of a scalar expression gets its own group and rules can be applied on scalar expressions.
+    /// Defaults to `false`, where scalar expression subtrees are interned in the memo as opaque expressions: matchers
+    /// still see the full expression tree, but a rule rooted at a scalar node only fires on the root of an interned
+    /// subtree, and rewriting a scalar expression adds the whole rewritten subtree as a new expression.
+    pub explore_scalar_expressions: bool,
+    /// Memory budget of the memo table (in bytes). `step_gc` evicts least recently used groups above this limit.
+    pub memo_memory_cap: Option<usize>,
+    /// If not empty, the optimizer runs these phases in order instead of applying all rules at once. The memo
+    /// table is shared across phases, and the plan of the last phase producing a plan is used.
+    pub phases: Vec<OptimizerPhase>,
+    /// By default, the expressions in a group are always visited in the order they are added to the memo table.
+    /// If set, they are visited in a pseudo-random order determined by the seed, which is useful for testing the
+    /// robustness of the optimizer. The same seed always produces the same order.
+    pub exploration_seed: Option<u64>,
 }
 
 pub struct CascadesOptimizer<T: RelNodeTyp> {
@@ -42,7 +108,16 @@ pub struct CascadesOptimizer<T: RelNodeTyp> {
     pub(super) tasks: VecDeque<Box<dyn Task<T>>>,
     explored_group: HashSet<GroupId>,
     fired_rules: HashMap<ExprId, HashSet<RuleId>>,
+    /// Expressions with logical rules not applied because the exploration budget was used up, together with whether
+    /// they were being explored. The pending tasks are still executed after the budget is used up, only without
+    /// applying logical rules, so these expressions are all the remaining exploration work. They are resumed in the
+    /// next `step_optimize_rel` on the same root group, except when running in phases.
+    suspended_exprs: Vec<(ExprId, bool)>,
+    suspended_root: Option<GroupId>,
+    /// The rules of the current phase, `None` if all rules are enabled.
+    phase_rules: Option<HashSet<RuleId>>,
     rules: Arc<[Arc<dyn Rule<T, Self>>]>,
+    rule_names: HashMap<&'static str, RuleId>,
     disabled_rules: HashSet<usize>,
     cost: Arc<dyn CostModel<T>>,
     property_builders: Arc<[Box<dyn PropertyBuilderAny<T>>]>,
@@ -59,7 +134,7 @@ pub struct RelNodeContext {
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Hash)]
-pub struct GroupId(pub(super) usize);
+pub struct GroupId(pub(crate) usize);
 
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default, Hash)]
 pub struct ExprId(pub usize);
@@ -93,13 +168,27 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
     ) -> Self {
         let tasks = VecDeque::new();
         let property_builders: Arc<[_]> = property_builders.into();
-        let memo = Memo::new(property_builders.clone());
+        let memo = Memo::new(
+            property_builders.clone(),
+            prop.explore_scalar_expressions,
+            prop.exploration_seed,
+        );
+        let mut rule_names = HashMap::new();
+        for (rule_id, rule) in rules.iter().enumerate() {
+            if rule_names.insert(rule.name(), rule_id).is_some() {
+                panic!("duplicated rule name: {}", rule.name());
+            }
+        }
         Self {
             memo,
             tasks,
             explored_group: HashSet::new(),
             fired_rules: HashMap::new(),
+            suspended_exprs: Vec::new(),
+            suspended_root: None,
+            phase_rules: None,
             rules: rules.into(),
+            rule_names,
             cost: cost.into(),
             ctx: OptimizerContext::default(),
             property_builders,
@@ -128,6 +217,43 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
         self.disabled_rules.contains(&rule_id)
     }
 
+    /// Get the id of a rule by its name.
+    pub fn rule_id(&self, name: &str) -> Option<RuleId> {
+        self.rule_names.get(name).copied()
+    }
+
+    pub fn disable_rule_by_name(&mut self, name: &str) -> Result<()> {
+        let Some(rule_id) = self.rule_id(name) else {
+            bail!("rule {} not found", name)
+        };
+        self.disable_rule(rule_id);
+        Ok(())
+    }
+
+    pub fn enable_rule_by_name(&mut self, name: &str) -> Result<()> {
+        let Some(rule_id) = self.rule_id(name) else {
+            bail!("rule {} not found", name)
+        };
+        self.enable_rule(rule_id);
+        Ok(())
+    }
+
+    /// Get the metadata of all rules registered in the optimizer.
+    pub fn rules_info(&self) -> Vec<RuleInfo> {
+        self.rules
+            .iter()
+            .enumerate()
+            .map(|(id, rule)| RuleInfo {
+                id,
+                name: rule.name(),
+                category: rule.category(),
+                is_impl_rule: rule.is_impl_rule(),
+                description: rule.description(),
+                enabled: !self.is_rule_disabled(id),
+            })
+            .collect()
+    }
+
     pub fn dump(&self, group_id: Option<GroupId>) {
         if let Some(group_id) = group_id {
             fn dump_inner<T: RelNodeTyp>(this: &CascadesOptimizer<T>, group_id: GroupId) {
@@ -191,9 +317,15 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
 
     /// Clear the memo table and all optimizer states.
     pub fn step_clear(&mut self) {
-        self.memo = Memo::new(self.property_builders.clone());
+        self.memo = Memo::new(
+            self.property_builders.clone(),
+            self.prop.explore_scalar_expressions,
+            self.prop.exploration_seed,
+        );
         self.fired_rules.clear();
         self.explored_group.clear();
+        self.suspended_exprs.clear();
+        self.suspended_root = None;
     }
 
     /// Clear the winner so that the optimizer can continue to explore the group.
@@ -203,11 +335,103 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
 
     /// Optimize a `RelNode`.
     pub fn step_optimize_rel(&mut self, root_rel: RelNodeRef<T>) -> Result<GroupId> {
+        self.memo.next_iteration();
         let (group_id, _) = self.add_group_expr(root_rel, None);
-        self.fire_optimize_tasks(group_id)?;
+        if self.prop.phases.is_empty() {
+            self.fire_optimize_tasks(
+                group_id,
+                self.prop.partial_explore_iter,
+                self.prop.partial_explore_space,
+            )?;
+        } else {
+            self.fire_optimize_phases(group_id)?;
+        }
+        self.memo.mark_used(group_id);
         Ok(group_id)
     }
 
+    /// Run all optimization phases. Winners are recomputed in each phase, so that plans produced by the rules of the
+    /// current phase can win. If a phase fails to produce a plan, the winners of the last successful phase are kept.
+    fn fire_optimize_phases(&mut self, group_id: GroupId) -> Result<()> {
+        let phases = self.prop.phases.clone();
+        let mut phase_rules = Vec::with_capacity(phases.len());
+        for phase in &phases {
+            let mut rules = HashSet::new();
+            for name in &phase.rules {
+                let Some(rule_id) = self.rule_id(name) else {
+                    bail!("rule {} of phase {} not found", name, phase.name)
+                };
+                rules.insert(rule_id);
+            }
+            phase_rules.push(rules);
+        }
+        let mut handoff = None;
+        for ((idx, phase), rules) in phases.iter().enumerate().zip(phase_rules) {
+            if idx != 0 {
+                self.memo.clear_winner();
+            }
+            self.explored_group.clear();
+            // the expressions suspended in another phase are pending on the rules of that phase
+            self.suspended_exprs.clear();
+            self.suspended_root = None;
+            self.phase_rules = Some(rules);
+            let res = self.fire_optimize_tasks(
+                group_id,
+                phase.partial_explore_iter,
+                phase.partial_explore_space,
+            );
+            self.phase_rules = None;
+            res?;
+            let Some(winner) = self.memo.get_group_info(group_id).winner else {
+                continue;
+            };
+            if winner.impossible {
+                continue;
+            }
+            handoff = Some(self.memo.snapshot_group_info());
+            if let Some(stop_if_cost_below) = phase.stop_if_cost_below {
+                if winner.cost.0[0] < stop_if_cost_below {
+                    break;
+                }
+            }
+        }
+        let has_winner = self
+            .memo
+            .get_group_info(group_id)
+            .winner
+            .map(|x| !x.impossible)
+            .unwrap_or(false);
+        if !has_winner {
+            if let Some(handoff) = handoff {
+                self.memo.restore_group_info(handoff);
+            }
+        }
+        Ok(())
+    }
+
+    /// Estimated memory usage of the memo table in bytes.
+    pub fn memo_memory_usage(&self) -> usize {
+        self.memo.estimated_memory_usage()
+    }
+
+    /// Evict least recently used groups if the memo table exceeds `memo_memory_cap`. Groups reachable from `root`
+    /// are always kept. Returns the evicted groups.
+    pub fn step_gc(&mut self, root: GroupId) -> Vec<GroupId> {
+        let Some(memory_cap) = self.prop.memo_memory_cap else {
+            return vec![];
+        };
+        let (evicted_groups, evicted_exprs) = self.memo.gc(&[root], memory_cap);
+        for group_id in &evicted_groups {
+            self.explored_group.remove(group_id);
+        }
+        for expr_id in &evicted_exprs {
+            self.fired_rules.remove(expr_id);
+        }
+        self.suspended_exprs
+            .retain(|(expr_id, _)| evicted_exprs.binary_search(expr_id).is_err());
+        evicted_groups
+    }
+
     /// Get the group binding.
     pub fn step_get_optimize_rel(
         &self,
@@ -219,9 +443,49 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
             .get_best_group_binding(group_id, &mut on_produce)?)
     }
 
-    fn fire_optimize_tasks(&mut self, group_id: GroupId) -> Result<()> {
+    /// Get a plan from the Pareto frontier of a group, using `preference` to score the candidates (the lower the
+    /// better). Requires `pareto` to be set.
+    pub fn step_get_optimize_rel_pareto(
+        &self,
+        group_id: GroupId,
+        preference: impl Fn(&Cost) -> f64,
+        mut on_produce: impl FnMut(RelNodeRef<T>, GroupId) -> RelNodeRef<T>,
+    ) -> Result<RelNodeRef<T>> {
+        self.memo
+            .get_pareto_group_binding(group_id, &preference, &mut on_produce)
+    }
+
+    /// Get the costs of all plans on the Pareto frontier of a group.
+    pub fn get_pareto_frontier(&self, group_id: GroupId) -> Vec<Cost> {
+        self.memo
+            .get_group_info(group_id)
+            .pareto_frontier
+            .iter()
+            .map(|x| x.cost.clone())
+            .collect()
+    }
+
+    fn fire_optimize_tasks(
+        &mut self,
+        group_id: GroupId,
+        partial_explore_iter: Option<usize>,
+        partial_explore_space: Option<usize>,
+    ) -> Result<()> {
         self.tasks
             .push_back(Box::new(OptimizeGroupTask::new(group_id)));
+        // resume the logical rules suspended in the last run on the same root, so that repeated runs converge to a
+        // full exploration instead of stopping at the explored groups
+        let suspended_exprs = std::mem::take(&mut self.suspended_exprs);
+        if self.suspended_root == Some(group_id) && !suspended_exprs.is_empty() {
+            // the winners are recomputed, as the new expressions might improve the winners of any group, which the
+            // parent groups have to see
+            self.memo.clear_winner();
+            for (expr_id, exploring) in suspended_exprs.into_iter().rev() {
+                self.tasks
+                    .push_back(Box::new(OptimizeExpressionTask::new(expr_id, exploring)));
+            }
+        }
+        self.suspended_root = Some(group_id);
         // get the task from the stack
         self.ctx.budget_used = false;
         let plan_space_begin = self.memo.compute_plan_space();
@@ -232,7 +496,7 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
             iter += 1;
             if !self.ctx.budget_used {
                 let plan_space = self.memo.compute_plan_space();
-                if let Some(partial_explore_space) = self.prop.partial_explore_space {
+                if let Some(partial_explore_space) = partial_explore_space {
                     if plan_space - plan_space_begin > partial_explore_space {
                         println!(
                             "plan space size budget used, not applying logical rules any more. current plan space: {}",
@@ -240,7 +504,7 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
                         );
                         self.ctx.budget_used = true;
                     }
-                } else if let Some(partial_explore_iter) = self.prop.partial_explore_iter {
+                } else if let Some(partial_explore_iter) = partial_explore_iter {
                     if iter >= partial_explore_iter {
                         println!(
                             "plan explore iter budget used, not applying logical rules any more. current plan space: {}",
@@ -255,8 +519,7 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
     }
 
     fn optimize_inner(&mut self, root_rel: RelNodeRef<T>) -> Result<RelNodeRef<T>> {
-        let (group_id, _) = self.add_group_expr(root_rel, None);
-        self.fire_optimize_tasks(group_id)?;
+        let group_id = self.step_optimize_rel(root_rel)?;
         self.memo.get_best_group_binding(group_id, &mut |x, _| x)
     }
 
@@ -268,6 +531,43 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
         group_id
     }
 
+    /// Add `root_rel` to the memo and apply the rule `rule_id` once to its root expression, without exploring or
+    /// optimizing the plan. Returns the expressions produced by the rule, where the groups they refer to are expanded
+    /// into the (first) expression of each group. This is useful for testing a rule with input and output plans.
+    pub fn apply_rule_to_root(
+        &mut self,
+        rule_id: RuleId,
+        root_rel: RelNodeRef<T>,
+    ) -> Vec<RelNodeRef<T>> {
+        let (group_id, _) = self.add_group_expr(root_rel, None);
+        let rule = self.rules[rule_id].clone();
+        let mut result = vec![];
+        for binding in match_and_pick_group(rule.matcher(), group_id, self) {
+            for expr in rule.apply(self, binding) {
+                result.push(self.expand_groups(expr.into()));
+            }
+        }
+        result
+    }
+
+    fn expand_groups(&self, node: RelNodeRef<T>) -> RelNodeRef<T> {
+        if let Some(group_id) = node.typ.extract_group() {
+            return self
+                .memo
+                .get_all_group_bindings(group_id, false, false, None)
+                .remove(0);
+        }
+        Arc::new(RelNode {
+            typ: node.typ.clone(),
+            children: node
+                .children
+                .iter()
+                .map(|child| self.expand_groups(child.clone()))
+                .collect(),
+            data: node.data.clone(),
+        })
+    }
+
     pub(super) fn get_all_exprs_in_group(&self, group_id: GroupId) -> Vec<ExprId> {
         self.memo.get_all_exprs_in_group(group_id)
     }
@@ -284,6 +584,10 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
         self.memo.add_new_group_expr(expr, group_id)
     }
 
+    pub(super) fn merge_group_into(&mut self, from: GroupId, into: GroupId) {
+        self.memo.merge_group_into(from, into)
+    }
+
     pub(super) fn get_group_info(&self, group_id: GroupId) -> GroupInfo {
         self.memo.get_group_info(group_id)
     }
@@ -311,6 +615,10 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
         self.memo.get_expr_memoed(expr_id)
     }
 
+    pub(super) fn get_scalar_expr(&self, expr_id: ExprId) -> Option<RelNodeRef<T>> {
+        self.memo.get_scalar_expr(expr_id)
+    }
+
     pub(super) fn get_all_expr_bindings(
         &self,
         expr_id: ExprId,
@@ -333,6 +641,19 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
         self.explored_group.insert(group_id);
     }
 
+    pub(super) fn is_rule_in_phase(&self, rule_id: RuleId) -> bool {
+        self.phase_rules
+            .as_ref()
+            .map(|rules| rules.contains(&rule_id))
+            .unwrap_or(true)
+    }
+
+    pub(super) fn suspend_expr(&mut self, expr_id: ExprId, exploring: bool) {
+        if !self.suspended_exprs.contains(&(expr_id, exploring)) {
+            self.suspended_exprs.push((expr_id, exploring));
+        }
+    }
+
     pub(super) fn is_rule_fired(&self, group_expr_id: ExprId, rule_id: RuleId) -> bool {
         self.fired_rules
             .get(&group_expr_id)
@@ -347,6 +668,70 @@ impl<T: RelNodeTyp> CascadesOptimizer<T> {
             .insert(rule_id);
     }
 
+    pub(super) fn update_expr_cost_status(&mut self, expr_id: ExprId, status: ExprCostStatus) {
+        self.memo.update_expr_cost_status(expr_id, status)
+    }
+
+    /// List all physical expressions of a group, with their costs compared with the winner of the group.
+    pub fn explain_choice(&self, group_id: GroupId) -> Vec<ExprChoice<T>> {
+        let winner = self
+            .memo
+            .get_group_info(group_id)
+            .winner
+            .filter(|x| !x.impossible);
+        self.memo
+            .get_all_exprs_in_group(group_id)
+            .into_iter()
+            .filter_map(|expr_id| {
+                let expr = self.memo.get_expr_memoed(expr_id);
+                if expr.typ.is_logical() {
+                    return None;
+                }
+                let status = self.memo.get_expr_cost_status(expr_id);
+                let cost_over_winner = match (&status, &winner) {
+                    (Some(ExprCostStatus::Costed(cost)), Some(winner)) => {
+                        Some(cost.0[0] - winner.cost.0[0])
+                    }
+                    _ => None,
+                };
+                Some(ExprChoice {
+                    expr_id,
+                    typ: expr.typ.clone(),
+                    expr: expr.to_string(),
+                    status,
+                    is_winner: winner.as_ref().map(|x| x.expr_id) == Some(expr_id),
+                    cost_over_winner,
+                })
+            })
+            .collect()
+    }
+
+    /// Explain the choices of all groups in the best plan of the root group, in pre-order.
+    pub fn explain_choice_plan(&self, group_id: GroupId) -> Vec<(GroupId, Vec<ExprChoice<T>>)> {
+        fn explain_inner<T: RelNodeTyp>(
+            this: &CascadesOptimizer<T>,
+            group_id: GroupId,
+            visited: &mut HashSet<GroupId>,
+            result: &mut Vec<(GroupId, Vec<ExprChoice<T>>)>,
+        ) {
+            if !visited.insert(group_id) {
+                return;
+            }
+            result.push((group_id, this.explain_choice(group_id)));
+            if let Some(ref winner) = this.memo.get_group_info(group_id).winner {
+                if winner.impossible {
+                    return;
+                }
+                for &child in &this.memo.get_expr_memoed(winner.expr_id).children {
+                    explain_inner(this, child, visited, result);
+                }
+            }
+        }
+        let mut result = vec![];
+        explain_inner(self, group_id, &mut HashSet::new(), &mut result);
+        result
+    }
+
     pub fn get_cost_of(&self, group_id: GroupId) -> f64 {
         self.memo
             .get_group_info(group_id)
@@ -366,3 +751,252 @@ impl<T: RelNodeTyp> Optimizer<T> for CascadesOptimizer<T> {
         self.get_property_by_group::<P>(self.resolve_group_id(root_rel), idx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        optimizer::Optimizer,
+        rules::RuleMatcher,
+        testing::{
+            eliminate_projection_rule, fn_rule, join_assoc_rule, join_commute_rule, new_optimizer,
+            parse, TestTyp,
+        },
+    };
+
+    use super::{ExprCostStatus, OptimizerPhase, OptimizerProperties};
+
+    #[test]
+    fn rules_see_expanded_scalars() {
+        // `(Filter child (Eq x x)) -> child`, matching into the scalar expression
+        let eliminate_filter = fn_rule(
+            RuleMatcher::MatchNode {
+                typ: TestTyp::Filter,
+                children: vec![
+                    RuleMatcher::PickOne {
+                        pick_to: 0,
+                        expand: false,
+                    },
+                    RuleMatcher::MatchNode {
+                        typ: TestTyp::Eq,
+                        children: vec![
+                            RuleMatcher::PickOne {
+                                pick_to: 1,
+                                expand: false,
+                            },
+                            RuleMatcher::PickOne {
+                                pick_to: 2,
+                                expand: false,
+                            },
+                        ],
+                    },
+                ],
+            },
+            |mut input| {
+                if input[&1] != input[&2] {
+                    return vec![];
+                }
+                vec![input.remove(&0).unwrap()]
+            },
+            "eliminate_filter",
+        );
+        for explore_scalar_expressions in [false, true] {
+            let mut optimizer = new_optimizer(
+                vec![eliminate_filter.clone(), eliminate_projection_rule()],
+                &[],
+                OptimizerProperties {
+                    explore_scalar_expressions,
+                    ..Default::default()
+                },
+            );
+            let plan = optimizer
+                .optimize(parse(
+                    "(Join (Filter (Scan \"t1\") (Eq (Column 1) (Column 1))) (Projection (Scan \"t2\") (List)) (Eq (Column 1) (Column 2)))",
+                ))
+                .unwrap();
+            assert_eq!(
+                plan,
+                parse("(PhysicalJoin (PhysicalScan \"t1\") (PhysicalScan \"t2\") (Eq (Column 1) (Column 2)))")
+            );
+            // the root group is merged into the group of the scan
+            let plan = optimizer
+                .optimize(parse(
+                    "(Projection (Filter (Scan \"t3\") (Eq (Column 1) (Column 1))) (List))",
+                ))
+                .unwrap();
+            assert_eq!(plan, parse("(PhysicalScan \"t3\")"));
+        }
+    }
+
+    #[test]
+    fn apply_rule_to_root() {
+        let mut optimizer = new_optimizer(
+            vec![join_commute_rule(), join_assoc_rule()],
+            &[],
+            OptimizerProperties::default(),
+        );
+        let join_assoc = optimizer.rule_id("join_assoc").unwrap();
+        let plan = parse(r#"(Join (Join (Scan "t1") (Scan "t2") (List)) (Scan "t3") (List))"#);
+        assert_eq!(
+            optimizer.apply_rule_to_root(join_assoc, plan),
+            vec![parse(
+                r#"(Join (Scan "t1") (Join (Scan "t2") (Scan "t3") (List)) (List))"#
+            )]
+        );
+        // the rule does not match
+        let plan = parse(r#"(Join (Scan "t1") (Scan "t2") (List))"#);
+        assert!(optimizer.apply_rule_to_root(join_assoc, plan).is_empty());
+    }
+
+    #[test]
+    fn budgeted_runs_converge() {
+        let plan = parse(r#"(Join (Join (Scan "t1") (Scan "t2") (List)) (Scan "t3") (List))"#);
+        let tables = [("t1", 100.0), ("t2", 10.0), ("t3", 1.0)];
+        let rules = || vec![join_commute_rule(), join_assoc_rule()];
+        let mut optimizer = new_optimizer(rules(), &tables, OptimizerProperties::default());
+        optimizer.optimize(plan.clone()).unwrap();
+        let expected = optimizer.get_cost_of(optimizer.resolve_group_id(plan.clone()));
+
+        let mut optimizer = new_optimizer(
+            rules(),
+            &tables,
+            OptimizerProperties {
+                partial_explore_iter: Some(2),
+                ..Default::default()
+            },
+        );
+        optimizer.optimize(plan.clone()).unwrap();
+        assert!(optimizer.get_cost_of(optimizer.resolve_group_id(plan.clone())) > expected);
+        // the second run continues the exploration, and the winners are recomputed
+        optimizer.optimize(plan.clone()).unwrap();
+        assert_eq!(
+            optimizer.get_cost_of(optimizer.resolve_group_id(plan)),
+            expected
+        );
+    }
+
+    #[test]
+    fn seeded_exploration_order() {
+        let plan = parse(r#"(Join (Join (Scan "t1") (Scan "t2") (List)) (Scan "t3") (List))"#);
+        let tables = [("t1", 100.0), ("t2", 10.0), ("t3", 1.0)];
+        let rules = || vec![join_commute_rule(), join_assoc_rule()];
+        let optimize = |exploration_seed| {
+            let mut optimizer = new_optimizer(
+                rules(),
+                &tables,
+                OptimizerProperties {
+                    exploration_seed,
+                    ..Default::default()
+                },
+            );
+            let best = optimizer.optimize(plan.clone()).unwrap();
+            (
+                best,
+                optimizer.get_cost_of(optimizer.resolve_group_id(plan.clone())),
+            )
+        };
+        let (_, expected) = optimize(None);
+        for seed in 0..8 {
+            // the order does not change the cost of the best plan, and the same seed produces the same plan
+            let (best, cost) = optimize(Some(seed));
+            assert_eq!(cost, expected);
+            assert_eq!(optimize(Some(seed)).0, best);
+        }
+    }
+
+    #[test]
+    fn phases() {
+        let plan = parse(r#"(Join (Join (Scan "t1") (Scan "t2") (List)) (Scan "t3") (List))"#);
+        let tables = [("t1", 100.0), ("t2", 10.0), ("t3", 1.0)];
+        let rules = || vec![join_commute_rule(), join_assoc_rule()];
+        let mut optimizer = new_optimizer(rules(), &tables, OptimizerProperties::default());
+        optimizer.optimize(plan.clone()).unwrap();
+        let expected = optimizer.get_cost_of(optimizer.resolve_group_id(plan.clone()));
+
+        let implementation = OptimizerPhase {
+            name: "implementation".to_string(),
+            rules: ["convert_scan", "convert_join"]
+                .map(|x| x.to_string())
+                .to_vec(),
+            partial_explore_iter: None,
+            partial_explore_space: None,
+            stop_if_cost_below: None,
+        };
+        let join_reorder = OptimizerPhase {
+            name: "join_reorder".to_string(),
+            rules: ["join_commute", "join_assoc", "convert_scan", "convert_join"]
+                .map(|x| x.to_string())
+                .to_vec(),
+            ..implementation.clone()
+        };
+        let mut optimizer = new_optimizer(
+            rules(),
+            &tables,
+            OptimizerProperties {
+                phases: vec![implementation.clone()],
+                ..Default::default()
+            },
+        );
+        optimizer.optimize(plan.clone()).unwrap();
+        assert!(optimizer.get_cost_of(optimizer.resolve_group_id(plan.clone())) > expected);
+
+        let mut optimizer = new_optimizer(
+            rules(),
+            &tables,
+            OptimizerProperties {
+                phases: vec![implementation.clone(), join_reorder],
+                ..Default::default()
+            },
+        );
+        optimizer.optimize(plan.clone()).unwrap();
+        assert_eq!(
+            optimizer.get_cost_of(optimizer.resolve_group_id(plan.clone())),
+            expected
+        );
+
+        let mut optimizer = new_optimizer(
+            rules(),
+            &tables,
+            OptimizerProperties {
+                phases: vec![OptimizerPhase {
+                    rules: vec!["no_such_rule".to_string()],
+                    ..implementation
+                }],
+                ..Default::default()
+            },
+        );
+        assert!(optimizer.optimize(plan).is_err());
+    }
+
+    #[test]
+    fn explain_choice() {
+        let plan = parse(r#"(Join (Scan "t1") (Scan "t2") (List))"#);
+        let mut optimizer = new_optimizer(
+            vec![join_commute_rule()],
+            &[("t1", 100.0), ("t2", 1.0)],
+            OptimizerProperties::default(),
+        );
+        optimizer.optimize(plan.clone()).unwrap();
+        let group_id = optimizer.resolve_group_id(plan);
+        let choices = optimizer.explain_choice(group_id);
+        // the logical joins are not listed, only the two physical joins
+        assert_eq!(choices.len(), 2);
+        assert!(choices.iter().all(|x| x.typ == TestTyp::PhysicalJoin));
+        let (winners, losers): (Vec<_>, Vec<_>) = choices.into_iter().partition(|x| x.is_winner);
+        assert_eq!(winners.len(), 1);
+        assert_eq!(winners[0].cost_over_winner, Some(0.0));
+        // `t2 join t1` costs 1 + 1 + 100 + 1, `t1 join t2` costs 100 + 100 + 1 + 1 (the last 1 is the condition)
+        assert!(matches!(
+            losers[0].status,
+            Some(ExprCostStatus::Costed(ref cost)) if cost.0[0] == 202.0
+        ));
+        assert_eq!(losers[0].cost_over_winner, Some(99.0));
+
+        // the scans and the condition are explained after the root group, following the winner
+        let plan_choices = optimizer.explain_choice_plan(group_id);
+        assert_eq!(plan_choices.len(), 4);
+        assert_eq!(plan_choices[0].0, group_id);
+        assert!(plan_choices[1..]
+            .iter()
+            .all(|(_, choices)| choices.len() == 1 && choices[0].is_winner));
+    }
+}
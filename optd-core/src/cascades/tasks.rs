@@ -10,6 +10,7 @@ mod optimize_expression;
 mod optimize_group;
 mod optimize_inputs;
 
+pub(crate) use apply_rule::match_and_pick_group;
 pub use apply_rule::ApplyRuleTask;
 pub use explore_group::ExploreGroupTask;
 pub use optimize_expression::OptimizeExpressionTask;
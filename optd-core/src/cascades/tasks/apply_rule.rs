@@ -8,10 +8,10 @@ use crate::{
     cascades::{
         memo::RelMemoNodeRef,
         optimizer::{CascadesOptimizer, ExprId, RuleId},
-        tasks::{OptimizeExpressionTask, OptimizeInputsTask},
+        tasks::{OptimizeExpressionTask, OptimizeGroupTask, OptimizeInputsTask},
         GroupId,
     },
-    rel_node::{RelNode, RelNodeTyp},
+    rel_node::{RelNode, RelNodeRef, RelNodeTyp},
     rules::RuleMatcher,
 };
 
@@ -125,19 +125,77 @@ fn match_node<T: RelNodeTyp>(
     picks
 }
 
-fn match_and_pick_group<T: RelNodeTyp>(
+pub(crate) fn match_and_pick_group<T: RelNodeTyp>(
     matcher: &RuleMatcher<T>,
     group_id: GroupId,
     optimizer: &CascadesOptimizer<T>,
 ) -> Vec<HashMap<usize, RelNode<T>>> {
     let mut matches = vec![];
     for expr_id in optimizer.get_all_exprs_in_group(group_id) {
+        if let Some(scalar) = optimizer.get_scalar_expr(expr_id) {
+            let mut pick = HashMap::new();
+            if match_and_pick_scalar(matcher, &scalar, &mut pick) {
+                matches.push(pick);
+            }
+            continue;
+        }
         let node = optimizer.get_expr_memoed(expr_id);
         matches.extend(match_and_pick(matcher, node, optimizer));
     }
     matches
 }
 
+/// Match an interned scalar expression. The expression is fully materialized, so there is at most one binding.
+fn match_and_pick_scalar<T: RelNodeTyp>(
+    matcher: &RuleMatcher<T>,
+    node: &RelNodeRef<T>,
+    pick: &mut HashMap<usize, RelNode<T>>,
+) -> bool {
+    let (typ, children, pick_to) = match matcher {
+        RuleMatcher::MatchAndPickNode {
+            typ,
+            children,
+            pick_to,
+        } => (typ, children, Some(*pick_to)),
+        RuleMatcher::MatchNode { typ, children } => (typ, children, None),
+        _ => panic!("top node should be match node"),
+    };
+    if &node.typ != typ {
+        return false;
+    }
+    if let Some(RuleMatcher::PickMany { .. } | RuleMatcher::IgnoreMany) = children.last() {
+    } else if children.len() != node.children.len() {
+        return false;
+    }
+    for (idx, child) in children.iter().enumerate() {
+        match child {
+            RuleMatcher::IgnoreOne => {}
+            RuleMatcher::IgnoreMany => break,
+            RuleMatcher::PickOne { pick_to, .. } => {
+                let res = pick.insert(*pick_to, node.child(idx).as_ref().clone());
+                assert!(res.is_none(), "dup pick");
+            }
+            RuleMatcher::PickMany { pick_to } => {
+                let res = pick.insert(*pick_to, RelNode::new_list(node.children[idx..].to_vec()));
+                assert!(res.is_none(), "dup pick");
+                break;
+            }
+            _ => {
+                if idx >= node.children.len()
+                    || !match_and_pick_scalar(child, &node.children[idx], pick)
+                {
+                    return false;
+                }
+            }
+        }
+    }
+    if let Some(pick_to) = pick_to {
+        let res = pick.insert(pick_to, node.as_ref().clone());
+        assert!(res.is_none(), "dup pick");
+    }
+    true
+}
+
 fn match_and_pick<T: RelNodeTyp>(
     matcher: &RuleMatcher<T>,
     node: RelMemoNodeRef<T>,
@@ -188,8 +246,18 @@ impl<T: RelNodeTyp> Task<T> for ApplyRuleTask {
             let applied = rule.apply(optimizer, expr);
             for expr in applied {
                 let RelNode { typ, .. } = &expr;
-                if typ.extract_group().is_some() {
-                    unreachable!();
+                if let Some(into_group_id) = typ.extract_group() {
+                    // the rule finds that the group is the same as another group
+                    trace!(event = "apply_rule", expr_id = %self.expr_id, rule_id = %self.rule_id, merge_into = %into_group_id);
+                    optimizer.merge_group_into(group_id, into_group_id);
+                    if !self.exploring {
+                        // the merged group may be the one being optimized, e.g., the root group, while `into` has
+                        // not been optimized yet
+                        tasks.push(
+                            Box::new(OptimizeGroupTask::new(into_group_id)) as Box<dyn Task<T>>
+                        );
+                    }
+                    continue;
                 }
                 let expr_typ = typ.clone();
                 let (_, expr_id) = optimizer.add_group_expr(expr.into(), Some(group_id));
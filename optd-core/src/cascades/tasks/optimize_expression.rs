@@ -45,6 +45,9 @@ impl<T: RelNodeTyp> Task<T> for OptimizeExpressionTask {
         trace!(event = "task_begin", task = "optimize_expr", expr_id = %self.expr_id, expr = %expr);
         let mut tasks = vec![];
         for (rule_id, rule) in optimizer.rules().iter().enumerate() {
+            if !optimizer.is_rule_in_phase(rule_id) {
+                continue;
+            }
             if optimizer.is_rule_fired(self.expr_id, rule_id) {
                 continue;
             }
@@ -52,7 +55,9 @@ impl<T: RelNodeTyp> Task<T> for OptimizeExpressionTask {
                 continue;
             }
             if optimizer.ctx.budget_used && !rule.is_impl_rule() {
-                break;
+                // the implementation rules are still applied so that the expression can be costed
+                optimizer.suspend_expr(self.expr_id, self.exploring);
+                continue;
             }
             if top_matches(rule.matcher(), expr.typ.clone(), expr.data.clone()) {
                 tasks.push(
@@ -1,12 +1,14 @@
+use std::sync::Arc;
+
 use anyhow::Result;
 use tracing::trace;
 
 use crate::{
     cascades::{
-        memo::{GroupInfo, Winner},
+        memo::{ExprCostStatus, GroupInfo, ParetoWinner, Winner},
         optimizer::ExprId,
         tasks::OptimizeGroupTask,
-        CascadesOptimizer, GroupId, RelNodeContext,
+        CascadesOptimizer, GroupId, ParetoConfig, RelNodeContext,
     },
     cost::Cost,
     rel_node::RelNodeTyp,
@@ -67,10 +69,19 @@ impl OptimizeInputsTask {
         input_cost
     }
 
-    fn should_terminate(&self, cost_so_far: f64, upper_bound: Option<f64>) -> bool {
+    fn should_terminate<T: RelNodeTyp>(
+        &self,
+        cost_so_far: f64,
+        optimizer: &CascadesOptimizer<T>,
+    ) -> bool {
         if !self.pruning {
             return false;
         }
+        // a plan exceeding the bound on the weighted cost might still be on the Pareto frontier
+        if optimizer.prop.pareto.is_some() {
+            return false;
+        }
+        let upper_bound = optimizer.ctx.upper_bound;
         if upper_bound.is_none() {
             return false;
         }
@@ -111,10 +122,69 @@ impl OptimizeInputsTask {
                         expr_id: self.expr_id,
                         cost: cost_so_far.clone(),
                     }),
+                    pareto_frontier: group_info.pareto_frontier,
                 },
             );
         }
     }
+
+    /// Combine the Pareto frontiers of all children with this expression and merge the resulting plans into the
+    /// frontier of the group.
+    fn update_pareto_frontier<T: RelNodeTyp>(
+        &self,
+        pareto: &ParetoConfig,
+        optimizer: &mut CascadesOptimizer<T>,
+    ) {
+        let expr = optimizer.get_expr_memoed(self.expr_id);
+        let group_id = optimizer.get_group_id(self.expr_id);
+        let cost = optimizer.cost();
+        let context = RelNodeContext {
+            expr_id: self.expr_id,
+            group_id,
+        };
+        let mut combinations: Vec<Vec<Arc<ParetoWinner>>> = vec![vec![]];
+        for &child in &expr.children {
+            let frontier = optimizer.get_group_info(child).pareto_frontier;
+            if frontier.is_empty() {
+                return;
+            }
+            let mut next_combinations = Vec::with_capacity(combinations.len() * frontier.len());
+            for combination in &combinations {
+                for child_winner in &frontier {
+                    let mut combination = combination.clone();
+                    combination.push(child_winner.clone());
+                    next_combinations.push(combination);
+                }
+            }
+            combinations = next_combinations;
+        }
+        let scalar = optimizer.get_scalar_expr(self.expr_id);
+        let mut group_info = optimizer.get_group_info(group_id);
+        let mut updated = false;
+        for children in combinations {
+            let total_cost = if let Some(ref scalar) = scalar {
+                cost.compute_plan_node_cost(scalar)
+            } else {
+                let input_cost = children.iter().map(|x| x.cost.clone()).collect::<Vec<_>>();
+                cost.sum(
+                    &cost.compute_cost(&expr.typ, &expr.data, &input_cost, Some(context)),
+                    &input_cost,
+                )
+            };
+            updated |= group_info.add_to_pareto_frontier(
+                Arc::new(ParetoWinner {
+                    expr_id: self.expr_id,
+                    cost: total_cost,
+                    children,
+                }),
+                &pareto.objectives,
+                pareto.frontier_cap,
+            );
+        }
+        if updated {
+            optimizer.update_group_info(group_id, group_info);
+        }
+    }
 }
 
 impl<T: RelNodeTyp> Task<T> for OptimizeInputsTask {
@@ -141,6 +211,18 @@ impl<T: RelNodeTyp> Task<T> for OptimizeInputsTask {
         let children = &expr.children;
         let cost = optimizer.cost();
 
+        if let Some(scalar) = optimizer.get_scalar_expr(self.expr_id) {
+            // interned scalar expressions are costed as a whole
+            let scalar_cost = cost.compute_plan_node_cost(&scalar);
+            self.update_winner(&scalar_cost, optimizer);
+            optimizer.update_expr_cost_status(self.expr_id, ExprCostStatus::Costed(scalar_cost));
+            if let Some(pareto) = optimizer.prop.pareto.clone() {
+                self.update_pareto_frontier(&pareto, optimizer);
+            }
+            trace!(event = "task_finish", task = "optimize_inputs", expr_id = %self.expr_id);
+            return Ok(vec![]);
+        }
+
         if let Some(ContinueTask {
             next_group_idx,
             mut input_cost,
@@ -151,14 +233,13 @@ impl<T: RelNodeTyp> Task<T> for OptimizeInputsTask {
                 expr_id: self.expr_id,
                 group_id,
             };
-            if self.should_terminate(
-                cost.sum(
-                    &cost.compute_cost(&expr.typ, &expr.data, &input_cost, Some(context.clone())),
-                    &input_cost,
-                )
-                .0[0],
-                optimizer.ctx.upper_bound,
-            ) {
+            let cost_so_far = cost.sum(
+                &cost.compute_cost(&expr.typ, &expr.data, &input_cost, Some(context.clone())),
+                &input_cost,
+            );
+            if self.should_terminate(cost_so_far.0[0], optimizer) {
+                optimizer
+                    .update_expr_cost_status(self.expr_id, ExprCostStatus::Pruned(cost_so_far));
                 trace!(event = "task_finish", task = "optimize_inputs", expr_id = %self.expr_id);
                 return Ok(vec![]);
             }
@@ -171,19 +252,20 @@ impl<T: RelNodeTyp> Task<T> for OptimizeInputsTask {
                     if !winner.impossible {
                         input_cost[group_idx] = winner.cost.clone();
                         has_full_winner = true;
-                        if self.should_terminate(
-                            cost.sum(
-                                &cost.compute_cost(
-                                    &expr.typ,
-                                    &expr.data,
-                                    &input_cost,
-                                    Some(context.clone()),
-                                ),
+                        let cost_so_far = cost.sum(
+                            &cost.compute_cost(
+                                &expr.typ,
+                                &expr.data,
                                 &input_cost,
-                            )
-                            .0[0],
-                            optimizer.ctx.upper_bound,
-                        ) {
+                                Some(context.clone()),
+                            ),
+                            &input_cost,
+                        );
+                        if self.should_terminate(cost_so_far.0[0], optimizer) {
+                            optimizer.update_expr_cost_status(
+                                self.expr_id,
+                                ExprCostStatus::Pruned(cost_so_far),
+                            );
                             trace!(event = "task_finish", task = "optimize_inputs", expr_id = %self.expr_id);
                             return Ok(vec![]);
                         }
@@ -213,8 +295,13 @@ impl<T: RelNodeTyp> Task<T> for OptimizeInputsTask {
                                             impossible: true,
                                             ..Default::default()
                                         }),
+                                        pareto_frontier: group_info.pareto_frontier.clone(),
                                     },
                                 );
+                                optimizer.update_expr_cost_status(
+                                    self.expr_id,
+                                    ExprCostStatus::Impossible,
+                                );
                                 trace!(event = "task_finish", task = "optimize_inputs", expr_id = %self.expr_id);
                                 return Ok(vec![]);
                             }
@@ -226,8 +313,10 @@ impl<T: RelNodeTyp> Task<T> for OptimizeInputsTask {
                                     impossible: true,
                                     ..Default::default()
                                 }),
+                                pareto_frontier: group_info.pareto_frontier,
                             },
                         );
+                        optimizer.update_expr_cost_status(self.expr_id, ExprCostStatus::Impossible);
                         trace!(event = "task_finish", task = "optimize_inputs", expr_id = %self.expr_id);
                         return Ok(vec![]);
                     }
@@ -242,18 +331,15 @@ impl<T: RelNodeTyp> Task<T> for OptimizeInputsTask {
                     self.pruning,
                 )) as Box<dyn Task<T>>])
             } else {
-                self.update_winner(
-                    &cost.sum(
-                        &cost.compute_cost(
-                            &expr.typ,
-                            &expr.data,
-                            &input_cost,
-                            Some(context.clone()),
-                        ),
-                        &input_cost,
-                    ),
-                    optimizer,
+                let total_cost = cost.sum(
+                    &cost.compute_cost(&expr.typ, &expr.data, &input_cost, Some(context.clone())),
+                    &input_cost,
                 );
+                self.update_winner(&total_cost, optimizer);
+                optimizer.update_expr_cost_status(self.expr_id, ExprCostStatus::Costed(total_cost));
+                if let Some(pareto) = optimizer.prop.pareto.clone() {
+                    self.update_pareto_frontier(&pareto, optimizer);
+                }
                 trace!(event = "task_finish", task = "optimize_inputs", expr_id = %self.expr_id);
                 Ok(vec![])
             }
@@ -6,6 +6,28 @@ use crate::{
 #[derive(Default, Clone, Debug, PartialOrd, PartialEq)]
 pub struct Cost(pub Vec<f64>);
 
+impl Cost {
+    /// Returns whether this cost Pareto-dominates `other` on the given cost slots, i.e., it is no worse on all of
+    /// them and strictly better on at least one.
+    pub fn dominates(&self, other: &Cost, objectives: &[usize]) -> bool {
+        let mut strictly_better = false;
+        for &idx in objectives {
+            if self.0[idx] > other.0[idx] {
+                return false;
+            }
+            if self.0[idx] < other.0[idx] {
+                strictly_better = true;
+            }
+        }
+        strictly_better
+    }
+
+    /// Returns whether the two costs are identical on the given cost slots.
+    pub fn same_objectives(&self, other: &Cost, objectives: &[usize]) -> bool {
+        objectives.iter().all(|&idx| self.0[idx] == other.0[idx])
+    }
+}
+
 pub trait CostModel<T: RelNodeTyp>: 'static + Send + Sync {
     fn compute_cost(
         &self,
@@ -31,3 +53,25 @@ pub trait CostModel<T: RelNodeTyp>: 'static + Send + Sync {
 
     fn zero(&self) -> Cost;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Cost;
+
+    #[test]
+    fn dominates() {
+        let a = Cost(vec![3.0, 1.0, 2.0]);
+        let b = Cost(vec![4.0, 1.0, 3.0]);
+        let c = Cost(vec![2.0, 2.0, 0.0]);
+        assert!(a.dominates(&b, &[1, 2]));
+        assert!(!b.dominates(&a, &[1, 2]));
+        // no worse, but not strictly better on the objectives
+        assert!(!a.dominates(&b, &[1]));
+        assert!(a.same_objectives(&b, &[1]));
+        // better on one objective and worse on the other
+        assert!(!a.dominates(&c, &[1, 2]));
+        assert!(!c.dominates(&a, &[1, 2]));
+        // only the given slots are compared
+        assert!(c.dominates(&a, &[0, 2]));
+    }
+}
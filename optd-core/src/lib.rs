@@ -7,3 +7,5 @@ pub mod optimizer;
 pub mod property;
 pub mod rel_node;
 pub mod rules;
+#[cfg(test)]
+mod testing;
@@ -1,6 +1,8 @@
 //! The RelNode is the basic data structure of the optimizer. It is dynamically typed and is
 //! the internal representation of the plan nodes.
 
+mod parser;
+
 use std::{
     fmt::{Debug, Display},
     hash::Hash,
@@ -23,6 +25,13 @@ pub trait RelNodeTyp:
     fn extract_group(&self) -> Option<GroupId>;
 
     fn list_typ() -> Self;
+
+    /// Whether the node is part of a scalar expression (e.g., column references, constants and expression lists).
+    /// Unless scalar expression exploration is enabled, scalar expression subtrees are interned in the memo as a
+    /// whole, instead of having one group per node.
+    fn is_scalar(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -31,53 +40,285 @@ pub enum Value {
     Float(OrderedFloat<f64>),
     String(Arc<str>),
     Bool(bool),
+    /// A fixed-point decimal, the actual value is `value / 10^scale`.
+    Decimal {
+        value: i128,
+        precision: u8,
+        scale: i8,
+    },
+    /// Number of days since the UNIX epoch.
+    Date32(i32),
+    /// Number of `unit`s since the UNIX epoch, in the time zone `tz` if any.
+    Timestamp {
+        value: i64,
+        unit: TimeUnit,
+        tz: Option<Arc<str>>,
+    },
+    Interval {
+        months: i32,
+        days: i32,
+        nanos: i64,
+    },
+    Null,
+    List(Arc<[Value]>),
     Serialized(Arc<[u8]>),
 }
 
+/// The unit of a timestamp.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TimeUnit {
+    Second,
+    Millisecond,
+    Microsecond,
+    Nanosecond,
+}
+
+impl TimeUnit {
+    pub const ALL: [TimeUnit; 4] = [
+        Self::Second,
+        Self::Millisecond,
+        Self::Microsecond,
+        Self::Nanosecond,
+    ];
+
+    /// The number of units in a second.
+    pub fn per_second(&self) -> i64 {
+        match self {
+            Self::Second => 1,
+            Self::Millisecond => 1_000,
+            Self::Microsecond => 1_000_000,
+            Self::Nanosecond => 1_000_000_000,
+        }
+    }
+
+    /// The number of fractional digits of a second in this unit.
+    pub fn digits(&self) -> usize {
+        match self {
+            Self::Second => 0,
+            Self::Millisecond => 3,
+            Self::Microsecond => 6,
+            Self::Nanosecond => 9,
+        }
+    }
+}
+
+impl std::str::FromStr for TimeUnit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "Second" => Self::Second,
+            "Millisecond" => Self::Millisecond,
+            "Microsecond" => Self::Microsecond,
+            "Nanosecond" => Self::Nanosecond,
+            _ => anyhow::bail!("unknown TimeUnit `{}`", s),
+        })
+    }
+}
+
+/// Converts the number of days since the UNIX epoch to (year, month, day).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Converts (year, month, day) to the number of days since the UNIX epoch.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Int(x) => write!(f, "{x}"),
             Self::Float(x) => write!(f, "{x}"),
-            Self::String(x) => write!(f, "\"{x}\""),
+            Self::String(x) => write!(f, "\"{}\"", x.replace('\\', "\\\\").replace('"', "\\\"")),
             Self::Bool(x) => write!(f, "{x}"),
+            Self::Decimal {
+                value,
+                precision,
+                scale,
+            } => {
+                if *scale <= 0 {
+                    let zeros = "0".repeat(scale.unsigned_abs() as usize);
+                    write!(f, "{value}{zeros}")?;
+                } else {
+                    let scale = *scale as usize;
+                    let sign = if *value < 0 { "-" } else { "" };
+                    let digits = format!("{:0>width$}", value.unsigned_abs(), width = scale + 1);
+                    let (int_part, frac_part) = digits.split_at(digits.len() - scale);
+                    write!(f, "{sign}{int_part}.{frac_part}")?;
+                }
+                write!(f, "::decimal({precision},{scale})")
+            }
+            Self::Date32(x) => {
+                let (year, month, day) = civil_from_days(*x as i64);
+                write!(f, "date'{year:04}-{month:02}-{day:02}'")
+            }
+            Self::Timestamp { value, unit, tz } => {
+                // the unit is given by the number of fractional digits of the seconds
+                let per_second = unit.per_second();
+                let secs = value.div_euclid(per_second);
+                let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+                let secs = secs.rem_euclid(86_400);
+                write!(
+                    f,
+                    "timestamp'{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+                    secs / 3600,
+                    secs / 60 % 60,
+                    secs % 60,
+                )?;
+                if *unit != TimeUnit::Second {
+                    write!(
+                        f,
+                        ".{:0width$}",
+                        value.rem_euclid(per_second),
+                        width = unit.digits()
+                    )?;
+                }
+                if let Some(tz) = tz {
+                    write!(f, " {tz}")?;
+                }
+                write!(f, "'")
+            }
+            Self::Interval {
+                months,
+                days,
+                nanos,
+            } => write!(f, "interval'{months} months {days} days {nanos} nanos'"),
+            Self::Null => write!(f, "null"),
+            Self::List(x) => {
+                write!(f, "[")?;
+                for (idx, item) in x.iter().enumerate() {
+                    if idx != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                write!(f, "]")
+            }
             Self::Serialized(x) => write!(f, "<len:{}>", x.len()),
         }
     }
 }
 
 impl Value {
-    pub fn as_i64(&self) -> i64 {
+    pub fn as_i64(&self) -> Option<i64> {
         match self {
-            Value::Int(i) => *i,
-            _ => panic!("Value is not an i64"),
+            Value::Int(i) => Some(*i),
+            _ => None,
         }
     }
 
-    pub fn as_f64(&self) -> f64 {
+    pub fn as_f64(&self) -> Option<f64> {
         match self {
-            Value::Float(i) => **i,
-            _ => panic!("Value is not an f64"),
+            Value::Float(i) => Some(**i),
+            _ => None,
         }
     }
 
-    pub fn as_bool(&self) -> bool {
+    pub fn as_bool(&self) -> Option<bool> {
         match self {
-            Value::Bool(i) => *i,
-            _ => panic!("Value is not a bool"),
+            Value::Bool(i) => Some(*i),
+            _ => None,
         }
     }
 
-    pub fn as_str(&self) -> Arc<str> {
+    pub fn as_str(&self) -> Option<Arc<str>> {
         match self {
-            Value::String(i) => i.clone(),
-            _ => panic!("Value is not a string"),
+            Value::String(i) => Some(i.clone()),
+            _ => None,
         }
     }
+
+    /// Returns the (value, precision, scale) of a decimal.
+    pub fn as_decimal(&self) -> Option<(i128, u8, i8)> {
+        match self {
+            Value::Decimal {
+                value,
+                precision,
+                scale,
+            } => Some((*value, *precision, *scale)),
+            _ => None,
+        }
+    }
+
+    pub fn as_date32(&self) -> Option<i32> {
+        match self {
+            Value::Date32(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Returns the (value, unit, time zone) of a timestamp.
+    pub fn as_timestamp(&self) -> Option<(i64, TimeUnit, Option<Arc<str>>)> {
+        match self {
+            Value::Timestamp { value, unit, tz } => Some((*value, *unit, tz.clone())),
+            _ => None,
+        }
+    }
+
+    /// Returns the (months, days, nanos) of an interval.
+    pub fn as_interval(&self) -> Option<(i32, i32, i64)> {
+        match self {
+            Value::Interval {
+                months,
+                days,
+                nanos,
+            } => Some((*months, *days, *nanos)),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<Arc<[Value]>> {
+        match self {
+            Value::List(i) => Some(i.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+}
+
+/// Writes a value in the textual plan format, which is the `Display` format except that floats always have a decimal
+/// point or an exponent, so that they are not parsed back as integers.
+fn fmt_plan_value(value: &Value, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match value {
+        Value::Float(x) => write!(f, "{:?}", x.0),
+        Value::List(x) => {
+            write!(f, "[")?;
+            for (idx, item) in x.iter().enumerate() {
+                if idx != 0 {
+                    write!(f, ", ")?;
+                }
+                fmt_plan_value(item, f)?;
+            }
+            write!(f, "]")
+        }
+        _ => write!(f, "{value}"),
+    }
 }
 
 /// A RelNode is consisted of a plan node type and some children.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct RelNode<T: RelNodeTyp> {
     pub typ: T,
     pub children: Vec<RelNodeRef<T>>,
@@ -86,9 +327,13 @@ pub struct RelNode<T: RelNodeTyp> {
 
 impl<T: RelNodeTyp> std::fmt::Display for RelNode<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(group_id) = self.typ.extract_group() {
+            return write!(f, "{}", group_id);
+        }
         write!(f, "({}", self.typ)?;
         if let Some(ref data) = self.data {
-            write!(f, " {}", data)?;
+            write!(f, " ")?;
+            fmt_plan_value(data, f)?;
         }
         for child in &self.children {
             write!(f, " {}", child)?;
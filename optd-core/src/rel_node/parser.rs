@@ -0,0 +1,387 @@
+//! Parser for the textual plan format produced by the `Display` implementation of `RelNode`.
+//!
+//! A node is written as `(<typ> [<value>] <children>...)`, where the type is parsed with `FromStr` of the plan node
+//! type, e.g., `(Join(Inner) (Scan "t1") (Scan "t2") (Constant(Bool) true))`. A group placeholder is written as
+//! `!<group_id>`. Values are written as in `Value`'s `Display` implementation, except that floats always have a decimal
+//! point or an exponent.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Result};
+use ordered_float::OrderedFloat;
+
+use super::{days_from_civil, RelNode, RelNodeTyp, TimeUnit, Value};
+use crate::cascades::GroupId;
+
+struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.rest().chars().next()
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        match self.peek() {
+            Some(x) if x == c => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            Some(x) => bail!("expect `{}`, found `{}` at {}", c, x, self.pos),
+            None => bail!("expect `{}`, found end of input", c),
+        }
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Take the characters until `f` returns true, and keep the parentheses balanced.
+    fn take_until(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        self.skip_whitespace();
+        let start = self.pos;
+        let mut depth = 0;
+        for (idx, c) in self.rest().char_indices() {
+            match c {
+                '(' if idx != 0 => depth += 1,
+                ')' if depth > 0 => depth -= 1,
+                c if depth == 0 && f(c) => {
+                    self.pos = start + idx;
+                    return &self.input[start..self.pos];
+                }
+                _ => {}
+            }
+        }
+        self.pos = self.input.len();
+        &self.input[start..]
+    }
+
+    fn parse_node<T: RelNodeTyp + FromStr>(&mut self) -> Result<RelNode<T>>
+    where
+        T::Err: std::fmt::Display,
+    {
+        if self.eat("!") {
+            let group_id = self.take_until(|c| !c.is_ascii_digit());
+            let group_id = group_id
+                .parse()
+                .with_context(|| format!("invalid group id `{}`", group_id))?;
+            return Ok(RelNode::new_group(GroupId(group_id)));
+        }
+        self.expect('(')?;
+        let typ = self.take_until(|c| c.is_whitespace() || c == '(' || c == ')');
+        let typ = T::from_str(typ).map_err(|err| anyhow!("invalid type `{}`: {}", typ, err))?;
+        let mut data = None;
+        let mut children = vec![];
+        loop {
+            match self.peek() {
+                Some(')') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some('(') | Some('!') => children.push(self.parse_node::<T>()?.into()),
+                Some(_) if data.is_none() && children.is_empty() => {
+                    data = Some(self.parse_value()?)
+                }
+                Some(c) => bail!("unexpected `{}` at {}", c, self.pos),
+                None => bail!("unexpected end of input, expect `)`"),
+            }
+        }
+        Ok(RelNode {
+            typ,
+            children,
+            data,
+        })
+    }
+
+    fn parse_quoted(&mut self, quote: char) -> Result<String> {
+        self.expect(quote)?;
+        let mut result = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '\\' => {
+                    let (_, c) = chars.next().context("unexpected end of input")?;
+                    result.push(c);
+                }
+                c if c == quote => {
+                    self.pos += idx + 1;
+                    return Ok(result);
+                }
+                c => result.push(c),
+            }
+        }
+        bail!("unterminated string, expect `{}`", quote)
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some('"') => return Ok(Value::String(self.parse_quoted('"')?.into())),
+            Some('[') => {
+                self.pos += 1;
+                let mut items = vec![];
+                if !self.eat("]") {
+                    loop {
+                        items.push(self.parse_value()?);
+                        if self.eat("]") {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                return Ok(Value::List(items.into()));
+            }
+            _ => {}
+        }
+        if self.eat("date") {
+            let date = self.parse_quoted('\'')?;
+            return Ok(Value::Date32(parse_date(&date)? as i32));
+        }
+        if self.eat("timestamp") {
+            let timestamp = self.parse_quoted('\'')?;
+            return parse_timestamp(&timestamp);
+        }
+        if self.eat("interval") {
+            let interval = self.parse_quoted('\'')?;
+            return parse_interval(&interval);
+        }
+        let token = self.take_until(|c| c.is_whitespace() || c == ')' || c == ',' || c == ']');
+        match token {
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            "null" => return Ok(Value::Null),
+            _ => {}
+        }
+        if let Some((number, decimal)) = token.split_once("::decimal(") {
+            let (precision, scale) = decimal
+                .strip_suffix(')')
+                .and_then(|x| x.split_once(','))
+                .with_context(|| format!("invalid decimal `{}`", token))?;
+            let precision: u8 = precision.trim().parse()?;
+            let scale: i8 = scale.trim().parse()?;
+            let (int_part, frac_part) = number.split_once('.').unwrap_or((number, ""));
+            if scale > 0 && frac_part.len() != scale as usize {
+                bail!("invalid decimal `{}`: scale mismatch", token);
+            }
+            let digits = format!("{}{}", int_part, frac_part);
+            let mut value: i128 = digits
+                .parse()
+                .with_context(|| format!("invalid decimal `{}`", token))?;
+            if scale < 0 {
+                value /= 10i128.pow(scale.unsigned_abs() as u32);
+            }
+            return Ok(Value::Decimal {
+                value,
+                precision,
+                scale,
+            });
+        }
+        if let Ok(x) = token.parse::<i64>() {
+            return Ok(Value::Int(x));
+        }
+        if let Ok(x) = token.parse::<f64>() {
+            return Ok(Value::Float(OrderedFloat(x)));
+        }
+        bail!("invalid value `{}`", token)
+    }
+}
+
+/// Parses `YYYY-MM-DD` into the number of days since the UNIX epoch.
+fn parse_date(date: &str) -> Result<i64> {
+    let parts = date.split('-').collect::<Vec<_>>();
+    let [year, month, day] = parts[..] else {
+        bail!("invalid date `{}`", date)
+    };
+    Ok(days_from_civil(year.parse()?, month.parse()?, day.parse()?))
+}
+
+/// Parses `YYYY-MM-DD HH:MM:SS[.fff[fff[fff]]] [<time zone>]`, where the number of fractional digits gives the unit
+/// of the timestamp.
+fn parse_timestamp(timestamp: &str) -> Result<Value> {
+    let parts = timestamp.split(' ').collect::<Vec<_>>();
+    let (date, time, tz) = match parts[..] {
+        [date, time] => (date, time, None),
+        [date, time, tz] => (date, time, Some(tz.into())),
+        _ => bail!("invalid timestamp `{}`", timestamp),
+    };
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let Some(unit) = TimeUnit::ALL
+        .into_iter()
+        .find(|x| x.digits() == fraction.len())
+    else {
+        bail!("invalid timestamp `{}`: unknown unit", timestamp)
+    };
+    let parts = time.split(':').collect::<Vec<_>>();
+    let [hour, minute, second] = parts[..] else {
+        bail!("invalid timestamp `{}`", timestamp)
+    };
+    let secs = hour.parse::<i64>()? * 3600 + minute.parse::<i64>()? * 60 + second.parse::<i64>()?;
+    let fraction = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse::<i64>()?
+    };
+    let value = (parse_date(date)? * 86_400 + secs)
+        .checked_mul(unit.per_second())
+        .and_then(|x| x.checked_add(fraction))
+        .with_context(|| format!("invalid timestamp `{}`: out of range", timestamp))?;
+    Ok(Value::Timestamp { value, unit, tz })
+}
+
+/// Parses `<months> months <days> days <nanos> nanos`.
+fn parse_interval(interval: &str) -> Result<Value> {
+    let parts = interval.split_whitespace().collect::<Vec<_>>();
+    let [months, "months", days, "days", nanos, "nanos"] = parts[..] else {
+        bail!("invalid interval `{}`", interval)
+    };
+    Ok(Value::Interval {
+        months: months.parse()?,
+        days: days.parse()?,
+        nanos: nanos.parse()?,
+    })
+}
+
+impl<T: RelNodeTyp + FromStr> FromStr for RelNode<T>
+where
+    T::Err: std::fmt::Display,
+{
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser::new(s);
+        let node = parser.parse_node::<T>()?;
+        if let Some(c) = parser.peek() {
+            bail!("unexpected `{}` at {}", c, parser.pos);
+        }
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ordered_float::OrderedFloat;
+
+    use crate::{
+        rel_node::{RelNode, TimeUnit, Value},
+        testing::TestTyp,
+    };
+
+    #[test]
+    fn value_round_trip() {
+        let values = [
+            Value::Int(-3),
+            Value::Float(OrderedFloat(1.0)),
+            Value::Float(OrderedFloat(-0.25)),
+            Value::String(r#"a "quoted" \\ string"#.into()),
+            Value::Bool(false),
+            Value::Decimal {
+                value: 150,
+                precision: 15,
+                scale: 2,
+            },
+            Value::Decimal {
+                value: -5,
+                precision: 10,
+                scale: 3,
+            },
+            Value::Decimal {
+                value: 12,
+                precision: 5,
+                scale: -2,
+            },
+            Value::Date32(-1),
+            Value::Date32(10_000),
+            Value::Timestamp {
+                value: -1,
+                unit: TimeUnit::Nanosecond,
+                tz: None,
+            },
+            Value::Timestamp {
+                value: 1_700_000_000_123_456_789,
+                unit: TimeUnit::Nanosecond,
+                tz: None,
+            },
+            Value::Timestamp {
+                value: 1_700_000_000,
+                unit: TimeUnit::Second,
+                tz: Some("UTC".into()),
+            },
+            Value::Timestamp {
+                value: -1_500,
+                unit: TimeUnit::Millisecond,
+                tz: Some("+05:00".into()),
+            },
+            Value::Timestamp {
+                value: 1_700_000_000_000_001,
+                unit: TimeUnit::Microsecond,
+                tz: Some("America/New_York".into()),
+            },
+            Value::Interval {
+                months: 1,
+                days: -2,
+                nanos: 3,
+            },
+            Value::Null,
+            Value::List(
+                vec![
+                    Value::Int(1),
+                    Value::Null,
+                    Value::List(vec![].into()),
+                    Value::Float(OrderedFloat(2.0)),
+                ]
+                .into(),
+            ),
+        ];
+        for value in values {
+            let node = RelNode {
+                typ: TestTyp::Constant,
+                children: vec![],
+                data: Some(value),
+            };
+            let parsed: RelNode<TestTyp> = node.to_string().parse().unwrap();
+            assert_eq!(parsed, node, "{}", node);
+        }
+        // floats are displayed as usual, but always have a decimal point in the plan format
+        let node = RelNode {
+            typ: TestTyp::Constant,
+            children: vec![],
+            data: Some(Value::List(vec![Value::Float(OrderedFloat(1.0))].into())),
+        };
+        assert_eq!(node.data.as_ref().unwrap().to_string(), "[1]");
+        assert_eq!(node.to_string(), "(Constant [1.0])");
+        // a constant as the child of another node
+        let node = RelNode {
+            typ: TestTyp::Eq,
+            children: vec![Arc::new(RelNode {
+                typ: TestTyp::Constant,
+                children: vec![],
+                data: Some(Value::Date32(0)),
+            })],
+            data: None,
+        };
+        assert_eq!(node.to_string().parse::<RelNode<TestTyp>>().unwrap(), node);
+    }
+}
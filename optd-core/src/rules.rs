@@ -12,8 +12,21 @@ pub use ir::RuleMatcher;
 pub trait Rule<T: RelNodeTyp, O: Optimizer<T>>: 'static + Send + Sync {
     fn matcher(&self) -> &RuleMatcher<T>;
     fn apply(&self, optimizer: &O, input: HashMap<usize, RelNode<T>>) -> Vec<RelNode<T>>;
+    /// The name of the rule, which should be unique among all rules of an optimizer.
     fn name(&self) -> &'static str;
     fn is_impl_rule(&self) -> bool {
         false
     }
+    /// The category of the rule, used to group rules when listing them.
+    fn category(&self) -> &'static str {
+        if self.is_impl_rule() {
+            "implementation"
+        } else {
+            "transformation"
+        }
+    }
+    /// A short description of what the rule does.
+    fn description(&self) -> &'static str {
+        ""
+    }
 }
@@ -0,0 +1,386 @@
+//! A minimal plan representation, cost model and rules for testing the optimizer without the Datafusion
+//! representation.
+
+use std::{collections::HashMap, fmt::Display, str::FromStr, sync::Arc};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    cascades::{CascadesOptimizer, GroupId, OptimizerProperties, RelNodeContext},
+    cost::{Cost, CostModel},
+    optimizer::Optimizer,
+    rel_node::{RelNode, RelNodeRef, RelNodeTyp, Value},
+    rules::{Rule, RuleMatcher},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum TestTyp {
+    Scan,
+    Filter,
+    Join,
+    Projection,
+    PhysicalScan,
+    PhysicalFilter,
+    PhysicalJoin,
+    PhysicalProjection,
+    Column,
+    Constant,
+    Eq,
+    List,
+    Placeholder(GroupId),
+}
+
+impl Display for TestTyp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Placeholder(group_id) => write!(f, "{}", group_id),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+impl FromStr for TestTyp {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "Scan" => Self::Scan,
+            "Filter" => Self::Filter,
+            "Join" => Self::Join,
+            "Projection" => Self::Projection,
+            "PhysicalScan" => Self::PhysicalScan,
+            "PhysicalFilter" => Self::PhysicalFilter,
+            "PhysicalJoin" => Self::PhysicalJoin,
+            "PhysicalProjection" => Self::PhysicalProjection,
+            "Column" => Self::Column,
+            "Constant" => Self::Constant,
+            "Eq" => Self::Eq,
+            "List" => Self::List,
+            _ => bail!("unknown type {}", s),
+        })
+    }
+}
+
+impl RelNodeTyp for TestTyp {
+    fn is_logical(&self) -> bool {
+        matches!(
+            self,
+            Self::Scan | Self::Filter | Self::Join | Self::Projection
+        )
+    }
+
+    fn group_typ(group_id: GroupId) -> Self {
+        Self::Placeholder(group_id)
+    }
+
+    fn extract_group(&self) -> Option<GroupId> {
+        if let Self::Placeholder(group_id) = self {
+            Some(*group_id)
+        } else {
+            None
+        }
+    }
+
+    fn list_typ() -> Self {
+        Self::List
+    }
+
+    fn is_scalar(&self) -> bool {
+        matches!(self, Self::Column | Self::Constant | Self::Eq | Self::List)
+    }
+}
+
+pub(crate) fn parse(plan: &str) -> RelNodeRef<TestTyp> {
+    Arc::new(plan.parse().unwrap())
+}
+
+/// Scans cost their table cost, joins cost the weighted cost of their left input (i.e., the join order matters), and
+/// all other nodes cost 1, in addition to the costs of their children. The cost has two slots: the weighted cost and
+/// the number of scans.
+pub(crate) struct TestCostModel {
+    pub(crate) table_cost: HashMap<String, f64>,
+}
+
+impl CostModel<TestTyp> for TestCostModel {
+    fn compute_cost(
+        &self,
+        node: &TestTyp,
+        data: &Option<Value>,
+        children: &[Cost],
+        _context: Option<RelNodeContext>,
+    ) -> Cost {
+        match node {
+            TestTyp::PhysicalJoin => Cost(vec![children[0].0[0], 0.0]),
+            TestTyp::PhysicalScan => {
+                let table = data.as_ref().unwrap().as_str().unwrap();
+                Cost(vec![
+                    self.table_cost.get(table.as_ref()).copied().unwrap_or(10.0),
+                    1.0,
+                ])
+            }
+            _ => Cost(vec![1.0, 0.0]),
+        }
+    }
+
+    fn compute_plan_node_cost(&self, node: &RelNode<TestTyp>) -> Cost {
+        let children = node
+            .children
+            .iter()
+            .map(|child| self.compute_plan_node_cost(child))
+            .collect::<Vec<_>>();
+        let cost = self.compute_cost(&node.typ, &node.data, &children, None);
+        self.sum(&cost, &children)
+    }
+
+    fn explain(&self, cost: &Cost) -> String {
+        format!("{:?}", cost.0)
+    }
+
+    fn accumulate(&self, total_cost: &mut Cost, cost: &Cost) {
+        for (total, cost) in total_cost.0.iter_mut().zip(cost.0.iter()) {
+            *total += cost;
+        }
+    }
+
+    fn zero(&self) -> Cost {
+        Cost(vec![0.0, 0.0])
+    }
+}
+
+/// Converts a logical node into the physical node of type `to`, with the same children and data.
+pub(crate) struct ConvertRule {
+    matcher: RuleMatcher<TestTyp>,
+    to: TestTyp,
+    name: &'static str,
+}
+
+impl ConvertRule {
+    pub(crate) fn all_conversions<O: Optimizer<TestTyp>>() -> Vec<Arc<dyn Rule<TestTyp, O>>> {
+        [
+            (TestTyp::Scan, TestTyp::PhysicalScan, "convert_scan"),
+            (TestTyp::Filter, TestTyp::PhysicalFilter, "convert_filter"),
+            (TestTyp::Join, TestTyp::PhysicalJoin, "convert_join"),
+            (
+                TestTyp::Projection,
+                TestTyp::PhysicalProjection,
+                "convert_projection",
+            ),
+        ]
+        .into_iter()
+        .map(|(from, to, name)| {
+            Arc::new(Self {
+                matcher: RuleMatcher::MatchAndPickNode {
+                    typ: from,
+                    pick_to: 0,
+                    children: vec![RuleMatcher::IgnoreMany],
+                },
+                to,
+                name,
+            }) as Arc<dyn Rule<TestTyp, O>>
+        })
+        .collect()
+    }
+}
+
+impl<O: Optimizer<TestTyp>> Rule<TestTyp, O> for ConvertRule {
+    fn matcher(&self) -> &RuleMatcher<TestTyp> {
+        &self.matcher
+    }
+
+    fn apply(
+        &self,
+        _optimizer: &O,
+        mut input: HashMap<usize, RelNode<TestTyp>>,
+    ) -> Vec<RelNode<TestTyp>> {
+        let mut node = input.remove(&0).unwrap();
+        node.typ = self.to.clone();
+        vec![node]
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn is_impl_rule(&self) -> bool {
+        true
+    }
+}
+
+type ApplyFn = fn(HashMap<usize, RelNode<TestTyp>>) -> Vec<RelNode<TestTyp>>;
+
+/// A logical rule built from a matcher and a function, e.g., `(Join a b cond) -> (Join b a cond)`.
+struct FnRule {
+    matcher: RuleMatcher<TestTyp>,
+    apply: ApplyFn,
+    name: &'static str,
+}
+
+pub(crate) fn fn_rule<O: Optimizer<TestTyp>>(
+    matcher: RuleMatcher<TestTyp>,
+    apply: ApplyFn,
+    name: &'static str,
+) -> Arc<dyn Rule<TestTyp, O>> {
+    Arc::new(FnRule {
+        matcher,
+        apply,
+        name,
+    })
+}
+
+impl<O: Optimizer<TestTyp>> Rule<TestTyp, O> for FnRule {
+    fn matcher(&self) -> &RuleMatcher<TestTyp> {
+        &self.matcher
+    }
+
+    fn apply(
+        &self,
+        _optimizer: &O,
+        input: HashMap<usize, RelNode<TestTyp>>,
+    ) -> Vec<RelNode<TestTyp>> {
+        (self.apply)(input)
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// `(Join a b cond) -> (Join b a cond)`
+pub(crate) fn join_commute_rule<O: Optimizer<TestTyp>>() -> Arc<dyn Rule<TestTyp, O>> {
+    fn_rule(
+        RuleMatcher::MatchNode {
+            typ: TestTyp::Join,
+            children: vec![
+                RuleMatcher::PickOne {
+                    pick_to: 0,
+                    expand: false,
+                },
+                RuleMatcher::PickOne {
+                    pick_to: 1,
+                    expand: false,
+                },
+                RuleMatcher::PickOne {
+                    pick_to: 2,
+                    expand: false,
+                },
+            ],
+        },
+        |mut input| {
+            let left = input.remove(&0).unwrap();
+            let right = input.remove(&1).unwrap();
+            let cond = input.remove(&2).unwrap();
+            vec![RelNode {
+                typ: TestTyp::Join,
+                children: vec![right.into(), left.into(), cond.into()],
+                data: None,
+            }]
+        },
+        "join_commute",
+    )
+}
+
+/// `(Join (Join a b cond1) c cond2) -> (Join a (Join b c cond2) cond1)`
+pub(crate) fn join_assoc_rule<O: Optimizer<TestTyp>>() -> Arc<dyn Rule<TestTyp, O>> {
+    fn_rule(
+        RuleMatcher::MatchNode {
+            typ: TestTyp::Join,
+            children: vec![
+                RuleMatcher::MatchNode {
+                    typ: TestTyp::Join,
+                    children: vec![
+                        RuleMatcher::PickOne {
+                            pick_to: 0,
+                            expand: false,
+                        },
+                        RuleMatcher::PickOne {
+                            pick_to: 1,
+                            expand: false,
+                        },
+                        RuleMatcher::PickOne {
+                            pick_to: 3,
+                            expand: false,
+                        },
+                    ],
+                },
+                RuleMatcher::PickOne {
+                    pick_to: 2,
+                    expand: false,
+                },
+                RuleMatcher::PickOne {
+                    pick_to: 4,
+                    expand: false,
+                },
+            ],
+        },
+        |mut input| {
+            let a = input.remove(&0).unwrap();
+            let b = input.remove(&1).unwrap();
+            let c = input.remove(&2).unwrap();
+            let cond1 = input.remove(&3).unwrap();
+            let cond2 = input.remove(&4).unwrap();
+            vec![RelNode {
+                typ: TestTyp::Join,
+                children: vec![
+                    a.into(),
+                    RelNode {
+                        typ: TestTyp::Join,
+                        children: vec![b.into(), c.into(), cond2.into()],
+                        data: None,
+                    }
+                    .into(),
+                    cond1.into(),
+                ],
+                data: None,
+            }]
+        },
+        "join_assoc",
+    )
+}
+
+/// `(Projection child (List)) -> child`, i.e., a projection without expressions is the identity in tests.
+pub(crate) fn eliminate_projection_rule<O: Optimizer<TestTyp>>() -> Arc<dyn Rule<TestTyp, O>> {
+    fn_rule(
+        RuleMatcher::MatchNode {
+            typ: TestTyp::Projection,
+            children: vec![
+                RuleMatcher::PickOne {
+                    pick_to: 0,
+                    expand: false,
+                },
+                RuleMatcher::PickOne {
+                    pick_to: 1,
+                    expand: true,
+                },
+            ],
+        },
+        |mut input| {
+            let exprs = input.remove(&1).unwrap();
+            if !exprs.children.is_empty() {
+                return vec![];
+            }
+            vec![input.remove(&0).unwrap()]
+        },
+        "eliminate_projection",
+    )
+}
+
+/// Create an optimizer with the conversion rules and `rules`, where the scans of `tables` cost the given costs.
+pub(crate) fn new_optimizer(
+    rules: Vec<Arc<dyn Rule<TestTyp, CascadesOptimizer<TestTyp>>>>,
+    tables: &[(&str, f64)],
+    prop: OptimizerProperties,
+) -> CascadesOptimizer<TestTyp> {
+    let mut all_rules = ConvertRule::all_conversions();
+    all_rules.extend(rules);
+    CascadesOptimizer::new_with_prop(
+        all_rules,
+        Box::new(TestCostModel {
+            table_cost: tables
+                .iter()
+                .map(|(table, cost)| (table.to_string(), *cost))
+                .collect(),
+        }),
+        vec![],
+        prop,
+    )
+}
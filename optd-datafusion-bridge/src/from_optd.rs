@@ -4,8 +4,11 @@ use anyhow::{bail, Context, Result};
 use async_recursion::async_recursion;
 use datafusion::{
     arrow::{
+        self,
+        array::{new_empty_array, ListArray},
+        buffer::OffsetBuffer,
         compute::kernels::filter,
-        datatypes::{Schema, SchemaRef},
+        datatypes::{DataType, IntervalDayTimeType, IntervalMonthDayNanoType, Schema, SchemaRef},
     },
     datasource::source_as_provider,
     logical_expr::Operator,
@@ -13,36 +16,209 @@ use datafusion::{
     physical_plan::{
         self,
         aggregates::AggregateMode,
+        coalesce_partitions::CoalescePartitionsExec,
+        empty::EmptyExec,
         explain::ExplainExec,
         expressions::create_aggregate_expr,
         joins::{
             utils::{ColumnIndex, JoinFilter},
             PartitionMode,
         },
+        limit::GlobalLimitExec,
         projection::ProjectionExec,
+        union::UnionExec,
+        values::ValuesExec,
         AggregateExpr, ExecutionPlan, PhysicalExpr,
     },
     scalar::ScalarValue,
 };
+use optd_core::rel_node::TimeUnit;
 use optd_datafusion_repr::{
     plan_nodes::{
         BinOpExpr, BinOpType, ColumnRefExpr, ConstantExpr, ConstantType, Expr, FuncExpr, FuncType,
-        JoinType, LogOpExpr, LogOpType, OptRelNode, OptRelNodeRef, OptRelNodeTyp, PhysicalAgg,
-        PhysicalFilter, PhysicalHashJoin, PhysicalNestedLoopJoin, PhysicalProjection, PhysicalScan,
-        PhysicalSort, PlanNode, SortOrderExpr, SortOrderType,
+        IntervalKind, JoinType, LogOpExpr, LogOpType, OptRelNode, OptRelNodeRef, OptRelNodeTyp,
+        PhysicalAgg, PhysicalEmptyRelation, PhysicalFilter, PhysicalHashJoin, PhysicalLimit,
+        PhysicalNestedLoopJoin, PhysicalProjection, PhysicalScan, PhysicalSort,
+        PhysicalSortMergeJoin, PhysicalTopN, PhysicalUnion, PhysicalValues, PhysicalWindow,
+        PlanNode, SortOrderExpr, SortOrderType, UnionType,
     },
+    properties::schema::Field,
     PhysicalCollector, Value,
 };
 
 use crate::{physical_collector::CollectorExec, OptdPlanContext};
 
+fn from_optd_timestamp(value: Option<i64>, unit: TimeUnit, tz: Option<Arc<str>>) -> ScalarValue {
+    match unit {
+        TimeUnit::Second => ScalarValue::TimestampSecond(value, tz),
+        TimeUnit::Millisecond => ScalarValue::TimestampMillisecond(value, tz),
+        TimeUnit::Microsecond => ScalarValue::TimestampMicrosecond(value, tz),
+        TimeUnit::Nanosecond => ScalarValue::TimestampNanosecond(value, tz),
+    }
+}
+
+/// An interval of the given kind, which fails if the interval has fields that the kind cannot represent.
+fn from_optd_interval(
+    months: i32,
+    days: i32,
+    nanos: i64,
+    kind: IntervalKind,
+) -> Result<ScalarValue> {
+    let value = match kind {
+        IntervalKind::YearMonth => {
+            if days != 0 || nanos != 0 {
+                bail!("interval with days or nanoseconds typed as {:?}", kind)
+            }
+            ScalarValue::IntervalYearMonth(Some(months))
+        }
+        IntervalKind::DayTime => {
+            let millis = i32::try_from(nanos / 1_000_000).ok();
+            let Some(millis) = millis.filter(|_| months == 0 && nanos % 1_000_000 == 0) else {
+                bail!("interval with months or nanoseconds typed as {:?}", kind)
+            };
+            ScalarValue::IntervalDayTime(Some(IntervalDayTimeType::make_value(days, millis)))
+        }
+        IntervalKind::MonthDayNano => ScalarValue::IntervalMonthDayNano(Some(
+            IntervalMonthDayNanoType::make_value(months, days, nanos),
+        )),
+    };
+    Ok(value)
+}
+
+pub(crate) fn from_optd_value(value: &Value, typ: ConstantType) -> Result<ScalarValue> {
+    let value = match (value, typ) {
+        (Value::Null, ConstantType::Bool) => ScalarValue::Boolean(None),
+        (Value::Null, ConstantType::Int) => ScalarValue::Int64(None),
+        (Value::Null, ConstantType::Float) => ScalarValue::Float64(None),
+        (Value::Null, ConstantType::Decimal(precision, scale)) => {
+            ScalarValue::Decimal128(None, precision, scale)
+        }
+        (Value::Null, ConstantType::Date) => ScalarValue::Date32(None),
+        (Value::Null, ConstantType::Timestamp(unit, tz)) => {
+            from_optd_timestamp(None, unit, tz.map(|x| x.as_str().into()))
+        }
+        (Value::Null, ConstantType::Interval(IntervalKind::YearMonth)) => {
+            ScalarValue::IntervalYearMonth(None)
+        }
+        (Value::Null, ConstantType::Interval(IntervalKind::DayTime)) => {
+            ScalarValue::IntervalDayTime(None)
+        }
+        (Value::Null, ConstantType::Interval(IntervalKind::MonthDayNano)) => {
+            ScalarValue::IntervalMonthDayNano(None)
+        }
+        (Value::Null, ConstantType::Utf8String) => ScalarValue::Utf8(None),
+        (Value::Null, ConstantType::Any) => ScalarValue::Null,
+        (Value::Bool(x), _) => ScalarValue::Boolean(Some(*x)),
+        (Value::Int(x), _) => ScalarValue::Int64(Some(*x)),
+        (Value::Float(x), _) => ScalarValue::Float64(Some(**x)),
+        (
+            Value::Decimal {
+                value,
+                precision,
+                scale,
+            },
+            _,
+        ) => ScalarValue::Decimal128(Some(*value), *precision, *scale),
+        (Value::Date32(x), _) => ScalarValue::Date32(Some(*x)),
+        (Value::Timestamp { value, unit, tz }, _) => {
+            from_optd_timestamp(Some(*value), *unit, tz.clone())
+        }
+        (
+            Value::Interval {
+                months,
+                days,
+                nanos,
+            },
+            typ,
+        ) => {
+            let kind = match typ {
+                ConstantType::Interval(kind) => kind,
+                _ => IntervalKind::MonthDayNano,
+            };
+            from_optd_interval(*months, *days, *nanos, kind)?
+        }
+        (Value::String(x), _) => ScalarValue::Utf8(Some(x.to_string())),
+        (Value::List(items), _) => from_optd_list(items)?,
+        (value, typ) => bail!("unsupported constant {} of type {:?}", value, typ),
+    };
+    Ok(value)
+}
+
+/// A list value, whose items are typed as the first item that is not null.
+fn from_optd_list(items: &[Value]) -> Result<ScalarValue> {
+    let items = items
+        .iter()
+        .map(|item| from_optd_value(item, ConstantType::Any))
+        .collect::<Result<Vec<_>>>()?;
+    let data_type = items
+        .iter()
+        .find(|item| !item.is_null())
+        .map(|item| item.get_datatype())
+        .unwrap_or(DataType::Null);
+    let values = if items.is_empty() {
+        new_empty_array(&data_type)
+    } else {
+        let items = items
+            .into_iter()
+            .map(|item| {
+                if item.is_null() {
+                    ScalarValue::try_from(&data_type)
+                } else {
+                    Ok(item)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        ScalarValue::iter_to_array(items)?
+    };
+    let field = Arc::new(arrow::datatypes::Field::new("item", data_type, true));
+    let list = ListArray::new(
+        field,
+        OffsetBuffer::from_lengths([values.len()]),
+        values,
+        None,
+    );
+    Ok(ScalarValue::try_from_array(&list, 0)?)
+}
+
+/// The arrow schema of the given output fields, typed as the values of `from_optd_value`.
+fn from_optd_schema(fields: &[Field]) -> Result<SchemaRef> {
+    let fields = fields
+        .iter()
+        .map(|field| {
+            Ok(arrow::datatypes::Field::new(
+                &field.name,
+                from_optd_value(&Value::Null, field.typ)?.get_datatype(),
+                field.nullable,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Arc::new(Schema::new(fields)))
+}
+
+/// Cross joins are executed as inner joins with a `true` condition.
+fn from_optd_join_type(join_type: JoinType) -> datafusion::logical_expr::JoinType {
+    use datafusion::logical_expr::JoinType as DFJoinType;
+    match join_type {
+        JoinType::Inner | JoinType::Cross => DFJoinType::Inner,
+        JoinType::LeftOuter => DFJoinType::Left,
+        JoinType::RightOuter => DFJoinType::Right,
+        JoinType::FullOuter => DFJoinType::Full,
+        JoinType::LeftSemi => DFJoinType::LeftSemi,
+        JoinType::RightSemi => DFJoinType::RightSemi,
+        JoinType::LeftAnti => DFJoinType::LeftAnti,
+        JoinType::RightAnti => DFJoinType::RightAnti,
+    }
+}
+
 impl OptdPlanContext<'_> {
     #[async_recursion]
     async fn from_optd_table_scan(
         &mut self,
         node: PhysicalScan,
     ) -> Result<Arc<dyn ExecutionPlan + 'static>> {
-        let source = self.tables.get(node.table().as_ref()).unwrap();
+        let Some(source) = self.tables.get(node.table().as_ref()) else {
+            bail!("table {} not found", node.table())
+        };
         let provider = source_as_provider(source)?;
         let plan = provider.scan(self.session_state, None, &[], None).await?;
         Ok(plan)
@@ -75,9 +251,9 @@ impl OptdPlanContext<'_> {
         context: &SchemaRef,
     ) -> Result<Arc<dyn AggregateExpr>> {
         let expr = FuncExpr::from_rel_node(expr.into_rel_node()).unwrap();
-        let typ = expr.func();
-        let FuncType::Agg(func) = typ else {
-            unreachable!()
+        let func = match expr.func() {
+            FuncType::Agg(func) => func,
+            func => bail!("{} is not an aggregate function", func),
         };
         let args = expr
             .children()
@@ -106,18 +282,7 @@ impl OptdPlanContext<'_> {
             }
             OptRelNodeTyp::Constant(typ) => {
                 let expr = ConstantExpr::from_rel_node(expr.into_rel_node()).unwrap();
-                let value = expr.value();
-                let value = match typ {
-                    ConstantType::Bool => ScalarValue::Boolean(Some(value.as_bool())),
-                    ConstantType::Int => ScalarValue::Int64(Some(value.as_i64())),
-                    ConstantType::Decimal => {
-                        ScalarValue::Decimal128(Some(value.as_f64() as i128), 20, 0)
-                        // TODO(chi): no hard code decimal
-                    }
-                    ConstantType::Date => ScalarValue::Date32(Some(value.as_i64() as i32)),
-                    ConstantType::Utf8String => ScalarValue::Utf8(Some(value.as_str().to_string())),
-                    ConstantType::Any => unimplemented!(),
-                };
+                let value = from_optd_value(&expr.value(), typ)?;
                 Ok(Arc::new(
                     datafusion::physical_plan::expressions::Literal::new(value),
                 ))
@@ -150,14 +315,17 @@ impl OptdPlanContext<'_> {
                             Some(else_expr),
                         )?)
                     }
-                    _ => unreachable!(),
+                    func => bail!("{} cannot be evaluated as a scalar expression", func),
                 }
             }
-            OptRelNodeTyp::Sort => unreachable!(),
+            OptRelNodeTyp::SortOrder(_) => bail!("a sort order is not a scalar expression"),
             OptRelNodeTyp::LogOp(typ) => {
                 let expr = LogOpExpr::from_rel_node(expr.into_rel_node()).unwrap();
                 let mut children = expr.children().to_vec().into_iter();
-                let first_expr = self.from_optd_expr(children.next().unwrap(), context)?;
+                let Some(first_expr) = children.next() else {
+                    bail!("{} without operands", typ)
+                };
+                let first_expr = self.from_optd_expr(first_expr, context)?;
                 let op = match typ {
                     LogOpType::And => datafusion::logical_expr::Operator::And,
                     LogOpType::Or => datafusion::logical_expr::Operator::Or,
@@ -177,15 +345,19 @@ impl OptdPlanContext<'_> {
                 let right = self.from_optd_expr(expr.right_child(), context)?;
                 let op = match op {
                     BinOpType::Eq => Operator::Eq,
+                    BinOpType::IsNotDistinctFrom => Operator::IsNotDistinctFrom,
                     BinOpType::Neq => Operator::NotEq,
+                    BinOpType::Lt => Operator::Lt,
+                    BinOpType::Gt => Operator::Gt,
                     BinOpType::Leq => Operator::LtEq,
                     BinOpType::Geq => Operator::GtEq,
                     BinOpType::And => Operator::And,
+                    BinOpType::Or => Operator::Or,
                     BinOpType::Add => Operator::Plus,
                     BinOpType::Sub => Operator::Minus,
                     BinOpType::Mul => Operator::Multiply,
                     BinOpType::Div => Operator::Divide,
-                    op => unimplemented!("{}", op),
+                    op => bail!("unsupported binary operator {}", op),
                 };
                 Ok(
                     Arc::new(datafusion::physical_plan::expressions::BinaryExpr::new(
@@ -193,7 +365,13 @@ impl OptdPlanContext<'_> {
                     )) as Arc<dyn PhysicalExpr>,
                 )
             }
-            _ => unimplemented!("{}", expr.into_rel_node()),
+            OptRelNodeTyp::ExternColumnRef => {
+                bail!(
+                    "outer reference {} is left after decorrelation",
+                    expr.into_rel_node()
+                )
+            }
+            _ => bail!("unsupported expression {}", expr.into_rel_node()),
         }
     }
 
@@ -254,14 +432,56 @@ impl OptdPlanContext<'_> {
                 )
             })
             .collect::<Result<Vec<_>>>()?;
+        // the input may already be in this order, e.g., if it is a window partitioned and ordered by the same keys
+        Ok(Self::sort_if_needed(input_exec, physical_exprs))
+    }
+
+    #[async_recursion]
+    async fn from_optd_limit(
+        &mut self,
+        node: PhysicalLimit,
+    ) -> Result<Arc<dyn ExecutionPlan + 'static>> {
+        let input_exec = self.from_optd_plan_node(node.child()).await?;
+        let input_exec = if input_exec.output_partitioning().partition_count() == 1 {
+            input_exec
+        } else {
+            Arc::new(CoalescePartitionsExec::new(input_exec)) as Arc<dyn ExecutionPlan>
+        };
         Ok(
-            Arc::new(datafusion::physical_plan::sorts::sort::SortExec::new(
-                physical_exprs,
-                input_exec,
-            )) as Arc<dyn ExecutionPlan + 'static>,
+            Arc::new(GlobalLimitExec::new(input_exec, node.skip(), node.fetch()))
+                as Arc<dyn ExecutionPlan + 'static>,
         )
     }
 
+    #[async_recursion]
+    async fn from_optd_top_n(
+        &mut self,
+        node: PhysicalTopN,
+    ) -> Result<Arc<dyn ExecutionPlan + 'static>> {
+        let input_exec = self.from_optd_plan_node(node.child()).await?;
+        let physical_exprs = node
+            .exprs()
+            .to_vec()
+            .into_iter()
+            .map(|expr| {
+                self.from_optd_sort_order_expr(
+                    SortOrderExpr::from_rel_node(expr.into_rel_node()).unwrap(),
+                    &input_exec.schema(),
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let (skip, fetch) = (node.skip(), node.fetch());
+        let sort_exec = Arc::new(
+            datafusion::physical_plan::sorts::sort::SortExec::new(physical_exprs, input_exec)
+                .with_fetch(Some(skip + fetch)),
+        ) as Arc<dyn ExecutionPlan + 'static>;
+        if skip == 0 {
+            return Ok(sort_exec);
+        }
+        Ok(Arc::new(GlobalLimitExec::new(sort_exec, skip, Some(fetch)))
+            as Arc<dyn ExecutionPlan + 'static>)
+    }
+
     #[async_recursion]
     async fn from_optd_agg(
         &mut self,
@@ -302,54 +522,272 @@ impl OptdPlanContext<'_> {
     }
 
     #[async_recursion]
-    async fn from_optd_nested_loop_join(
+    async fn from_optd_window(
         &mut self,
-        node: PhysicalNestedLoopJoin,
+        node: PhysicalWindow,
     ) -> Result<Arc<dyn ExecutionPlan + 'static>> {
-        let left_exec = self.from_optd_plan_node(node.left()).await?;
-        let right_exec = self.from_optd_plan_node(node.right()).await?;
+        use datafusion::logical_expr::{window_function::WindowFunction, WindowFrame};
+        let input_exec = self.from_optd_plan_node(node.child()).await?;
+        let schema = input_exec.schema();
+        let partition_keys = node
+            .partition_by()
+            .to_vec()
+            .into_iter()
+            .map(|expr| self.from_optd_expr(expr, &schema))
+            .collect::<Result<Vec<_>>>()?;
+        let order_by = node
+            .order_by()
+            .to_vec()
+            .into_iter()
+            .map(|expr| {
+                self.from_optd_sort_order_expr(
+                    SortOrderExpr::from_rel_node(expr.into_rel_node()).unwrap(),
+                    &schema,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let window_frame = Arc::new(WindowFrame::new(!order_by.is_empty()));
+        let window_exprs = node
+            .exprs()
+            .to_vec()
+            .into_iter()
+            .map(|expr| {
+                let expr = FuncExpr::from_rel_node(expr.into_rel_node()).unwrap();
+                let func = match expr.func() {
+                    FuncType::Agg(func) => WindowFunction::AggregateFunction(func),
+                    FuncType::Window(func) => WindowFunction::BuiltInWindowFunction(func),
+                    func => bail!("{} is not a window function", func),
+                };
+                let args = expr
+                    .children()
+                    .to_vec()
+                    .into_iter()
+                    .map(|expr| self.from_optd_expr(expr, &schema))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(physical_plan::windows::create_window_expr(
+                    &func,
+                    "<window_func>".to_string(),
+                    &args,
+                    &partition_keys,
+                    &order_by,
+                    window_frame.clone(),
+                    &schema,
+                )?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // the partitions are evaluated one after another, so the input is sorted on the partition keys followed by the
+        // order of the window, unless it is already sorted on them
+        let mut sort_exprs = partition_keys
+            .iter()
+            .map(|expr| physical_expr::PhysicalSortExpr {
+                expr: expr.clone(),
+                options: datafusion::arrow::compute::SortOptions::default(),
+            })
+            .collect::<Vec<_>>();
+        sort_exprs.extend(order_by);
+        let input_exec = if !sort_exprs.is_empty() {
+            Self::sort_if_needed(input_exec, sort_exprs)
+        } else if input_exec.output_partitioning().partition_count() == 1 {
+            input_exec
+        } else {
+            Arc::new(CoalescePartitionsExec::new(input_exec)) as Arc<dyn ExecutionPlan>
+        };
+        Ok(Arc::new(physical_plan::windows::WindowAggExec::try_new(
+            window_exprs,
+            input_exec,
+            partition_keys,
+        )?) as Arc<dyn ExecutionPlan + 'static>)
+    }
+
+    fn from_optd_values(
+        &mut self,
+        node: PhysicalValues,
+    ) -> Result<Arc<dyn ExecutionPlan + 'static>> {
+        let fields = node.fields();
+        if fields.is_empty() && node.row_cnt() == 1 {
+            return Ok(Arc::new(EmptyExec::new(true, Arc::new(Schema::empty())))
+                as Arc<dyn ExecutionPlan + 'static>);
+        }
+        let schema = from_optd_schema(&fields)?;
+        let empty_schema = Arc::new(Schema::empty());
+        let values = node.values().to_vec();
+        let rows = values
+            .chunks(fields.len())
+            .map(|row| {
+                row.iter()
+                    .zip(schema.fields().iter())
+                    .map(|(expr, field)| {
+                        let expr = self.from_optd_expr(expr.clone(), &empty_schema)?;
+                        // the constants are typed by their values, so cast them to the type of their column
+                        Ok(physical_expr::expressions::cast(
+                            expr,
+                            &empty_schema,
+                            field.data_type().clone(),
+                        )?)
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(ValuesExec::try_new(schema, rows)?) as Arc<dyn ExecutionPlan + 'static>)
+    }
+
+    fn from_optd_empty_relation(
+        &mut self,
+        node: PhysicalEmptyRelation,
+    ) -> Result<Arc<dyn ExecutionPlan + 'static>> {
+        let schema = from_optd_schema(&node.fields())?;
+        Ok(Arc::new(EmptyExec::new(false, schema)) as Arc<dyn ExecutionPlan + 'static>)
+    }
+
+    #[async_recursion]
+    async fn from_optd_union(
+        &mut self,
+        node: PhysicalUnion,
+    ) -> Result<Arc<dyn ExecutionPlan + 'static>> {
+        let left_exec = self.from_optd_plan_node(node.left_child()).await?;
+        let right_exec = self.from_optd_plan_node(node.right_child()).await?;
+        let union_exec =
+            Arc::new(UnionExec::new(vec![left_exec, right_exec])) as Arc<dyn ExecutionPlan>;
+        if node.union_type() == UnionType::All {
+            return Ok(union_exec);
+        }
+        // a distinct union is an aggregation grouping by all columns over the rows of both sides
+        let input_exec =
+            Arc::new(CoalescePartitionsExec::new(union_exec)) as Arc<dyn ExecutionPlan>;
+        let schema = input_exec.schema();
+        let group_exprs = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| {
+                (
+                    Arc::new(physical_expr::expressions::Column::new(field.name(), idx))
+                        as Arc<dyn PhysicalExpr>,
+                    field.name().to_string(),
+                )
+            })
+            .collect();
+        let group_exprs = physical_plan::aggregates::PhysicalGroupBy::new_single(group_exprs);
+        Ok(Arc::new(
+            datafusion::physical_plan::aggregates::AggregateExec::try_new(
+                AggregateMode::Single,
+                group_exprs,
+                vec![],
+                vec![],
+                vec![],
+                input_exec,
+                schema,
+            )?,
+        ) as Arc<dyn ExecutionPlan + 'static>)
+    }
+
+    /// Build the filter of a join, where `cond` refers to the columns of the left side followed by the columns of the
+    /// right side. Only the first `left_len` and `right_len` columns of the two sides are visible to the filter.
+    fn from_optd_join_filter(
+        &mut self,
+        cond: Expr,
+        (left_schema, left_len): (&SchemaRef, usize),
+        (right_schema, right_len): (&SchemaRef, usize),
+    ) -> Result<JoinFilter> {
         let filter_schema = {
-            let fields = left_exec
-                .schema()
-                .fields()
-                .into_iter()
-                .chain(right_exec.schema().fields().into_iter())
+            let fields = left_schema.fields()[..left_len]
+                .iter()
+                .chain(right_schema.fields()[..right_len].iter())
                 .cloned()
                 .collect::<Vec<_>>();
             Schema::new_with_metadata(fields, HashMap::new())
         };
 
-        let physical_expr = self.from_optd_expr(node.cond(), &Arc::new(filter_schema.clone()))?;
-        let join_type = match node.join_type() {
-            JoinType::Inner => datafusion::logical_expr::JoinType::Inner,
-            JoinType::LeftOuter => datafusion::logical_expr::JoinType::Left,
-            _ => unimplemented!(),
-        };
+        let physical_expr = self.from_optd_expr(cond, &Arc::new(filter_schema.clone()))?;
 
         let mut column_idxs = vec![];
-        for i in 0..left_exec.schema().fields().len() {
+        for i in 0..left_len {
             column_idxs.push(ColumnIndex {
                 index: i,
                 side: physical_plan::joins::utils::JoinSide::Left,
             });
         }
-        for i in 0..right_exec.schema().fields().len() {
+        for i in 0..right_len {
             column_idxs.push(ColumnIndex {
                 index: i,
                 side: physical_plan::joins::utils::JoinSide::Right,
             });
         }
+        Ok(JoinFilter::new(physical_expr, column_idxs, filter_schema))
+    }
+
+    #[async_recursion]
+    async fn from_optd_nested_loop_join(
+        &mut self,
+        node: PhysicalNestedLoopJoin,
+    ) -> Result<Arc<dyn ExecutionPlan + 'static>> {
+        let left_exec = self.from_optd_plan_node(node.left()).await?;
+        let right_exec = self.from_optd_plan_node(node.right()).await?;
+        let join_type = from_optd_join_type(node.join_type());
+        let filter = self.from_optd_join_filter(
+            node.cond(),
+            (&left_exec.schema(), left_exec.schema().fields().len()),
+            (&right_exec.schema(), right_exec.schema().fields().len()),
+        )?;
 
         Ok(Arc::new(
             datafusion::physical_plan::joins::NestedLoopJoinExec::try_new(
                 left_exec,
                 right_exec,
-                Some(JoinFilter::new(physical_expr, column_idxs, filter_schema)),
+                Some(filter),
                 &join_type,
             )?,
         ) as Arc<dyn ExecutionPlan + 'static>)
     }
 
+    /// Get the key columns of one side of a hash or sort-merge join. Keys that are not column references are computed
+    /// by a projection and appended to the columns of the input.
+    fn from_optd_hash_join_keys(
+        &mut self,
+        input_exec: Arc<dyn ExecutionPlan>,
+        keys: Vec<Expr>,
+    ) -> Result<(
+        Arc<dyn ExecutionPlan>,
+        Vec<physical_expr::expressions::Column>,
+    )> {
+        let schema = input_exec.schema();
+        let mut projection = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| {
+                (
+                    Arc::new(physical_expr::expressions::Column::new(field.name(), idx))
+                        as Arc<dyn PhysicalExpr>,
+                    field.name().to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut columns = Vec::with_capacity(keys.len());
+        for key in keys {
+            if let Some(key) = ColumnRefExpr::from_rel_node(key.clone().into_rel_node()) {
+                columns.push(physical_expr::expressions::Column::new(
+                    schema.field(key.index()).name(),
+                    key.index(),
+                ));
+            } else {
+                let name = format!("__hash_key{}", projection.len());
+                columns.push(physical_expr::expressions::Column::new(
+                    &name,
+                    projection.len(),
+                ));
+                projection.push((self.from_optd_expr(key, &schema)?, name));
+            }
+        }
+        if projection.len() == schema.fields().len() {
+            return Ok((input_exec, columns));
+        }
+        Ok((
+            Arc::new(ProjectionExec::try_new(projection, input_exec)?) as Arc<dyn ExecutionPlan>,
+            columns,
+        ))
+    }
+
     #[async_recursion]
     async fn from_optd_hash_join(
         &mut self,
@@ -357,41 +795,173 @@ impl OptdPlanContext<'_> {
     ) -> Result<Arc<dyn ExecutionPlan + 'static>> {
         let left_exec = self.from_optd_plan_node(node.left()).await?;
         let right_exec = self.from_optd_plan_node(node.right()).await?;
-        let join_type = match node.join_type() {
-            JoinType::Inner => datafusion::logical_expr::JoinType::Inner,
-            _ => unimplemented!(),
-        };
+        let join_type = from_optd_join_type(node.join_type());
+        let null_equals_null = node.nulls_equal();
         let left_exprs = node.left_keys().to_vec();
         let right_exprs = node.right_keys().to_vec();
         assert_eq!(left_exprs.len(), right_exprs.len());
-        let mut on = Vec::with_capacity(left_exprs.len());
-        for (left_expr, right_expr) in left_exprs.into_iter().zip(right_exprs.into_iter()) {
-            let Some(left_expr) = ColumnRefExpr::from_rel_node(left_expr.into_rel_node()) else {
-                bail!("left expr is not column ref")
-            };
-            let Some(right_expr) = ColumnRefExpr::from_rel_node(right_expr.into_rel_node()) else {
-                bail!("right expr is not column ref")
-            };
-            on.push((
-                physical_expr::expressions::Column::new(
-                    left_exec.schema().field(left_expr.index()).name(),
-                    left_expr.index(),
-                ),
-                physical_expr::expressions::Column::new(
-                    right_exec.schema().field(right_expr.index()).name(),
-                    right_expr.index(),
-                ),
-            ));
+        let (left_len, right_len) = (
+            left_exec.schema().fields().len(),
+            right_exec.schema().fields().len(),
+        );
+        let (left_exec, left_keys) = self.from_optd_hash_join_keys(left_exec, left_exprs)?;
+        let (right_exec, right_keys) = self.from_optd_hash_join_keys(right_exec, right_exprs)?;
+        let on = left_keys.into_iter().zip(right_keys).collect::<Vec<_>>();
+        let cond = node.cond();
+        let is_true = ConstantExpr::from_rel_node(cond.clone().into_rel_node())
+            .map_or(false, |x| x.value().as_bool() == Some(true));
+        let filter = if is_true {
+            None
+        } else {
+            Some(self.from_optd_join_filter(
+                cond,
+                (&left_exec.schema(), left_len),
+                (&right_exec.schema(), right_len),
+            )?)
+        };
+        let (left_exec_len, right_exec_len) = (
+            left_exec.schema().fields().len(),
+            right_exec.schema().fields().len(),
+        );
+        let join_exec = Arc::new(datafusion::physical_plan::joins::HashJoinExec::try_new(
+            left_exec,
+            right_exec,
+            on,
+            filter,
+            &join_type,
+            PartitionMode::CollectLeft,
+            null_equals_null,
+        )?) as Arc<dyn ExecutionPlan + 'static>;
+        Self::remove_join_keys(
+            join_exec,
+            node.join_type(),
+            (left_len, left_exec_len),
+            (right_len, right_exec_len),
+        )
+    }
+
+    /// Remove the key columns computed by `from_optd_hash_join_keys` from the output of a join, given the number of
+    /// columns of each side before and after computing the keys.
+    fn remove_join_keys(
+        join_exec: Arc<dyn ExecutionPlan>,
+        join_type: JoinType,
+        (left_len, left_exec_len): (usize, usize),
+        (right_len, right_exec_len): (usize, usize),
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if left_exec_len == left_len && right_exec_len == right_len {
+            return Ok(join_exec);
         }
-        Ok(
-            Arc::new(datafusion::physical_plan::joins::HashJoinExec::try_new(
+        // semi and anti joins only output the columns of one side
+        let (left_columns, right_columns) = match join_type {
+            JoinType::LeftSemi | JoinType::LeftAnti => (0..left_len, 0..0),
+            JoinType::RightSemi | JoinType::RightAnti => (0..0, 0..right_len),
+            _ => (0..left_len, left_exec_len..left_exec_len + right_len),
+        };
+        let schema = join_exec.schema();
+        let projection = left_columns
+            .chain(right_columns)
+            .map(|idx| {
+                let name = schema.field(idx).name();
+                (
+                    Arc::new(physical_expr::expressions::Column::new(name, idx))
+                        as Arc<dyn PhysicalExpr>,
+                    name.to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        Ok(Arc::new(ProjectionExec::try_new(projection, join_exec)?)
+            as Arc<dyn ExecutionPlan + 'static>)
+    }
+
+    /// Sort one side of a sort-merge join on its key columns, unless it is already sorted on them.
+    fn sort_join_input(
+        input_exec: Arc<dyn ExecutionPlan>,
+        keys: &[physical_expr::expressions::Column],
+    ) -> Arc<dyn ExecutionPlan> {
+        let sort_exprs = keys
+            .iter()
+            .map(|key| physical_expr::PhysicalSortExpr {
+                expr: Arc::new(key.clone()) as Arc<dyn PhysicalExpr>,
+                options: datafusion::arrow::compute::SortOptions::default(),
+            })
+            .collect::<Vec<_>>();
+        Self::sort_if_needed(input_exec, sort_exprs)
+    }
+
+    /// Sort the input into a single partition, unless it already is a single partition whose order starts with
+    /// `sort_exprs`.
+    fn sort_if_needed(
+        input_exec: Arc<dyn ExecutionPlan>,
+        sort_exprs: Vec<physical_expr::PhysicalSortExpr>,
+    ) -> Arc<dyn ExecutionPlan> {
+        let sorted = input_exec.output_partitioning().partition_count() == 1
+            && input_exec.output_ordering().map_or(false, |ordering| {
+                ordering.len() >= sort_exprs.len()
+                    && ordering.iter().zip(&sort_exprs).all(|(x, y)| x == y)
+            });
+        if sorted {
+            return input_exec;
+        }
+        Arc::new(datafusion::physical_plan::sorts::sort::SortExec::new(
+            sort_exprs, input_exec,
+        )) as Arc<dyn ExecutionPlan>
+    }
+
+    #[async_recursion]
+    async fn from_optd_sort_merge_join(
+        &mut self,
+        node: PhysicalSortMergeJoin,
+    ) -> Result<Arc<dyn ExecutionPlan + 'static>> {
+        let left_exec = self.from_optd_plan_node(node.left()).await?;
+        let right_exec = self.from_optd_plan_node(node.right()).await?;
+        let join_type = from_optd_join_type(node.join_type());
+        let left_exprs = node.left_keys().to_vec();
+        let right_exprs = node.right_keys().to_vec();
+        assert_eq!(left_exprs.len(), right_exprs.len());
+        let (left_len, right_len) = (
+            left_exec.schema().fields().len(),
+            right_exec.schema().fields().len(),
+        );
+        let (left_exec, left_keys) = self.from_optd_hash_join_keys(left_exec, left_exprs)?;
+        let (right_exec, right_keys) = self.from_optd_hash_join_keys(right_exec, right_exprs)?;
+        let left_exec = Self::sort_join_input(left_exec, &left_keys);
+        let right_exec = Self::sort_join_input(right_exec, &right_keys);
+        let (left_exec_len, right_exec_len) = (
+            left_exec.schema().fields().len(),
+            right_exec.schema().fields().len(),
+        );
+        let sort_options =
+            vec![datafusion::arrow::compute::SortOptions::default(); left_keys.len()];
+        let on = left_keys.into_iter().zip(right_keys).collect::<Vec<_>>();
+        let join_exec = Arc::new(
+            datafusion::physical_plan::joins::SortMergeJoinExec::try_new(
                 left_exec,
                 right_exec,
                 on,
-                None,
-                &join_type,
-                PartitionMode::CollectLeft,
+                join_type,
+                sort_options,
                 false,
+            )?,
+        ) as Arc<dyn ExecutionPlan + 'static>;
+        let join_exec = Self::remove_join_keys(
+            join_exec,
+            node.join_type(),
+            (left_len, left_exec_len),
+            (right_len, right_exec_len),
+        )?;
+        // the sort-merge join does not take a filter, so the residual condition of an inner join is evaluated on its
+        // output
+        let cond = node.cond();
+        let is_true = ConstantExpr::from_rel_node(cond.clone().into_rel_node())
+            .map_or(false, |x| x.value().as_bool() == Some(true));
+        if is_true {
+            return Ok(join_exec);
+        }
+        let physical_expr = self.from_optd_expr(cond, &join_exec.schema())?;
+        Ok(
+            Arc::new(datafusion::physical_plan::filter::FilterExec::try_new(
+                physical_expr,
+                join_exec,
             )?) as Arc<dyn ExecutionPlan + 'static>,
         )
     }
@@ -421,6 +991,27 @@ impl OptdPlanContext<'_> {
                 self.from_optd_agg(PhysicalAgg::from_rel_node(rel_node).unwrap())
                     .await
             }
+            OptRelNodeTyp::PhysicalLimit => {
+                self.from_optd_limit(PhysicalLimit::from_rel_node(rel_node).unwrap())
+                    .await
+            }
+            OptRelNodeTyp::PhysicalTopN => {
+                self.from_optd_top_n(PhysicalTopN::from_rel_node(rel_node).unwrap())
+                    .await
+            }
+            OptRelNodeTyp::PhysicalWindow => {
+                self.from_optd_window(PhysicalWindow::from_rel_node(rel_node).unwrap())
+                    .await
+            }
+            OptRelNodeTyp::PhysicalValues => {
+                self.from_optd_values(PhysicalValues::from_rel_node(rel_node).unwrap())
+            }
+            OptRelNodeTyp::PhysicalEmptyRelation => self
+                .from_optd_empty_relation(PhysicalEmptyRelation::from_rel_node(rel_node).unwrap()),
+            OptRelNodeTyp::PhysicalUnion(_) => {
+                self.from_optd_union(PhysicalUnion::from_rel_node(rel_node).unwrap())
+                    .await
+            }
             OptRelNodeTyp::PhysicalNestedLoopJoin(_) => {
                 self.from_optd_nested_loop_join(
                     PhysicalNestedLoopJoin::from_rel_node(rel_node).unwrap(),
@@ -431,6 +1022,12 @@ impl OptdPlanContext<'_> {
                 self.from_optd_hash_join(PhysicalHashJoin::from_rel_node(rel_node).unwrap())
                     .await
             }
+            OptRelNodeTyp::PhysicalSortMergeJoin(_) => {
+                self.from_optd_sort_merge_join(
+                    PhysicalSortMergeJoin::from_rel_node(rel_node).unwrap(),
+                )
+                .await
+            }
             OptRelNodeTyp::PhysicalCollector(_) => {
                 let node = PhysicalCollector::from_rel_node(rel_node).unwrap();
                 let child = self.from_optd_plan_node(node.child()).await?;
@@ -440,7 +1037,7 @@ impl OptdPlanContext<'_> {
                     self.optimizer.as_ref().unwrap().runtime_statistics.clone(),
                 )) as Arc<dyn ExecutionPlan>)
             }
-            typ => unimplemented!("{}", typ),
+            typ => Err(anyhow::anyhow!("{} is not a physical plan node", typ)),
         };
         result.with_context(|| format!("when processing {}", rel_node_dbg))
     }
@@ -1,39 +1,172 @@
 use anyhow::{bail, Result};
 use datafusion::{
-    common::DFSchema,
-    logical_expr::{self, logical_plan, LogicalPlan, Operator},
+    arrow::datatypes::{
+        self, DataType, IntervalDayTimeType, IntervalMonthDayNanoType, IntervalUnit,
+    },
+    common::{cast::as_list_array, DFSchema},
+    logical_expr::{
+        self, logical_plan, utils::split_conjunction, ExprSchemable, LogicalPlan, Operator,
+        Subquery,
+    },
     scalar::ScalarValue,
 };
-use optd_core::rel_node::RelNode;
-use optd_datafusion_repr::plan_nodes::{
-    BinOpExpr, BinOpType, ColumnRefExpr, ConstantExpr, Expr, ExprList, FuncExpr, FuncType,
-    JoinType, LogOpExpr, LogOpType, LogicalAgg, LogicalFilter, LogicalJoin, LogicalProjection,
-    LogicalScan, LogicalSort, OptRelNode, OptRelNodeRef, OptRelNodeTyp, PlanNode, SortOrderExpr,
-    SortOrderType,
+use optd_core::rel_node::{RelNode, TimeUnit};
+use optd_datafusion_repr::{
+    plan_nodes::{
+        ApplyType, BinOpExpr, BinOpType, ColumnRefExpr, ConstantExpr, ConstantType, Expr, ExprList,
+        ExternColumnRefExpr, FuncExpr, FuncType, IntervalKind, JoinType, LogOpExpr, LogOpType,
+        LogicalAgg, LogicalApply, LogicalDistinct, LogicalEmptyRelation, LogicalFilter,
+        LogicalJoin, LogicalLimit, LogicalProjection, LogicalScan, LogicalSort, LogicalUnion,
+        LogicalValues, LogicalWindow, OptRelNode, OptRelNodeRef, OptRelNodeTyp, PlanNode,
+        SortOrderExpr, SortOrderType, Timezone, UnionType,
+    },
+    properties::schema::Field,
+    Value,
 };
 
 use crate::OptdPlanContext;
 
+fn into_optd_time_unit(unit: &datatypes::TimeUnit) -> TimeUnit {
+    match unit {
+        datatypes::TimeUnit::Second => TimeUnit::Second,
+        datatypes::TimeUnit::Millisecond => TimeUnit::Millisecond,
+        datatypes::TimeUnit::Microsecond => TimeUnit::Microsecond,
+        datatypes::TimeUnit::Nanosecond => TimeUnit::Nanosecond,
+    }
+}
+
+pub(crate) fn into_optd_type(typ: &DataType) -> ConstantType {
+    match typ {
+        DataType::Boolean => ConstantType::Bool,
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => ConstantType::Int,
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 => ConstantType::Int,
+        DataType::Float32 | DataType::Float64 => ConstantType::Float,
+        DataType::Decimal128(precision, scale) => ConstantType::Decimal(*precision, *scale),
+        DataType::Date32 => ConstantType::Date,
+        DataType::Timestamp(unit, tz) => {
+            ConstantType::Timestamp(into_optd_time_unit(unit), tz.as_deref().map(Timezone::new))
+        }
+        DataType::Interval(IntervalUnit::YearMonth) => {
+            ConstantType::Interval(IntervalKind::YearMonth)
+        }
+        DataType::Interval(IntervalUnit::DayTime) => ConstantType::Interval(IntervalKind::DayTime),
+        DataType::Interval(IntervalUnit::MonthDayNano) => {
+            ConstantType::Interval(IntervalKind::MonthDayNano)
+        }
+        DataType::Utf8 | DataType::LargeUtf8 => ConstantType::Utf8String,
+        _ => ConstantType::Any,
+    }
+}
+
+/// The output fields of a plan node without a child, whose schema cannot be derived by the optimizer.
+fn into_optd_fields(schema: &DFSchema) -> Vec<Field> {
+    schema
+        .fields()
+        .iter()
+        .map(|field| Field {
+            name: field.name().to_string(),
+            typ: into_optd_type(field.data_type()),
+            nullable: field.is_nullable(),
+        })
+        .collect()
+}
+
+fn into_optd_value(x: &ScalarValue) -> Result<ConstantExpr> {
+    if x.is_null() {
+        return Ok(ConstantExpr::null(into_optd_type(&x.get_datatype())));
+    }
+    let expr = match x {
+        ScalarValue::Boolean(Some(x)) => ConstantExpr::bool(*x),
+        ScalarValue::Int8(Some(x)) => ConstantExpr::int(*x as i64),
+        ScalarValue::Int16(Some(x)) => ConstantExpr::int(*x as i64),
+        ScalarValue::Int32(Some(x)) => ConstantExpr::int(*x as i64),
+        ScalarValue::Int64(Some(x)) => ConstantExpr::int(*x),
+        ScalarValue::UInt8(Some(x)) => ConstantExpr::int(*x as i64),
+        ScalarValue::UInt16(Some(x)) => ConstantExpr::int(*x as i64),
+        ScalarValue::UInt32(Some(x)) => ConstantExpr::int(*x as i64),
+        ScalarValue::Float32(Some(x)) => ConstantExpr::float(*x as f64),
+        ScalarValue::Float64(Some(x)) => ConstantExpr::float(*x),
+        ScalarValue::Decimal128(Some(x), p, s) => ConstantExpr::decimal(*x, *p, *s),
+        ScalarValue::Utf8(Some(x)) | ScalarValue::LargeUtf8(Some(x)) => ConstantExpr::string(x),
+        ScalarValue::Date32(Some(x)) => ConstantExpr::date(*x),
+        ScalarValue::TimestampSecond(Some(x), tz) => {
+            ConstantExpr::timestamp(*x, TimeUnit::Second, tz.as_deref())
+        }
+        ScalarValue::TimestampMillisecond(Some(x), tz) => {
+            ConstantExpr::timestamp(*x, TimeUnit::Millisecond, tz.as_deref())
+        }
+        ScalarValue::TimestampMicrosecond(Some(x), tz) => {
+            ConstantExpr::timestamp(*x, TimeUnit::Microsecond, tz.as_deref())
+        }
+        ScalarValue::TimestampNanosecond(Some(x), tz) => {
+            ConstantExpr::timestamp(*x, TimeUnit::Nanosecond, tz.as_deref())
+        }
+        ScalarValue::IntervalYearMonth(Some(x)) => {
+            ConstantExpr::interval(*x, 0, 0, IntervalKind::YearMonth)
+        }
+        ScalarValue::IntervalDayTime(Some(x)) => {
+            let (days, millis) = IntervalDayTimeType::to_parts(*x);
+            ConstantExpr::interval(0, days, millis as i64 * 1_000_000, IntervalKind::DayTime)
+        }
+        ScalarValue::IntervalMonthDayNano(Some(x)) => {
+            let (months, days, nanos) = IntervalMonthDayNanoType::to_parts(*x);
+            ConstantExpr::interval(months, days, nanos, IntervalKind::MonthDayNano)
+        }
+        ScalarValue::List(..) => {
+            let array = x.to_array();
+            let values = as_list_array(&array)?.value(0);
+            let items = (0..values.len())
+                .map(|idx| {
+                    Ok(into_optd_value(&ScalarValue::try_from_array(&values, idx)?)?.value())
+                })
+                .collect::<Result<Vec<_>>>()?;
+            ConstantExpr::new(Value::List(items.into()))
+        }
+        _ => bail!("{:?}", x),
+    };
+    Ok(expr)
+}
+
+/// Whether the plan returns at most one row regardless of its input, e.g., an aggregation without group by columns.
+fn returns_at_most_one_row(plan: &LogicalPlan) -> bool {
+    match plan {
+        LogicalPlan::Aggregate(node) => node.group_expr.is_empty(),
+        LogicalPlan::Limit(node) => node.fetch.is_some_and(|x| x <= 1),
+        LogicalPlan::Sort(node) => {
+            node.fetch.is_some_and(|x| x <= 1) || returns_at_most_one_row(&node.input)
+        }
+        LogicalPlan::EmptyRelation(_) => true,
+        LogicalPlan::Projection(node) => returns_at_most_one_row(&node.input),
+        LogicalPlan::Filter(node) => returns_at_most_one_row(&node.input),
+        LogicalPlan::SubqueryAlias(node) => returns_at_most_one_row(&node.input),
+        _ => false,
+    }
+}
+
 impl OptdPlanContext<'_> {
     fn into_optd_table_scan(&mut self, node: &logical_plan::TableScan) -> Result<PlanNode> {
         let table_name = node.table_name.to_string();
-        if node.fetch.is_some() {
-            bail!("fetch")
-        }
         if !node.filters.is_empty() {
             bail!("no filters")
         }
         self.tables.insert(table_name.clone(), node.source.clone());
-        let scan = LogicalScan::new(table_name);
+        let mut plan_node = LogicalScan::new(table_name).into_plan_node();
         if let Some(ref projection) = node.projection {
-            let mut exprs = Vec::with_capacity(projection.len());
-            for &p in projection {
-                exprs.push(ColumnRefExpr::new(p).into_expr());
+            let column_count = node.source.schema().fields().len();
+            // skip the projection if it outputs all columns in order
+            if !projection.iter().copied().eq(0..column_count) {
+                let mut exprs = Vec::with_capacity(projection.len());
+                for &p in projection {
+                    exprs.push(ColumnRefExpr::new(p).into_expr());
+                }
+                plan_node =
+                    LogicalProjection::new(plan_node, ExprList::new(exprs)).into_plan_node();
             }
-            let projection = LogicalProjection::new(scan.into_plan_node(), ExprList::new(exprs));
-            return Ok(projection.into_plan_node());
         }
-        Ok(scan.into_plan_node())
+        if let Some(fetch) = node.fetch {
+            plan_node = LogicalLimit::new(plan_node, 0, Some(fetch)).into_plan_node();
+        }
+        Ok(plan_node)
     }
 
     fn into_optd_expr(&mut self, expr: &logical_expr::Expr, context: &DFSchema) -> Result<Expr> {
@@ -44,15 +177,19 @@ impl OptdPlanContext<'_> {
                 let right = self.into_optd_expr(node.right.as_ref(), context)?;
                 let op = match node.op {
                     Operator::Eq => BinOpType::Eq,
+                    Operator::IsNotDistinctFrom => BinOpType::IsNotDistinctFrom,
                     Operator::NotEq => BinOpType::Neq,
+                    Operator::Lt => BinOpType::Lt,
+                    Operator::Gt => BinOpType::Gt,
                     Operator::LtEq => BinOpType::Leq,
                     Operator::GtEq => BinOpType::Geq,
                     Operator::And => BinOpType::And,
+                    Operator::Or => BinOpType::Or,
                     Operator::Plus => BinOpType::Add,
                     Operator::Minus => BinOpType::Sub,
                     Operator::Multiply => BinOpType::Mul,
                     Operator::Divide => BinOpType::Div,
-                    op => unimplemented!("{}", op),
+                    op => bail!("unsupported binary operator: {}", op),
                 };
                 Ok(BinOpExpr::new(left, right, op).into_expr())
             }
@@ -60,25 +197,20 @@ impl OptdPlanContext<'_> {
                 let idx = context.index_of_column(col)?;
                 Ok(ColumnRefExpr::new(idx).into_expr())
             }
-            Expr::Literal(x) => match x {
-                ScalarValue::UInt8(x) => {
-                    let x = x.as_ref().unwrap();
-                    Ok(ConstantExpr::int(*x as i64).into_expr())
-                }
-                ScalarValue::Utf8(x) => {
-                    let x = x.as_ref().unwrap();
-                    Ok(ConstantExpr::string(x).into_expr())
-                }
-                ScalarValue::Date32(x) => {
-                    let x = x.as_ref().unwrap();
-                    Ok(ConstantExpr::date(*x as i64).into_expr())
-                }
-                ScalarValue::Decimal128(x, p, s) => {
-                    let x = x.as_ref().unwrap();
-                    Ok(ConstantExpr::decimal(*x as f64).into_expr())
-                }
-                _ => bail!("{:?}", x),
-            },
+            Expr::OuterReferenceColumn(_, col) => {
+                let Some(outer_schema) = &self.outer_schema else {
+                    bail!("outer reference {} outside of a subquery", col)
+                };
+                let idx = outer_schema.index_of_column(col)?;
+                Ok(ExternColumnRefExpr::new(idx).into_expr())
+            }
+            Expr::ScalarSubquery(subquery) => {
+                // the subquery is evaluated by an apply below the plan node, which appends its output to the input
+                self.scalar_subqueries.push(subquery.clone());
+                let idx = context.fields().len() + self.scalar_subqueries.len() - 1;
+                Ok(ColumnRefExpr::new(idx).into_expr())
+            }
+            Expr::Literal(x) => Ok(into_optd_value(x)?.into_expr()),
             Expr::Alias(x) => self.into_optd_expr(x.expr.as_ref(), context),
             Expr::ScalarFunction(x) => {
                 let args = self.into_optd_expr_list(&x.args, context)?;
@@ -88,14 +220,31 @@ impl OptdPlanContext<'_> {
                 let args = self.into_optd_expr_list(&x.args, context)?;
                 Ok(FuncExpr::new(FuncType::new_agg(x.fun.clone()), args).into_expr())
             }
+            Expr::WindowFunction(x) => {
+                use logical_expr::window_function::WindowFunction;
+                let func = match &x.fun {
+                    WindowFunction::AggregateFunction(func) => FuncType::new_agg(func.clone()),
+                    WindowFunction::BuiltInWindowFunction(func) => {
+                        FuncType::new_window(func.clone())
+                    }
+                    _ => bail!("unsupported window function: {}", x.fun),
+                };
+                let args = self.into_optd_expr_list(&x.args, context)?;
+                Ok(FuncExpr::new(func, args).into_expr())
+            }
             Expr::Case(x) => {
-                let when_then_expr = &x.when_then_expr;
-                assert_eq!(when_then_expr.len(), 1);
-                let (when_expr, then_expr) = &when_then_expr[0];
-                let when_expr = self.into_optd_expr(&when_expr, context)?;
-                let then_expr = self.into_optd_expr(&then_expr, context)?;
-                let else_expr = self.into_optd_expr(x.else_expr.as_ref().unwrap(), context)?;
-                assert!(x.expr.is_none());
+                if x.expr.is_some() {
+                    bail!("unsupported CASE with an operand: {}", expr)
+                }
+                let [(when_expr, then_expr)] = &x.when_then_expr[..] else {
+                    bail!("unsupported CASE without exactly one WHEN: {}", expr)
+                };
+                let Some(else_expr) = &x.else_expr else {
+                    bail!("unsupported CASE without ELSE: {}", expr)
+                };
+                let when_expr = self.into_optd_expr(when_expr, context)?;
+                let then_expr = self.into_optd_expr(then_expr, context)?;
+                let else_expr = self.into_optd_expr(else_expr, context)?;
                 Ok(FuncExpr::new(
                     FuncType::Case,
                     ExprList::new(vec![when_expr, then_expr, else_expr]),
@@ -118,19 +267,126 @@ impl OptdPlanContext<'_> {
         }
     }
 
+    /// Convert a subquery, whose outer references refer to the columns of `outer_schema`.
+    fn into_optd_subquery(
+        &mut self,
+        subquery: &Subquery,
+        outer_schema: &DFSchema,
+    ) -> Result<PlanNode> {
+        let outer_schema = self.outer_schema.replace(outer_schema.clone());
+        let scalar_subqueries = std::mem::take(&mut self.scalar_subqueries);
+        let node = self.into_optd_plan_node(subquery.subquery.as_ref());
+        self.outer_schema = outer_schema;
+        self.scalar_subqueries = scalar_subqueries;
+        node
+    }
+
+    /// Evaluate the scalar subqueries found while converting the expressions on `input` with left outer applies,
+    /// each of which appends one column to the input. A scalar subquery must fail at runtime if it returns more than
+    /// one row, which an apply cannot express, so only subqueries that return at most one row are supported.
+    fn into_optd_scalar_subqueries(
+        &mut self,
+        mut input: PlanNode,
+        schema: &DFSchema,
+    ) -> Result<PlanNode> {
+        for subquery in std::mem::take(&mut self.scalar_subqueries) {
+            if !returns_at_most_one_row(subquery.subquery.as_ref()) {
+                bail!(
+                    "scalar subquery may return more than one row: {}",
+                    subquery.subquery.display_indent()
+                );
+            }
+            let right = self.into_optd_subquery(&subquery, schema)?;
+            input = LogicalApply::new(
+                input,
+                right,
+                ConstantExpr::bool(true).into_expr(),
+                ApplyType::LeftOuter,
+            )
+            .into_plan_node();
+        }
+        Ok(input)
+    }
+
     fn into_optd_projection(
         &mut self,
         node: &logical_plan::Projection,
     ) -> Result<LogicalProjection> {
         let input = self.into_optd_plan_node(node.input.as_ref())?;
         let expr_list = self.into_optd_expr_list(&node.expr, node.input.schema())?;
+        let input = self.into_optd_scalar_subqueries(input, node.input.schema())?;
         Ok(LogicalProjection::new(input, expr_list))
     }
 
-    fn into_optd_filter(&mut self, node: &logical_plan::Filter) -> Result<LogicalFilter> {
-        let input = self.into_optd_plan_node(node.input.as_ref())?;
-        let expr = self.into_optd_expr(&node.predicate, node.input.schema())?;
-        Ok(LogicalFilter::new(input, expr))
+    /// Convert a filter. `EXISTS` and `IN` subqueries in the top level conjuncts become semi or anti applies, and scalar
+    /// subqueries become left outer applies whose output columns are projected away after the filter.
+    fn into_optd_filter(&mut self, node: &logical_plan::Filter) -> Result<PlanNode> {
+        use logical_expr::Expr;
+        let schema = node.input.schema();
+        let mut input = self.into_optd_plan_node(node.input.as_ref())?;
+        let mut conjuncts = vec![];
+        for conjunct in split_conjunction(&node.predicate) {
+            let (subquery, cond, negated) = match conjunct {
+                Expr::Exists(exists) => (
+                    &exists.subquery,
+                    ConstantExpr::bool(true).into_expr(),
+                    exists.negated,
+                ),
+                Expr::InSubquery(in_subquery) => {
+                    // the subquery outputs a single column, which is appended to the columns of the input
+                    let expr = self.into_optd_expr(&in_subquery.expr, schema)?;
+                    let column = ColumnRefExpr::new(schema.fields().len()).into_expr();
+                    let subquery_schema = in_subquery.subquery.subquery.schema();
+                    let nullable = in_subquery.expr.nullable(schema)?
+                        || subquery_schema.field(0).is_nullable();
+                    let cond = if in_subquery.negated && nullable {
+                        // `NOT IN` drops the row if any comparison is null, so the anti apply must match the rows
+                        // where `expr <> column` is not true: `case when expr <> column then false else true end`
+                        FuncExpr::new(
+                            FuncType::Case,
+                            ExprList::new(vec![
+                                BinOpExpr::new(expr, column, BinOpType::Neq).into_expr(),
+                                ConstantExpr::bool(false).into_expr(),
+                                ConstantExpr::bool(true).into_expr(),
+                            ]),
+                        )
+                        .into_expr()
+                    } else {
+                        BinOpExpr::new(expr, column, BinOpType::Eq).into_expr()
+                    };
+                    (&in_subquery.subquery, cond, in_subquery.negated)
+                }
+                _ => {
+                    conjuncts.push(self.into_optd_expr(conjunct, schema)?);
+                    continue;
+                }
+            };
+            let right = self.into_optd_subquery(subquery, schema)?;
+            let apply_type = if negated {
+                ApplyType::AntiSemi
+            } else {
+                ApplyType::Semi
+            };
+            input = LogicalApply::new(input, right, cond, apply_type).into_plan_node();
+        }
+        let has_scalar_subqueries = !self.scalar_subqueries.is_empty();
+        let input = self.into_optd_scalar_subqueries(input, schema)?;
+        let node = match conjuncts.len() {
+            0 => input,
+            1 => LogicalFilter::new(input, conjuncts.remove(0)).into_plan_node(),
+            _ => LogicalFilter::new(
+                input,
+                LogOpExpr::new(LogOpType::And, ExprList::new(conjuncts)).into_expr(),
+            )
+            .into_plan_node(),
+        };
+        if !has_scalar_subqueries {
+            return Ok(node);
+        }
+        let exprs = (0..schema.fields().len())
+            .map(|x| ColumnRefExpr::new(x).into_expr())
+            .collect();
+        Ok(LogicalProjection::new(node, ExprList::new(exprs)).into_plan_node())
     }
 
     fn into_optd_expr_list(
@@ -145,10 +401,19 @@ impl OptdPlanContext<'_> {
         Ok(ExprList::new(exprs))
     }
 
-    fn into_optd_sort(&mut self, node: &logical_plan::Sort) -> Result<LogicalSort> {
+    fn into_optd_sort(&mut self, node: &logical_plan::Sort) -> Result<PlanNode> {
         let input = self.into_optd_plan_node(node.input.as_ref())?;
         let expr_list = self.into_optd_expr_list(&node.expr, node.input.schema())?;
-        Ok(LogicalSort::new(input, expr_list))
+        let sort = LogicalSort::new(input, expr_list).into_plan_node();
+        if let Some(fetch) = node.fetch {
+            return Ok(LogicalLimit::new(sort, 0, Some(fetch)).into_plan_node());
+        }
+        Ok(sort)
+    }
+
+    fn into_optd_limit(&mut self, node: &logical_plan::Limit) -> Result<LogicalLimit> {
+        let input = self.into_optd_plan_node(node.input.as_ref())?;
+        Ok(LogicalLimit::new(input, node.skip, node.fetch))
     }
 
     fn into_optd_agg(&mut self, node: &logical_plan::Aggregate) -> Result<LogicalAgg> {
@@ -158,6 +423,119 @@ impl OptdPlanContext<'_> {
         Ok(LogicalAgg::new(input, agg_exprs, group_exprs))
     }
 
+    /// Convert a window, whose functions may have different partition and order specs, into a stack of windows. Each
+    /// window evaluates a run of consecutive functions with the same specs, so that the output columns keep their order.
+    /// The functions only refer to the columns of the input, which are the first columns of every window in the stack.
+    fn into_optd_window(&mut self, node: &logical_plan::Window) -> Result<PlanNode> {
+        use logical_expr::{expr::WindowFunction, WindowFrame};
+        let mut input = self.into_optd_plan_node(node.input.as_ref())?;
+        let schema = node.input.schema();
+        let mut window_exprs = node.window_expr.iter().peekable();
+        while let Some(first) = window_exprs.next() {
+            let logical_expr::Expr::WindowFunction(WindowFunction {
+                partition_by,
+                order_by,
+                window_frame,
+                ..
+            }) = first.clone().unalias()
+            else {
+                bail!("unsupported window expression: {}", first)
+            };
+            // only the default frame is supported, which is the whole partition, or the rows up to the peers of the
+            // current row if the partition is ordered
+            if window_frame != WindowFrame::new(!order_by.is_empty()) {
+                bail!("unsupported window frame: {}", window_frame)
+            }
+            let mut exprs = vec![self.into_optd_expr(first, schema)?];
+            while let Some(next) = window_exprs.peek() {
+                let logical_expr::Expr::WindowFunction(x) = next.clone().unalias() else {
+                    break;
+                };
+                if x.partition_by != partition_by
+                    || x.order_by != order_by
+                    || x.window_frame != window_frame
+                {
+                    break;
+                }
+                exprs.push(self.into_optd_expr(next, schema)?);
+                window_exprs.next();
+            }
+            input = LogicalWindow::new(
+                input,
+                ExprList::new(exprs),
+                self.into_optd_expr_list(&partition_by, schema)?,
+                self.into_optd_expr_list(&order_by, schema)?,
+            )
+            .into_plan_node();
+        }
+        Ok(input)
+    }
+
+    /// Converts a union of any number of inputs, which is the input itself if there is only one.
+    fn into_optd_union(&mut self, node: &logical_plan::Union) -> Result<PlanNode> {
+        let mut inputs = node.inputs.iter();
+        let Some(first) = inputs.next() else {
+            bail!("union without inputs");
+        };
+        let mut union = self.into_optd_plan_node(first.as_ref())?;
+        // the union of more than two inputs is folded into a left-deep tree of unions
+        for input in inputs {
+            let input = self.into_optd_plan_node(input.as_ref())?;
+            union = LogicalUnion::new(union, input, UnionType::All).into_plan_node();
+        }
+        Ok(union)
+    }
+
+    fn into_optd_distinct(&mut self, node: &logical_plan::Distinct) -> Result<PlanNode> {
+        let input = self.into_optd_plan_node(node.input.as_ref())?;
+        // the distinct of a union of several inputs is the top union made distinct
+        if let Some(union) = LogicalUnion::from_rel_node(input.clone().into_rel_node()) {
+            return Ok(LogicalUnion::new(
+                union.left_child(),
+                union.right_child(),
+                UnionType::Distinct,
+            )
+            .into_plan_node());
+        }
+        Ok(LogicalDistinct::new(input).into_plan_node())
+    }
+
+    fn into_optd_values(&mut self, node: &logical_plan::Values) -> Result<LogicalValues> {
+        let empty_schema = DFSchema::empty();
+        let mut values = vec![];
+        for row in &node.values {
+            for value in row {
+                // Datafusion casts the null values to the type of their column
+                let value = match value {
+                    logical_expr::Expr::Cast(cast)
+                        if matches!(
+                            cast.expr.as_ref(),
+                            logical_expr::Expr::Literal(x) if x.is_null()
+                        ) =>
+                    {
+                        into_optd_value(&ScalarValue::try_from(&cast.data_type)?)?.into_expr()
+                    }
+                    value => self.into_optd_expr(value, &empty_schema)?,
+                };
+                values.push(value);
+            }
+        }
+        Ok(LogicalValues::new(
+            ExprList::new(values),
+            node.values.len(),
+            &into_optd_fields(&node.schema),
+        ))
+    }
+
+    fn into_optd_empty_relation(&mut self, node: &logical_plan::EmptyRelation) -> Result<PlanNode> {
+        let fields = into_optd_fields(&node.schema);
+        if node.produce_one_row {
+            // a single row without columns, e.g., the input of `SELECT 1`
+            return Ok(LogicalValues::new(ExprList::new(vec![]), 1, &fields).into_plan_node());
+        }
+        Ok(LogicalEmptyRelation::new(&fields).into_plan_node())
+    }
+
     fn add_column_offset(&mut self, offset: usize, expr: Expr) -> Expr {
         if expr.typ() == OptRelNodeTyp::ColumnRef {
             let expr = ColumnRefExpr::from_rel_node(expr.into_rel_node()).unwrap();
@@ -205,10 +583,20 @@ impl OptdPlanContext<'_> {
             let left = self.into_optd_expr(left, node.left.schema())?;
             let right = self.into_optd_expr(right, node.right.schema())?;
             let right = self.add_column_offset(node.left.schema().fields().len(), right);
-            let op = BinOpType::Eq;
+            // intersect and except are planned as semi and anti joins on which nulls are equal
+            let op = if node.null_equals_null {
+                BinOpType::IsNotDistinctFrom
+            } else {
+                BinOpType::Eq
+            };
             let expr = BinOpExpr::new(left, right, op).into_expr();
             log_ops.push(expr);
         }
+        if let Some(filter) = &node.filter {
+            // the filter refers to the columns of both sides, even for semi and anti joins
+            let schema = node.left.schema().join(node.right.schema())?;
+            log_ops.push(self.into_optd_expr(filter, &schema)?);
+        }
 
         if log_ops.is_empty() {
             Ok(LogicalJoin::new(
@@ -230,20 +618,41 @@ impl OptdPlanContext<'_> {
         }
     }
 
+    fn into_optd_cross_join(&mut self, node: &logical_plan::CrossJoin) -> Result<LogicalJoin> {
+        let left = self.into_optd_plan_node(node.left.as_ref())?;
+        let right = self.into_optd_plan_node(node.right.as_ref())?;
+        Ok(LogicalJoin::new(
+            left,
+            right,
+            ConstantExpr::bool(true).into_expr(),
+            JoinType::Cross,
+        ))
+    }
+
     fn into_optd_plan_node(&mut self, node: &LogicalPlan) -> Result<PlanNode> {
         let node = match node {
-            LogicalPlan::TableScan(node) => self.into_optd_table_scan(node)?.into_plan_node(),
+            LogicalPlan::TableScan(node) => self.into_optd_table_scan(node)?,
             LogicalPlan::Projection(node) => self.into_optd_projection(node)?.into_plan_node(),
-            LogicalPlan::Sort(node) => self.into_optd_sort(node)?.into_plan_node(),
+            LogicalPlan::Sort(node) => self.into_optd_sort(node)?,
+            LogicalPlan::Limit(node) => self.into_optd_limit(node)?.into_plan_node(),
             LogicalPlan::Aggregate(node) => self.into_optd_agg(node)?.into_plan_node(),
             LogicalPlan::SubqueryAlias(node) => self.into_optd_plan_node(node.input.as_ref())?,
             LogicalPlan::Join(node) => self.into_optd_join(node)?.into_plan_node(),
-            LogicalPlan::Filter(node) => self.into_optd_filter(node)?.into_plan_node(),
+            LogicalPlan::CrossJoin(node) => self.into_optd_cross_join(node)?.into_plan_node(),
+            LogicalPlan::Filter(node) => self.into_optd_filter(node)?,
+            LogicalPlan::Window(node) => self.into_optd_window(node)?,
+            LogicalPlan::Union(node) => self.into_optd_union(node)?,
+            LogicalPlan::Distinct(node) => self.into_optd_distinct(node)?,
+            LogicalPlan::Values(node) => self.into_optd_values(node)?.into_plan_node(),
+            LogicalPlan::EmptyRelation(node) => self.into_optd_empty_relation(node)?,
             _ => bail!(
                 "unsupported plan node: {}",
                 format!("{:?}", node).split('\n').next().unwrap()
             ),
         };
+        if !self.scalar_subqueries.is_empty() {
+            bail!("scalar subqueries are only supported in filters and projections")
+        }
         Ok(node)
     }
 
@@ -251,3 +660,95 @@ impl OptdPlanContext<'_> {
         Ok(self.into_optd_plan_node(root_rel)?.into_rel_node())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use datafusion::{
+        arrow::{
+            array::ListArray,
+            datatypes::{Int64Type, IntervalDayTimeType, IntervalMonthDayNanoType},
+        },
+        common::DFSchema,
+        execution::context::SessionContext,
+        logical_expr::{lit, Case, Expr},
+        scalar::ScalarValue,
+    };
+    use optd_datafusion_repr::plan_nodes::{OptRelNode, OptRelNodeTyp};
+
+    use super::into_optd_value;
+    use crate::{from_optd::from_optd_value, OptdPlanContext};
+
+    fn round_trip(value: &ScalarValue) -> ScalarValue {
+        let constant = into_optd_value(value).unwrap();
+        let OptRelNodeTyp::Constant(typ) = constant.clone().into_rel_node().typ else {
+            unreachable!()
+        };
+        from_optd_value(&constant.value(), typ).unwrap()
+    }
+
+    #[test]
+    fn value_round_trip() {
+        let values = [
+            ScalarValue::Boolean(Some(true)),
+            ScalarValue::Int64(Some(-3)),
+            ScalarValue::Int64(None),
+            ScalarValue::Float64(Some(0.5)),
+            ScalarValue::Float64(None),
+            ScalarValue::Utf8(Some("a".to_string())),
+            ScalarValue::Decimal128(Some(150), 15, 2),
+            ScalarValue::Decimal128(None, 15, 2),
+            ScalarValue::Date32(Some(10_000)),
+            ScalarValue::Date32(None),
+            ScalarValue::IntervalYearMonth(Some(14)),
+            ScalarValue::IntervalYearMonth(None),
+            ScalarValue::IntervalDayTime(Some(IntervalDayTimeType::make_value(-2, 3))),
+            ScalarValue::IntervalDayTime(None),
+            ScalarValue::IntervalMonthDayNano(Some(IntervalMonthDayNanoType::make_value(1, -2, 3))),
+            ScalarValue::IntervalMonthDayNano(None),
+        ];
+        let list =
+            ListArray::from_iter_primitive::<Int64Type, _, _>(vec![Some(vec![Some(1), None])]);
+        let values = values
+            .into_iter()
+            .chain([ScalarValue::try_from_array(&list, 0).unwrap()]);
+        for value in values {
+            assert_eq!(round_trip(&value), value);
+        }
+    }
+
+    #[test]
+    fn timestamp_round_trip() {
+        for tz in [None, Some("UTC".into()), Some("+05:00".into())] {
+            for value in [Some(i64::MAX), Some(-1), None] {
+                let values = [
+                    ScalarValue::TimestampSecond(value, tz.clone()),
+                    ScalarValue::TimestampMillisecond(value, tz.clone()),
+                    ScalarValue::TimestampMicrosecond(value, tz.clone()),
+                    ScalarValue::TimestampNanosecond(value, tz.clone()),
+                ];
+                for value in values {
+                    assert_eq!(round_trip(&value), value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn case() {
+        let state = SessionContext::new().state();
+        let mut context = OptdPlanContext::new(&state);
+        let schema = DFSchema::empty();
+        let when = |x: bool| (Box::new(lit(x)), Box::new(lit(1i64)));
+        let otherwise = Some(Box::new(lit(0i64)));
+        let case = Case::new(None, vec![when(true)], otherwise.clone());
+        assert!(context.into_optd_expr(&Expr::Case(case), &schema).is_ok());
+        let unsupported = [
+            Case::new(None, vec![when(true), when(false)], otherwise.clone()),
+            Case::new(Some(Box::new(lit(true))), vec![when(true)], otherwise),
+            Case::new(None, vec![when(true)], None),
+        ];
+        for case in unsupported {
+            assert!(context.into_optd_expr(&Expr::Case(case), &schema).is_err());
+        }
+    }
+}
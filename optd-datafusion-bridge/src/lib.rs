@@ -6,23 +6,25 @@ mod physical_collector;
 
 use async_trait::async_trait;
 use datafusion::{
-    arrow::datatypes::DataType,
     catalog::CatalogList,
-    error::Result,
+    common::DFSchema,
+    error::{DataFusionError, Result},
     execution::context::{QueryPlanner, SessionState},
     logical_expr::{
-        Explain, LogicalPlan, PlanType, StringifiedPlan, TableSource, ToStringifiedPlan,
+        Explain, LogicalPlan, PlanType, StringifiedPlan, Subquery, TableSource, ToStringifiedPlan,
     },
     physical_plan::{displayable, explain::ExplainExec, ExecutionPlan},
     physical_planner::{DefaultPhysicalPlanner, PhysicalPlanner},
 };
+use into_optd::into_optd_type;
 use itertools::Itertools;
+use optd_core::cascades::{ExprCostStatus, GroupId, RuleInfo};
 use optd_datafusion_repr::{
     plan_nodes::{
-        ConstantType, OptRelNode, OptRelNodeRef, OptRelNodeTyp, PhysicalHashJoin,
-        PhysicalNestedLoopJoin, PlanNode,
+        OptRelNode, OptRelNodeRef, OptRelNodeTyp, PhysicalHashJoin, PhysicalNestedLoopJoin,
+        PhysicalSortMergeJoin, PlanNode,
     },
-    properties::schema::Catalog,
+    properties::schema::{Catalog, Field, Schema},
     DatafusionOptimizer,
 };
 use std::{
@@ -34,6 +36,10 @@ struct OptdPlanContext<'a> {
     tables: HashMap<String, Arc<dyn TableSource>>,
     session_state: &'a SessionState,
     pub optimizer: Option<&'a DatafusionOptimizer>,
+    /// The schema of the outer query when converting a subquery, which outer references are resolved against.
+    outer_schema: Option<DFSchema>,
+    /// The scalar subqueries in the expressions being converted, which are evaluated by applies below the plan node.
+    scalar_subqueries: Vec<Subquery>,
 }
 
 impl<'a> OptdPlanContext<'a> {
@@ -42,6 +48,8 @@ impl<'a> OptdPlanContext<'a> {
             tables: HashMap::new(),
             session_state,
             optimizer: None,
+            outer_schema: None,
+            scalar_subqueries: vec![],
         }
     }
 }
@@ -57,25 +65,21 @@ impl DatafusionCatalog {
 }
 
 impl Catalog for DatafusionCatalog {
-    fn get(&self, name: &str) -> optd_datafusion_repr::properties::schema::Schema {
+    fn get(&self, name: &str) -> Schema {
         let catalog = self.catalog.catalog("datafusion").unwrap();
         let schema = catalog.schema("public").unwrap();
         let table = futures_lite::future::block_on(schema.table(name.as_ref())).unwrap();
         let fields = table.schema();
-        let mut optd_schema = vec![];
-        for field in fields.fields() {
-            let dt = match field.data_type() {
-                DataType::Date32 => ConstantType::Date,
-                DataType::Int32 => ConstantType::Int,
-                DataType::Int64 => ConstantType::Int,
-                DataType::Float64 => ConstantType::Decimal,
-                DataType::Utf8 => ConstantType::Utf8String,
-                DataType::Decimal128(_, _) => ConstantType::Decimal,
-                dt => unimplemented!("{:?}", dt),
-            };
-            optd_schema.push(dt);
-        }
-        optd_datafusion_repr::properties::schema::Schema(optd_schema)
+        let optd_schema = fields
+            .fields()
+            .iter()
+            .map(|field| Field {
+                name: field.name().to_string(),
+                typ: into_optd_type(field.data_type()),
+                nullable: field.is_nullable(),
+            })
+            .collect();
+        Schema::new(optd_schema)
     }
 }
 
@@ -88,6 +92,7 @@ enum JoinOrder {
     Table(String),
     HashJoin(Box<Self>, Box<Self>),
     NestedLoopJoin(Box<Self>, Box<Self>),
+    SortMergeJoin(Box<Self>, Box<Self>),
 }
 
 impl JoinOrder {
@@ -102,6 +107,10 @@ impl JoinOrder {
                 Box::new(left.into_logical_join_order()),
                 Box::new(right.into_logical_join_order()),
             ),
+            JoinOrder::SortMergeJoin(left, right) => LogicalJoinOrder::Join(
+                Box::new(left.into_logical_join_order()),
+                Box::new(right.into_logical_join_order()),
+            ),
         }
     }
 }
@@ -126,6 +135,12 @@ fn get_join_order(rel_node: OptRelNodeRef) -> Option<JoinOrder> {
             let right = get_join_order(join.right().into_rel_node())?;
             Some(JoinOrder::NestedLoopJoin(Box::new(left), Box::new(right)))
         }
+        OptRelNodeTyp::PhysicalSortMergeJoin(_) => {
+            let join = PhysicalSortMergeJoin::from_rel_node(rel_node.clone()).unwrap();
+            let left = get_join_order(join.left().into_rel_node())?;
+            let right = get_join_order(join.right().into_rel_node())?;
+            Some(JoinOrder::SortMergeJoin(Box::new(left), Box::new(right)))
+        }
         OptRelNodeTyp::PhysicalScan => {
             let scan =
                 optd_datafusion_repr::plan_nodes::PhysicalScan::from_rel_node(rel_node).unwrap();
@@ -163,8 +178,47 @@ impl std::fmt::Display for JoinOrder {
             JoinOrder::NestedLoopJoin(left, right) => {
                 write!(f, "(NLJ {} {})", left, right)
             }
+            JoinOrder::SortMergeJoin(left, right) => {
+                write!(f, "(SMJ {} {})", left, right)
+            }
+        }
+    }
+}
+
+/// Explain why the winner of each plan node in the optimized plan is chosen over the other physical expressions.
+fn explain_choice(optimizer: &DatafusionOptimizer, group_id: GroupId) -> String {
+    let optimizer = optimizer.optd_optimizer();
+    let cost = optimizer.cost();
+    let mut lines = vec![];
+    for (group_id, choices) in optimizer.explain_choice_plan(group_id) {
+        if !choices.iter().any(|x| x.typ.is_plan_node()) {
+            continue;
+        }
+        lines.push(format!("group {}", group_id));
+        for choice in choices {
+            let status = match &choice.status {
+                Some(ExprCostStatus::Costed(x)) => {
+                    let mut status = format!("cost={{{}}}", cost.explain(x));
+                    if choice.is_winner {
+                        status += " winner";
+                    } else if let Some(diff) = choice.cost_over_winner {
+                        status += &format!(" +{:.2} over winner", diff);
+                    }
+                    status
+                }
+                Some(ExprCostStatus::Pruned(x)) => {
+                    format!("pruned at cost={{{}}}", cost.explain(x))
+                }
+                Some(ExprCostStatus::Impossible) => "impossible".to_string(),
+                None => "not costed".to_string(),
+            };
+            lines.push(format!(
+                "  expr {} {} {}",
+                choice.expr_id, choice.expr, status
+            ));
         }
     }
+    lines.join("\n")
 }
 
 impl OptdQueryPlanner {
@@ -186,22 +240,52 @@ impl OptdQueryPlanner {
             .enable_adaptive(false);
     }
 
+    pub fn rules_info(&self) -> Vec<RuleInfo> {
+        self.optimizer
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .optd_optimizer()
+            .rules_info()
+    }
+
+    pub fn enable_rule(&self, name: &str) -> anyhow::Result<()> {
+        self.optimizer
+            .lock()
+            .unwrap()
+            .as_mut()
+            .unwrap()
+            .optd_optimizer_mut()
+            .enable_rule_by_name(name)
+    }
+
+    pub fn disable_rule(&self, name: &str) -> anyhow::Result<()> {
+        self.optimizer
+            .lock()
+            .unwrap()
+            .as_mut()
+            .unwrap()
+            .optd_optimizer_mut()
+            .disable_rule_by_name(name)
+    }
+
     async fn create_physical_plan_inner(
         &self,
         logical_plan: &LogicalPlan,
         session_state: &SessionState,
     ) -> anyhow::Result<Arc<dyn ExecutionPlan>> {
-        if let LogicalPlan::Dml(_) | LogicalPlan::Ddl(_) | LogicalPlan::EmptyRelation(_) =
-            logical_plan
-        {
+        if let LogicalPlan::Dml(_) | LogicalPlan::Ddl(_) = logical_plan {
             let planner = DefaultPhysicalPlanner::default();
             return Ok(planner
                 .create_physical_plan(logical_plan, session_state)
                 .await?);
         }
-        let (mut explains, logical_plan) = match logical_plan {
-            LogicalPlan::Explain(Explain { plan, .. }) => (Some(Vec::new()), plan.as_ref()),
-            _ => (None, logical_plan),
+        let (mut explains, verbose, logical_plan) = match logical_plan {
+            LogicalPlan::Explain(Explain { plan, verbose, .. }) => {
+                (Some(Vec::new()), *verbose, plan.as_ref())
+            }
+            _ => (None, false, logical_plan),
         };
         let mut ctx = OptdPlanContext::new(session_state);
         if let Some(explains) = &mut explains {
@@ -221,7 +305,13 @@ impl OptdQueryPlanner {
             ));
         }
         let mut optimizer = self.optimizer.lock().unwrap().take().unwrap();
-        let (group_id, optimized_rel) = optimizer.optimize(optd_rel)?;
+        let (group_id, optimized_rel) = match optimizer.optimize(optd_rel) {
+            Ok(result) => result,
+            Err(err) => {
+                self.optimizer.lock().unwrap().replace(optimizer);
+                return Err(err);
+            }
+        };
         if let Some(explains) = &mut explains {
             explains.push(StringifiedPlan::new(
                 PlanType::OptimizedPhysicalPlan {
@@ -242,6 +332,14 @@ impl OptdQueryPlanner {
                     "None".to_string()
                 },
             ));
+            if verbose {
+                explains.push(StringifiedPlan::new(
+                    PlanType::OptimizedPhysicalPlan {
+                        optimizer_name: "optd-explain-choice".to_string(),
+                    },
+                    explain_choice(&optimizer, group_id),
+                ));
+            }
             let bindings = optimizer
                 .optd_optimizer()
                 .get_all_group_physical_bindings(group_id);
@@ -273,14 +371,15 @@ impl OptdQueryPlanner {
         // );
         // optimizer.dump(Some(group_id));
         ctx.optimizer = Some(&optimizer);
-        let physical_plan = ctx.from_optd(optimized_rel).await?;
+        let physical_plan = ctx.from_optd(optimized_rel).await;
+        self.optimizer.lock().unwrap().replace(optimizer);
+        let physical_plan = physical_plan?;
         if let Some(explains) = &mut explains {
             explains.push(
                 displayable(&*physical_plan)
                     .to_stringified(false, datafusion::logical_expr::PlanType::FinalPhysicalPlan),
             );
         }
-        self.optimizer.lock().unwrap().replace(optimizer);
         if let Some(explains) = explains {
             Ok(Arc::new(ExplainExec::new(
                 LogicalPlan::explain_schema(),
@@ -306,9 +405,8 @@ impl QueryPlanner for OptdQueryPlanner {
         logical_plan: &LogicalPlan,
         session_state: &SessionState,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        Ok(self
-            .create_physical_plan_inner(logical_plan, session_state)
+        self.create_physical_plan_inner(logical_plan, session_state)
             .await
-            .unwrap())
+            .map_err(|err| DataFusionError::External(err.into()))
     }
 }
@@ -2,14 +2,11 @@ use std::sync::Arc;
 
 use optd_core::{
     cascades::CascadesOptimizer, heuristics::HeuristicsOptimizer, optimizer::Optimizer,
-    rel_node::Value,
+    rel_node::RelNode,
 };
 use optd_datafusion_repr::{
     cost::OptCostModel,
-    plan_nodes::{
-        BinOpExpr, BinOpType, ColumnRefExpr, ConstantExpr, JoinType, LogicalFilter, LogicalJoin,
-        LogicalScan, OptRelNode, OptRelNodeTyp, PlanNode,
-    },
+    plan_nodes::{JoinType, OptRelNode, OptRelNodeTyp, PlanNode},
     rules::{HashJoinRule, JoinAssocRule, JoinCommuteRule, PhysicalConversionRule},
 };
 
@@ -31,7 +28,7 @@ pub fn main() {
                 JoinType::Inner,
             ))),
             Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Filter)),
-            Arc::new(HashJoinRule::new()),
+            Arc::new(HashJoinRule::new(JoinType::Inner)),
         ],
         Box::new(OptCostModel::new(
             [("t1", 1000), ("t2", 100), ("t3", 10000)]
@@ -43,21 +40,21 @@ pub fn main() {
     );
 
     // The plan: (filter (scan t1) #1=2) join (scan t2) join (scan t3)
-    let scan1 = LogicalScan::new("t1".into());
-    let filter_cond = BinOpExpr::new(
-        ColumnRefExpr::new(1).0,
-        ConstantExpr::new(Value::Int(2)).0,
-        BinOpType::Eq,
-    );
-    let filter1 = LogicalFilter::new(scan1.0, filter_cond.0);
-    let scan2 = LogicalScan::new("t2".into());
-    let join_cond = ConstantExpr::new(Value::Bool(true));
-    let scan3 = LogicalScan::new("t3".into());
-    let join_filter = LogicalJoin::new(filter1.0, scan2.0, join_cond.clone().0, JoinType::Inner);
-    let fnal = LogicalJoin::new(scan3.0, join_filter.0, join_cond.0, JoinType::Inner);
-    let node = optimizer.optimize(fnal.0.clone().into_rel_node());
+    let fnal: RelNode<OptRelNodeTyp> = r#"
+        (Join(Inner)
+            (Scan "t3")
+            (Join(Inner)
+                (Filter (Scan "t1") (BinOp(Eq) (ColumnRef 1) (Constant(Int) 2)))
+                (Scan "t2")
+                (Constant(Bool) true))
+            (Constant(Bool) true))
+    "#
+    .parse()
+    .unwrap();
+    let fnal = Arc::new(fnal);
+    let node = optimizer.optimize(fnal.clone());
     optimizer.dump(None);
-    let node: Arc<optd_core::rel_node::RelNode<OptRelNodeTyp>> = node.unwrap();
+    let node = node.unwrap();
     println!(
         "cost={}",
         optimizer
@@ -78,11 +75,11 @@ pub fn main() {
                 JoinType::Inner,
             ))),
             Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Filter)),
-            Arc::new(HashJoinRule::new()),
+            Arc::new(HashJoinRule::new(JoinType::Inner)),
         ],
         optd_core::heuristics::ApplyOrder::BottomUp,
     );
-    let node = optimizer.optimize(fnal.0.into_rel_node()).unwrap();
+    let node = optimizer.optimize(fnal).unwrap();
     println!(
         "{}",
         PlanNode::from_rel_node(node).unwrap().explain_to_string()
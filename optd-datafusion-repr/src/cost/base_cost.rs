@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::plan_nodes::OptRelNodeTyp;
+use crate::plan_nodes::{decode_limit, decode_values, OptRelNodeTyp, UnionType};
 use itertools::Itertools;
 use optd_core::{
     cascades::RelNodeContext,
@@ -98,14 +98,19 @@ impl CostModel<OptRelNodeTyp> for OptCostModel {
     ) -> Cost {
         match node {
             OptRelNodeTyp::PhysicalScan => {
-                let table_name = data.as_ref().unwrap().as_str();
-                let row_cnt = self
-                    .table_stat
-                    .get(table_name.as_ref())
-                    .copied()
+                let row_cnt = data
+                    .as_ref()
+                    .and_then(|x| x.as_str())
+                    .and_then(|table_name| self.table_stat.get(table_name.as_ref()).copied())
                     .unwrap_or(1) as f64;
                 Self::cost(row_cnt, 0.0, row_cnt)
             }
+            OptRelNodeTyp::PhysicalValues => {
+                let (row_cnt, _) = decode_values(data.as_ref()).unwrap_or_default();
+                let (_, compute_cost, _) = Self::cost_tuple(&children[0]);
+                Self::cost((row_cnt as f64).max(1.0), compute_cost, 0.0)
+            }
+            OptRelNodeTyp::PhysicalEmptyRelation => Self::cost(1.0, 0.0, 0.0),
             OptRelNodeTyp::PhysicalFilter => {
                 let (row_cnt, _, _) = Self::cost_tuple(&children[0]);
                 let (_, compute_cost, _) = Self::cost_tuple(&children[1]);
@@ -135,9 +140,28 @@ impl CostModel<OptRelNodeTyp> for OptCostModel {
             OptRelNodeTyp::PhysicalHashJoin(_) => {
                 let (row_cnt_1, _, _) = Self::cost_tuple(&children[0]);
                 let (row_cnt_2, _, _) = Self::cost_tuple(&children[1]);
+                let (_, residual_cost, _) = Self::cost_tuple(&children[4]);
+                let row_cnt = row_cnt_1.min(row_cnt_2).max(1.0);
+                Self::cost(
+                    row_cnt,
+                    row_cnt_1 * 2.0 + row_cnt_2 + row_cnt * residual_cost,
+                    0.0,
+                )
+            }
+            OptRelNodeTyp::PhysicalSortMergeJoin(_) => {
+                let (row_cnt_1, _, _) = Self::cost_tuple(&children[0]);
+                let (row_cnt_2, _, _) = Self::cost_tuple(&children[1]);
+                let (_, residual_cost, _) = Self::cost_tuple(&children[4]);
+                let row_cnt = row_cnt_1.min(row_cnt_2).max(1.0);
+                // both sides are sorted on the keys before merging
+                let sort_cost = |row_cnt: f64| row_cnt * row_cnt.ln_1p().max(1.0);
                 Self::cost(
-                    row_cnt_1.min(row_cnt_2).max(1.0),
-                    row_cnt_1 * 2.0 + row_cnt_2,
+                    row_cnt,
+                    sort_cost(row_cnt_1)
+                        + sort_cost(row_cnt_2)
+                        + row_cnt_1
+                        + row_cnt_2
+                        + row_cnt * residual_cost,
                     0.0,
                 )
             }
@@ -146,11 +170,58 @@ impl CostModel<OptRelNodeTyp> for OptCostModel {
                 let (row_cnt, _, _) = Self::cost_tuple(&children[0]);
                 Self::cost(row_cnt, row_cnt * row_cnt.ln_1p().max(1.0), 0.0)
             }
+            OptRelNodeTyp::PhysicalLimit => {
+                let (row_cnt, _, _) = Self::cost_tuple(&children[0]);
+                let (skip, fetch) = decode_limit(data.as_ref()).unwrap_or_default();
+                // the limit stops reading its child after `skip + fetch` rows
+                let read_cnt = fetch.map_or(row_cnt, |x| row_cnt.min((skip + x) as f64));
+                let row_cnt = (read_cnt - skip as f64).max(1.0);
+                Self::cost(row_cnt, read_cnt, 0.0)
+            }
+            OptRelNodeTyp::PhysicalTopN => {
+                let (row_cnt, _, _) = Self::cost_tuple(&children[0]);
+                let (skip, fetch) = decode_limit(data.as_ref()).unwrap_or_default();
+                // only the first `skip + fetch` rows are kept in a heap instead of sorting all rows
+                let heap_size = fetch.map_or(row_cnt, |x| row_cnt.min((skip + x) as f64));
+                Self::cost(
+                    (heap_size - skip as f64).max(1.0),
+                    row_cnt * heap_size.ln_1p().max(1.0),
+                    0.0,
+                )
+            }
+            OptRelNodeTyp::PhysicalUnion(union_type) => {
+                let (row_cnt_1, _, _) = Self::cost_tuple(&children[0]);
+                let (row_cnt_2, _, _) = Self::cost_tuple(&children[1]);
+                let row_cnt = row_cnt_1 + row_cnt_2;
+                // removing the duplicates hashes all rows
+                let compute_cost = match union_type {
+                    UnionType::All => 0.0,
+                    UnionType::Distinct => row_cnt,
+                };
+                Self::cost(row_cnt, compute_cost, 0.0)
+            }
             OptRelNodeTyp::PhysicalAgg => {
                 let (row_cnt, _, _) = Self::cost_tuple(&children[0]);
                 let (_, compute_cost_1, _) = Self::cost_tuple(&children[1]);
-                let (_, compute_cost_2, _) = Self::cost_tuple(&children[2]);
-                Self::cost(row_cnt, row_cnt * (compute_cost_1 + compute_cost_2), 0.0)
+                let (group_cnt, compute_cost_2, _) = Self::cost_tuple(&children[2]);
+                // each group column is assumed to leave a tenth of the rows as distinct groups, and an aggregation
+                // without group columns outputs a single row
+                let selectivity = 1.0 - 0.9f64.powi(group_cnt as i32);
+                Self::cost(
+                    (row_cnt * selectivity).max(1.0),
+                    row_cnt * (compute_cost_1 + compute_cost_2),
+                    0.0,
+                )
+            }
+            OptRelNodeTyp::PhysicalWindow => {
+                let (row_cnt, _, _) = Self::cost_tuple(&children[0]);
+                let (_, compute_cost, _) = Self::cost_tuple(&children[1]);
+                // the child is sorted on the partition and order keys before evaluating the window functions
+                Self::cost(
+                    row_cnt,
+                    row_cnt * row_cnt.ln_1p().max(1.0) + row_cnt * compute_cost,
+                    0.0,
+                )
             }
             OptRelNodeTyp::List => {
                 let compute_cost = children
@@ -160,7 +231,8 @@ impl CostModel<OptRelNodeTyp> for OptCostModel {
                         compute_cost
                     })
                     .sum::<f64>();
-                Self::cost(1.0, compute_cost + 0.01, 0.0)
+                // the row count of a list is the number of its elements
+                Self::cost(children.len() as f64, compute_cost + 0.01, 0.0)
             }
             OptRelNodeTyp::ColumnRef => Self::cost(1.0, 0.01, 0.0),
             _ if node.is_expression() => {
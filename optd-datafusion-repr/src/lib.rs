@@ -4,11 +4,24 @@ use std::sync::Arc;
 
 use anyhow::Result;
 use cost::{AdaptiveCostModel, RuntimeAdaptionStorage};
-use optd_core::cascades::{CascadesOptimizer, GroupId, OptimizerProperties};
+use optd_core::cascades::{CascadesOptimizer, GroupId, OptimizerPhase, OptimizerProperties};
+use optd_core::property::PropertyBuilderAny;
 use plan_nodes::{OptRelNode, OptRelNodeRef, OptRelNodeTyp, PlanNode};
-use properties::schema::{Catalog, SchemaPropertyBuilder};
+use properties::{
+    column_equivalence::ColumnEquivalencePropertyBuilder,
+    functional_dependency::FunctionalDependencyPropertyBuilder,
+    outer_references::OuterReferencesPropertyBuilder,
+    schema::{Catalog, SchemaPropertyBuilder},
+};
 use rules::{
-    HashJoinRule, JoinAssocRule, JoinCommuteRule, PhysicalConversionRule, ProjectionPullUpJoin,
+    AggInputPruningRule, ApplyFilterPullUpRule, ApplyProjectionPullUpRule, ApplyScalarAggRule,
+    ApplyToJoinRule, DistinctToAggRule, EagerAggregationRule, EliminateFilterRule,
+    EliminateProjectionRule, EmptyRelationPropagationRule, FilterAggTransposeRule,
+    FilterJoinPushdownRule, FilterMergeRule, FilterProjectionTransposeRule,
+    FilterUnionTransposeRule, HashJoinRule, JoinAssocRule, JoinCommuteRule, JoinCondPushdownRule,
+    LeftJoinAssocRule, LeftJoinCommuteRule, OuterJoinToInnerRule, PhysicalConversionRule,
+    ProjectionFilterTransposeRule, ProjectionJoinTransposeRule, ProjectionMergeRule,
+    ProjectionPullUpJoin, ProjectionUnionTransposeRule, SortMergeJoinRule, TopNRule,
 };
 
 pub use adaptive::PhysicalCollector;
@@ -19,6 +32,19 @@ pub mod cost;
 pub mod plan_nodes;
 pub mod properties;
 pub mod rules;
+#[cfg(test)]
+mod testing;
+
+/// The default memory budget of the memo table (in bytes), see `DatafusionOptimizer::set_memo_memory_cap`.
+pub const DEFAULT_MEMO_MEMORY_CAP: usize = 256 << 20;
+
+/// The rules left out of the first phase of `DatafusionOptimizer::enable_join_reorder_phases`.
+const JOIN_REORDER_RULES: [&str; 4] = [
+    "join_commute_rule",
+    "join_assoc_rule",
+    "left_join_commute_rule",
+    "left_join_assoc_rule",
+];
 
 pub struct DatafusionOptimizer {
     optimizer: CascadesOptimizer<OptRelNodeTyp>,
@@ -26,11 +52,67 @@ pub struct DatafusionOptimizer {
     enable_adaptive: bool,
 }
 
+/// The property builders of the optimizer, in the order of the property indices: the schema, the column equivalence
+/// classes, the functional dependencies and the outer references.
+fn property_builders(catalog: Box<dyn Catalog>) -> Vec<Box<dyn PropertyBuilderAny<OptRelNodeTyp>>> {
+    let catalog: Arc<dyn Catalog> = catalog.into();
+    vec![
+        Box::new(SchemaPropertyBuilder::new(Box::new(catalog.clone()))),
+        Box::new(ColumnEquivalencePropertyBuilder::new(Box::new(
+            catalog.clone(),
+        ))),
+        Box::new(FunctionalDependencyPropertyBuilder::new(Box::new(catalog))),
+        Box::new(OuterReferencesPropertyBuilder::new()),
+    ]
+}
+
 impl DatafusionOptimizer {
     pub fn enable_adaptive(&mut self, enable: bool) {
         self.enable_adaptive = enable;
     }
 
+    /// Set the memory budget of the memo table (in bytes). Least recently used groups are evicted after each
+    /// optimization if the memo table exceeds the budget. `None` disables the eviction.
+    pub fn set_memo_memory_cap(&mut self, memory_cap: Option<usize>) {
+        self.optimizer.prop.memo_memory_cap = memory_cap;
+    }
+
+    /// Run the optimizer in two phases. The first phase applies all rules except the join reordering rules without an
+    /// exploration budget, which quickly produces a plan with the join order of the query. The second phase applies
+    /// all rules within the exploration budget, starting from the memo table of the first phase.
+    pub fn enable_join_reorder_phases(&mut self, enable: bool) {
+        if !enable {
+            self.optimizer.prop.phases = vec![];
+            return;
+        }
+        let all_rules = self
+            .optimizer
+            .rules_info()
+            .into_iter()
+            .map(|x| x.name.to_string())
+            .collect::<Vec<_>>();
+        self.optimizer.prop.phases = vec![
+            OptimizerPhase {
+                name: "heuristics".to_string(),
+                rules: all_rules
+                    .iter()
+                    .filter(|x| !JOIN_REORDER_RULES.contains(&x.as_str()))
+                    .cloned()
+                    .collect(),
+                partial_explore_iter: None,
+                partial_explore_space: None,
+                stop_if_cost_below: None,
+            },
+            OptimizerPhase {
+                name: "join_reorder".to_string(),
+                rules: all_rules,
+                partial_explore_iter: self.optimizer.prop.partial_explore_iter,
+                partial_explore_space: self.optimizer.prop.partial_explore_space,
+                stop_if_cost_below: None,
+            },
+        ];
+    }
+
     pub fn optd_optimizer(&self) -> &CascadesOptimizer<OptRelNodeTyp> {
         &self.optimizer
     }
@@ -42,20 +124,50 @@ impl DatafusionOptimizer {
     /// Create an optimizer with default settings: adaptive + partial explore.
     pub fn new_physical(catalog: Box<dyn Catalog>) -> Self {
         let mut rules = PhysicalConversionRule::all_conversions();
-        rules.push(Arc::new(HashJoinRule::new()));
+        rules.extend(HashJoinRule::all_join_types());
+        rules.extend(SortMergeJoinRule::all_join_types());
+        rules.push(Arc::new(TopNRule::new()));
         rules.push(Arc::new(JoinCommuteRule::new()));
         rules.push(Arc::new(JoinAssocRule::new()));
         rules.push(Arc::new(ProjectionPullUpJoin::new()));
+        rules.push(Arc::new(LeftJoinCommuteRule::new()));
+        rules.push(Arc::new(LeftJoinAssocRule::new()));
+        rules.extend(OuterJoinToInnerRule::all_join_types());
+        rules.extend(ApplyToJoinRule::all_apply_types());
+        rules.extend(ApplyFilterPullUpRule::all_apply_types());
+        rules.extend(ApplyProjectionPullUpRule::all_apply_types());
+        rules.extend(ApplyScalarAggRule::all_apply_types());
+        rules.push(Arc::new(FilterMergeRule::new()));
+        rules.push(Arc::new(FilterJoinPushdownRule::new()));
+        rules.push(Arc::new(JoinCondPushdownRule::new()));
+        rules.push(Arc::new(FilterProjectionTransposeRule::new()));
+        rules.push(Arc::new(FilterAggTransposeRule::new()));
+        rules.extend(FilterUnionTransposeRule::all_union_types());
+        rules.push(Arc::new(ProjectionMergeRule::new()));
+        rules.push(Arc::new(EliminateProjectionRule::new()));
+        rules.push(Arc::new(ProjectionFilterTransposeRule::new()));
+        rules.push(Arc::new(ProjectionJoinTransposeRule::new()));
+        rules.push(Arc::new(ProjectionUnionTransposeRule::new()));
+        rules.push(Arc::new(AggInputPruningRule::new()));
+        rules.push(Arc::new(EagerAggregationRule::new()));
+        rules.push(Arc::new(DistinctToAggRule::new()));
+        rules.push(Arc::new(EliminateFilterRule::new()));
+        rules.extend(EmptyRelationPropagationRule::all_rules());
         let cost_model = AdaptiveCostModel::new(50);
         Self {
             runtime_statistics: cost_model.get_runtime_map(),
             optimizer: CascadesOptimizer::new_with_prop(
                 rules,
                 Box::new(cost_model),
-                vec![Box::new(SchemaPropertyBuilder::new(catalog))],
+                property_builders(catalog),
                 OptimizerProperties {
                     partial_explore_iter: Some(1 << 20),
                     partial_explore_space: Some(1 << 10),
+                    pareto: None,
+                    explore_scalar_expressions: false,
+                    memo_memory_cap: Some(DEFAULT_MEMO_MEMORY_CAP),
+                    phases: vec![],
+                    exploration_seed: None,
                 },
             ),
             enable_adaptive: true,
@@ -65,17 +177,15 @@ impl DatafusionOptimizer {
     /// The optimizer settings for three-join demo as a perfect optimizer.
     pub fn new_alternative_physical_for_demo(catalog: Box<dyn Catalog>) -> Self {
         let mut rules = PhysicalConversionRule::all_conversions();
-        rules.push(Arc::new(HashJoinRule::new()));
+        rules.extend(HashJoinRule::all_join_types());
         rules.insert(0, Arc::new(JoinCommuteRule::new()));
         rules.insert(1, Arc::new(JoinAssocRule::new()));
         rules.insert(2, Arc::new(ProjectionPullUpJoin::new()));
+        rules.push(Arc::new(DistinctToAggRule::new()));
         let cost_model = AdaptiveCostModel::new(1000); // very large decay
         let runtime_statistics = cost_model.get_runtime_map();
-        let optimizer = CascadesOptimizer::new(
-            rules,
-            Box::new(cost_model),
-            vec![Box::new(SchemaPropertyBuilder::new(catalog))],
-        );
+        let optimizer =
+            CascadesOptimizer::new(rules, Box::new(cost_model), property_builders(catalog));
         Self {
             runtime_statistics,
             optimizer,
@@ -106,6 +216,16 @@ impl DatafusionOptimizer {
                     rel_node
                 })?;
 
+        if self.enable_adaptive {
+            let evicted = self.optimizer.step_gc(group_id);
+            if !evicted.is_empty() {
+                let mut guard = self.runtime_statistics.lock().unwrap();
+                for group_id in evicted {
+                    guard.history.remove(&group_id);
+                }
+            }
+        }
+
         Ok((group_id, optimized_rel))
     }
 
@@ -113,3 +233,88 @@ impl DatafusionOptimizer {
         self.optimizer.dump(group_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use optd_core::{
+        cascades::GroupId,
+        rel_node::{RelNode, RelNodeTyp},
+    };
+
+    use crate::{
+        plan_nodes::{OptRelNode, OptRelNodeTyp, PhysicalScan},
+        testing::TestCatalog,
+        DatafusionOptimizer, JOIN_REORDER_RULES,
+    };
+
+    fn is_physical(node: &RelNode<OptRelNodeTyp>) -> bool {
+        !node.typ.is_logical() && node.children.iter().all(|x| is_physical(x))
+    }
+
+    /// The join order of a physical plan, such as `((t1 t2) t3)`.
+    fn join_order(node: &RelNode<OptRelNodeTyp>) -> String {
+        match node.typ {
+            OptRelNodeTyp::PhysicalScan => PhysicalScan::from_rel_node(node.clone().into())
+                .unwrap()
+                .table()
+                .to_string(),
+            OptRelNodeTyp::PhysicalNestedLoopJoin(_) | OptRelNodeTyp::PhysicalHashJoin(_) => {
+                format!(
+                    "({} {})",
+                    join_order(&node.children[0]),
+                    join_order(&node.children[1])
+                )
+            }
+            _ => join_order(&node.children[0]),
+        }
+    }
+
+    /// The join orders of all physical plans of a group.
+    fn join_orders(optimizer: &DatafusionOptimizer, group_id: GroupId) -> BTreeSet<String> {
+        optimizer
+            .optd_optimizer()
+            .get_all_group_physical_bindings(group_id)
+            .iter()
+            .map(|x| join_order(x))
+            .collect()
+    }
+
+    const PLAN: &str = r#"(Join(Inner) (Join(Inner) (Scan "t1") (Scan "t2") (Constant(Bool) true)) (Scan "t3") (Constant(Bool) true))"#;
+
+    #[test]
+    fn join_reorder_phases() {
+        let mut optimizer = DatafusionOptimizer::new_physical(Box::new(TestCatalog));
+        for rule in JOIN_REORDER_RULES {
+            assert!(
+                optimizer.optd_optimizer().rule_id(rule).is_some(),
+                "{}",
+                rule
+            );
+        }
+        optimizer.enable_join_reorder_phases(true);
+        assert_eq!(optimizer.optd_optimizer().prop.phases.len(), 2);
+
+        // the first phase keeps the join order of the query
+        let phases = optimizer.optd_optimizer().prop.phases.clone();
+        optimizer.optd_optimizer_mut().prop.phases = phases[..1].to_vec();
+        let (group_id, optimized) = optimizer
+            .optimize(PLAN.parse::<RelNode<_>>().unwrap().into())
+            .unwrap();
+        assert!(is_physical(&optimized));
+        let first_phase = join_orders(&optimizer, group_id);
+        assert_eq!(first_phase, BTreeSet::from(["((t1 t2) t3)".to_string()]));
+
+        // the second phase adds the reordered joins
+        optimizer.optd_optimizer_mut().prop.phases = phases;
+        let (group_id, optimized) = optimizer
+            .optimize(PLAN.parse::<RelNode<_>>().unwrap().into())
+            .unwrap();
+        assert!(is_physical(&optimized));
+        let both_phases = join_orders(&optimizer, group_id);
+        assert!(both_phases.is_superset(&first_phase));
+        assert!(both_phases.contains("(t3 (t1 t2))"));
+        assert!(both_phases.contains("(t1 (t2 t3))"));
+    }
+}
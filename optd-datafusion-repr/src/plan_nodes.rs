@@ -5,30 +5,44 @@ mod apply;
 mod expr;
 mod filter;
 mod join;
+mod limit;
 pub(super) mod macros;
 mod projection;
 mod scan;
 mod sort;
+mod union;
+mod values;
+mod window;
 
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc};
 
 use optd_core::{
     cascades::{CascadesOptimizer, GroupId},
     rel_node::{RelNode, RelNodeRef, RelNodeTyp},
 };
 
-pub use agg::{LogicalAgg, PhysicalAgg};
+pub use agg::{LogicalAgg, LogicalDistinct, PhysicalAgg};
 pub use apply::{ApplyType, LogicalApply};
 pub use expr::{
-    BinOpExpr, BinOpType, ColumnRefExpr, ConstantExpr, ConstantType, ExprList, FuncExpr, FuncType,
-    LogOpExpr, LogOpType, SortOrderExpr, SortOrderType, UnOpExpr, UnOpType,
+    decode_column_index, BinOpExpr, BinOpType, ColumnRefExpr, ConstantExpr, ConstantType, ExprList,
+    ExternColumnRefExpr, FuncExpr, FuncType, IntervalKind, LogOpExpr, LogOpType, SortOrderExpr,
+    SortOrderType, Timezone, UnOpExpr, UnOpType,
 };
 pub use filter::{LogicalFilter, PhysicalFilter};
-pub use join::{JoinType, LogicalJoin, PhysicalHashJoin, PhysicalNestedLoopJoin};
+pub use join::{
+    JoinType, LogicalJoin, PhysicalHashJoin, PhysicalNestedLoopJoin, PhysicalSortMergeJoin,
+};
+pub use limit::{decode_limit, LogicalLimit, PhysicalLimit, PhysicalTopN};
 use pretty_xmlish::{Pretty, PrettyConfig};
 pub use projection::{LogicalProjection, PhysicalProjection};
 pub use scan::{LogicalScan, PhysicalScan};
 pub use sort::{LogicalSort, PhysicalSort};
+pub use union::{LogicalUnion, PhysicalUnion, UnionType};
+pub use values::{
+    decode_empty_relation, decode_values, LogicalEmptyRelation, LogicalValues,
+    PhysicalEmptyRelation, PhysicalValues,
+};
+pub use window::{LogicalWindow, PhysicalWindow};
 
 use crate::{
     adaptive::PhysicalCollector,
@@ -48,6 +62,12 @@ pub enum OptRelNodeTyp {
     Sort,
     Agg,
     Apply(ApplyType),
+    Limit,
+    Union(UnionType),
+    Window,
+    Distinct,
+    Values,
+    EmptyRelation,
     // Physical plan nodes
     PhysicalProjection,
     PhysicalFilter,
@@ -56,10 +76,19 @@ pub enum OptRelNodeTyp {
     PhysicalAgg,
     PhysicalHashJoin(JoinType),
     PhysicalNestedLoopJoin(JoinType),
+    PhysicalSortMergeJoin(JoinType),
+    PhysicalLimit,
+    PhysicalTopN,
+    PhysicalUnion(UnionType),
+    PhysicalWindow,
+    PhysicalValues,
+    PhysicalEmptyRelation,
     PhysicalCollector(GroupId), // only produced after optimization is done
     // Expressions
     Constant(ConstantType),
     ColumnRef,
+    /// A reference to a column of the left child of the enclosing `Apply`, which is only valid in its right child.
+    ExternColumnRef,
     UnOp(UnOpType),
     BinOp(BinOpType),
     LogOp(LogOpType),
@@ -78,6 +107,12 @@ impl OptRelNodeTyp {
                 | Self::Apply(_)
                 | Self::Sort
                 | Self::Agg
+                | Self::Limit
+                | Self::Union(_)
+                | Self::Window
+                | Self::Distinct
+                | Self::Values
+                | Self::EmptyRelation
                 | Self::PhysicalProjection
                 | Self::PhysicalFilter
                 | Self::PhysicalNestedLoopJoin(_)
@@ -85,6 +120,13 @@ impl OptRelNodeTyp {
                 | Self::PhysicalSort
                 | Self::PhysicalAgg
                 | Self::PhysicalHashJoin(_)
+                | Self::PhysicalSortMergeJoin(_)
+                | Self::PhysicalLimit
+                | Self::PhysicalTopN
+                | Self::PhysicalUnion(_)
+                | Self::PhysicalWindow
+                | Self::PhysicalValues
+                | Self::PhysicalEmptyRelation
                 | Self::PhysicalCollector(_)
         )
     }
@@ -94,6 +136,7 @@ impl OptRelNodeTyp {
             self,
             Self::Constant(_)
                 | Self::ColumnRef
+                | Self::ExternColumnRef
                 | Self::UnOp(_)
                 | Self::BinOp(_)
                 | Self::Func(_)
@@ -109,6 +152,63 @@ impl std::fmt::Display for OptRelNodeTyp {
     }
 }
 
+impl FromStr for OptRelNodeTyp {
+    type Err = anyhow::Error;
+
+    /// Parses the `Display` output of the node type, e.g., `Join(Inner)`. Group placeholders are not parsed here, as
+    /// they are written as `!<group_id>` in the textual plan format.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (name, arg) = match s.split_once('(') {
+            Some((name, arg)) => {
+                let Some(arg) = arg.strip_suffix(')') else {
+                    anyhow::bail!("unbalanced parentheses in `{}`", s)
+                };
+                (name, Some(arg))
+            }
+            None => (s, None),
+        };
+        Ok(match (name, arg) {
+            ("List", None) => Self::List,
+            ("Projection", None) => Self::Projection,
+            ("Filter", None) => Self::Filter,
+            ("Scan", None) => Self::Scan,
+            ("Join", Some(x)) => Self::Join(x.parse()?),
+            ("Sort", None) => Self::Sort,
+            ("Agg", None) => Self::Agg,
+            ("Apply", Some(x)) => Self::Apply(x.parse()?),
+            ("Limit", None) => Self::Limit,
+            ("Union", Some(x)) => Self::Union(x.parse()?),
+            ("Window", None) => Self::Window,
+            ("Distinct", None) => Self::Distinct,
+            ("Values", None) => Self::Values,
+            ("EmptyRelation", None) => Self::EmptyRelation,
+            ("PhysicalProjection", None) => Self::PhysicalProjection,
+            ("PhysicalFilter", None) => Self::PhysicalFilter,
+            ("PhysicalScan", None) => Self::PhysicalScan,
+            ("PhysicalSort", None) => Self::PhysicalSort,
+            ("PhysicalAgg", None) => Self::PhysicalAgg,
+            ("PhysicalHashJoin", Some(x)) => Self::PhysicalHashJoin(x.parse()?),
+            ("PhysicalNestedLoopJoin", Some(x)) => Self::PhysicalNestedLoopJoin(x.parse()?),
+            ("PhysicalSortMergeJoin", Some(x)) => Self::PhysicalSortMergeJoin(x.parse()?),
+            ("PhysicalLimit", None) => Self::PhysicalLimit,
+            ("PhysicalTopN", None) => Self::PhysicalTopN,
+            ("PhysicalUnion", Some(x)) => Self::PhysicalUnion(x.parse()?),
+            ("PhysicalWindow", None) => Self::PhysicalWindow,
+            ("PhysicalValues", None) => Self::PhysicalValues,
+            ("PhysicalEmptyRelation", None) => Self::PhysicalEmptyRelation,
+            ("Constant", Some(x)) => Self::Constant(x.parse()?),
+            ("ColumnRef", None) => Self::ColumnRef,
+            ("ExternColumnRef", None) => Self::ExternColumnRef,
+            ("UnOp", Some(x)) => Self::UnOp(x.parse()?),
+            ("BinOp", Some(x)) => Self::BinOp(x.parse()?),
+            ("LogOp", Some(x)) => Self::LogOp(x.parse()?),
+            ("Func", Some(x)) => Self::Func(x.parse()?),
+            ("SortOrder", Some(x)) => Self::SortOrder(x.parse()?),
+            _ => anyhow::bail!("unknown plan node type `{}`", s),
+        })
+    }
+}
+
 impl RelNodeTyp for OptRelNodeTyp {
     fn is_logical(&self) -> bool {
         matches!(
@@ -120,6 +220,12 @@ impl RelNodeTyp for OptRelNodeTyp {
                 | Self::Apply(_)
                 | Self::Sort
                 | Self::Agg
+                | Self::Limit
+                | Self::Union(_)
+                | Self::Window
+                | Self::Distinct
+                | Self::Values
+                | Self::EmptyRelation
         )
     }
 
@@ -138,6 +244,10 @@ impl RelNodeTyp for OptRelNodeTyp {
             None
         }
     }
+
+    fn is_scalar(&self) -> bool {
+        self.is_expression() || matches!(self, Self::List)
+    }
 }
 
 pub type OptRelNodeRef = RelNodeRef<OptRelNodeTyp>;
@@ -276,6 +386,9 @@ pub fn explain(rel_node: OptRelNodeRef) -> Pretty<'static> {
         OptRelNodeTyp::ColumnRef => ColumnRefExpr::from_rel_node(rel_node)
             .unwrap()
             .dispatch_explain(),
+        OptRelNodeTyp::ExternColumnRef => ExternColumnRefExpr::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
         OptRelNodeTyp::Constant(_) => ConstantExpr::from_rel_node(rel_node)
             .unwrap()
             .dispatch_explain(),
@@ -333,9 +446,48 @@ pub fn explain(rel_node: OptRelNodeRef) -> Pretty<'static> {
         OptRelNodeTyp::PhysicalSort => PhysicalSort::from_rel_node(rel_node)
             .unwrap()
             .dispatch_explain(),
+        OptRelNodeTyp::Limit => LogicalLimit::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
+        OptRelNodeTyp::PhysicalLimit => PhysicalLimit::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
+        OptRelNodeTyp::PhysicalTopN => PhysicalTopN::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
+        OptRelNodeTyp::Union(_) => LogicalUnion::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
+        OptRelNodeTyp::PhysicalUnion(_) => PhysicalUnion::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
+        OptRelNodeTyp::Window => LogicalWindow::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
+        OptRelNodeTyp::PhysicalWindow => PhysicalWindow::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
+        OptRelNodeTyp::Distinct => LogicalDistinct::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
+        OptRelNodeTyp::Values => LogicalValues::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
+        OptRelNodeTyp::PhysicalValues => PhysicalValues::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
+        OptRelNodeTyp::EmptyRelation => LogicalEmptyRelation::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
+        OptRelNodeTyp::PhysicalEmptyRelation => PhysicalEmptyRelation::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
         OptRelNodeTyp::PhysicalHashJoin(_) => PhysicalHashJoin::from_rel_node(rel_node)
             .unwrap()
             .dispatch_explain(),
+        OptRelNodeTyp::PhysicalSortMergeJoin(_) => PhysicalSortMergeJoin::from_rel_node(rel_node)
+            .unwrap()
+            .dispatch_explain(),
         OptRelNodeTyp::SortOrder(_) => SortOrderExpr::from_rel_node(rel_node)
             .unwrap()
             .dispatch_explain(),
@@ -355,3 +507,17 @@ fn replace_typ(node: OptRelNodeRef, target_type: OptRelNodeTyp) -> OptRelNodeRef
         data: node.data.clone(),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use optd_core::rel_node::RelNode;
+
+    use super::OptRelNodeTyp;
+
+    #[test]
+    fn parse_plan() {
+        let plan = r#"(Projection (Join(Inner) (Filter (Scan "t1") (BinOp(Eq) (ColumnRef 1) (Constant(Decimal(15, 2)) 1.50::decimal(15,2)))) !3 (Constant(Bool) true)) (List (ColumnRef 0) (Constant(Date) date'1998-12-01')))"#;
+        let node: RelNode<OptRelNodeTyp> = plan.parse().unwrap();
+        assert_eq!(node.to_string(), plan);
+    }
+}
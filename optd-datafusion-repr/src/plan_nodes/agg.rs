@@ -28,3 +28,16 @@ define_plan_node!(
         { 2, groups: ExprList }
     ]
 );
+
+/// Removes the duplicate rows of the child. It has no physical implementation and is rewritten into an aggregation
+/// grouping by all columns.
+#[derive(Clone, Debug)]
+pub struct LogicalDistinct(pub PlanNode);
+
+define_plan_node!(
+    LogicalDistinct : PlanNode,
+    Distinct, [
+        { 0, child: PlanNode }
+    ], [
+    ]
+);
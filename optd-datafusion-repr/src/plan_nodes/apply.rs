@@ -1,5 +1,6 @@
 use core::fmt;
 use std::fmt::Display;
+use std::str::FromStr;
 
 use pretty_xmlish::Pretty;
 
@@ -16,6 +17,8 @@ pub enum ApplyType {
 }
 
 impl ApplyType {
+    pub const ALL: [ApplyType; 4] = [Self::Cross, Self::LeftOuter, Self::Semi, Self::AntiSemi];
+
     pub fn to_join_type(self) -> JoinType {
         match self {
             Self::Cross => JoinType::Cross,
@@ -32,6 +35,20 @@ impl Display for ApplyType {
     }
 }
 
+impl FromStr for ApplyType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "Cross" => Self::Cross,
+            "LeftOuter" => Self::LeftOuter,
+            "Semi" => Self::Semi,
+            "AntiSemi" => Self::AntiSemi,
+            _ => anyhow::bail!("unknown ApplyType `{}`", s),
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LogicalApply(pub PlanNode);
 
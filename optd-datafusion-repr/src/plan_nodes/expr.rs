@@ -1,9 +1,14 @@
-use std::fmt::Display;
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    str::FromStr,
+    sync::{Mutex, OnceLock},
+};
 
 use itertools::Itertools;
 use pretty_xmlish::Pretty;
 
-use optd_core::rel_node::{RelNode, Value};
+use optd_core::rel_node::{RelNode, TimeUnit, Value};
 
 use super::{Expr, OptRelNode, OptRelNodeRef, OptRelNodeTyp};
 
@@ -60,27 +65,147 @@ impl OptRelNode for ExprList {
     }
 }
 
+/// The name of a time zone. Names are interned, so that `ConstantType` can be `Copy`; a workload only uses a few time
+/// zones, so the interned names are never freed.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Timezone(&'static str);
+
+impl Timezone {
+    pub fn new(name: &str) -> Self {
+        static NAMES: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+        let mut names = NAMES.get_or_init(Default::default).lock().unwrap();
+        if let Some(name) = names.get(name) {
+            return Self(name);
+        }
+        let name: &'static str = Box::leak(name.into());
+        names.insert(name);
+        Self(name)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl std::fmt::Debug for Timezone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+/// The fields of an interval, which are all stored as months, days and nanoseconds in a `Value::Interval`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum IntervalKind {
+    YearMonth,
+    DayTime,
+    MonthDayNano,
+}
+
+impl FromStr for IntervalKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "YearMonth" => Self::YearMonth,
+            "DayTime" => Self::DayTime,
+            "MonthDayNano" => Self::MonthDayNano,
+            _ => anyhow::bail!("unknown IntervalKind `{}`", s),
+        })
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum ConstantType {
     Bool,
     Utf8String,
     Int,
     Date,
-    Decimal,
+    Timestamp(TimeUnit, Option<Timezone>),
+    Interval(IntervalKind),
+    Float,
+    /// A decimal with the given precision and scale.
+    Decimal(u8, i8),
     Any,
 }
 
+impl FromStr for ConstantType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "Bool" => Self::Bool,
+            "Utf8String" => Self::Utf8String,
+            "Int" => Self::Int,
+            "Date" => Self::Date,
+            "Float" => Self::Float,
+            "Any" => Self::Any,
+            _ if s.starts_with("Timestamp(") => {
+                // `Timestamp(<unit>, None)` or `Timestamp(<unit>, Some("<time zone>"))`
+                let Some((unit, tz)) = s
+                    .strip_prefix("Timestamp(")
+                    .and_then(|x| x.strip_suffix(')'))
+                    .and_then(|x| x.split_once(','))
+                else {
+                    anyhow::bail!("unknown ConstantType `{}`", s)
+                };
+                let tz = match tz.trim() {
+                    "None" => None,
+                    tz => {
+                        let Some(tz) = tz
+                            .strip_prefix("Some(\"")
+                            .and_then(|x| x.strip_suffix("\")"))
+                        else {
+                            anyhow::bail!("unknown ConstantType `{}`", s)
+                        };
+                        Some(Timezone::new(tz))
+                    }
+                };
+                Self::Timestamp(unit.trim().parse()?, tz)
+            }
+            _ if s.starts_with("Interval(") => {
+                let Some(kind) = s
+                    .strip_prefix("Interval(")
+                    .and_then(|x| x.strip_suffix(')'))
+                else {
+                    anyhow::bail!("unknown ConstantType `{}`", s)
+                };
+                Self::Interval(kind.parse()?)
+            }
+            _ => {
+                // `Decimal(<precision>, <scale>)`
+                let Some((precision, scale)) = s
+                    .strip_prefix("Decimal(")
+                    .and_then(|x| x.strip_suffix(')'))
+                    .and_then(|x| x.split_once(','))
+                else {
+                    anyhow::bail!("unknown ConstantType `{}`", s)
+                };
+                Self::Decimal(precision.trim().parse()?, scale.trim().parse()?)
+            }
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ConstantExpr(pub Expr);
 
 impl ConstantExpr {
+    /// Creates a constant typed by its value. Nulls, lists and serialized values are typed as `Any`.
     pub fn new(value: Value) -> Self {
         let typ = match &value {
             Value::Bool(_) => ConstantType::Bool,
             Value::String(_) => ConstantType::Utf8String,
             Value::Int(_) => ConstantType::Int,
-            Value::Float(_) => ConstantType::Decimal,
-            _ => unimplemented!(),
+            Value::Float(_) => ConstantType::Float,
+            Value::Decimal {
+                precision, scale, ..
+            } => ConstantType::Decimal(*precision, *scale),
+            Value::Date32(_) => ConstantType::Date,
+            Value::Timestamp { unit, tz, .. } => {
+                ConstantType::Timestamp(*unit, tz.as_deref().map(Timezone::new))
+            }
+            Value::Interval { .. } => ConstantType::Interval(IntervalKind::MonthDayNano),
+            Value::Null | Value::List(_) | Value::Serialized(_) => ConstantType::Any,
         };
         Self::new_with_type(value, typ)
     }
@@ -111,12 +236,52 @@ impl ConstantExpr {
         Self::new_with_type(Value::Int(value), ConstantType::Int)
     }
 
-    pub fn date(value: i64) -> Self {
-        Self::new_with_type(Value::Int(value), ConstantType::Date)
+    /// Creates a date constant from the number of days since the UNIX epoch.
+    pub fn date(value: i32) -> Self {
+        Self::new_with_type(Value::Date32(value), ConstantType::Date)
+    }
+
+    /// Creates a timestamp constant from the number of `unit`s since the UNIX epoch.
+    pub fn timestamp(value: i64, unit: TimeUnit, tz: Option<&str>) -> Self {
+        Self::new_with_type(
+            Value::Timestamp {
+                value,
+                unit,
+                tz: tz.map(Into::into),
+            },
+            ConstantType::Timestamp(unit, tz.map(Timezone::new)),
+        )
+    }
+
+    pub fn interval(months: i32, days: i32, nanos: i64, kind: IntervalKind) -> Self {
+        Self::new_with_type(
+            Value::Interval {
+                months,
+                days,
+                nanos,
+            },
+            ConstantType::Interval(kind),
+        )
     }
 
-    pub fn decimal(value: f64) -> Self {
-        Self::new_with_type(Value::Float(value.into()), ConstantType::Decimal)
+    pub fn decimal(value: i128, precision: u8, scale: i8) -> Self {
+        Self::new_with_type(
+            Value::Decimal {
+                value,
+                precision,
+                scale,
+            },
+            ConstantType::Decimal(precision, scale),
+        )
+    }
+
+    pub fn float(value: f64) -> Self {
+        Self::new_with_type(Value::Float(value.into()), ConstantType::Float)
+    }
+
+    /// Creates a typed null constant.
+    pub fn null(typ: ConstantType) -> Self {
+        Self::new_with_type(Value::Null, typ)
     }
 
     /// Gets the constant value.
@@ -142,6 +307,12 @@ impl OptRelNode for ConstantExpr {
     }
 }
 
+/// Decodes the data of a `ColumnRef` or `ExternColumnRef` node into the column index. Returns `None` if the data is not
+/// a valid index.
+pub fn decode_column_index(data: Option<&Value>) -> Option<usize> {
+    usize::try_from(data?.as_i64()?).ok()
+}
+
 #[derive(Clone, Debug)]
 pub struct ColumnRefExpr(pub Expr);
 
@@ -159,7 +330,8 @@ impl ColumnRefExpr {
     }
 
     fn get_data_usize(&self) -> usize {
-        self.0 .0.data.as_ref().unwrap().as_i64() as usize
+        decode_column_index(self.0 .0.data.as_ref())
+            .expect("the column index is validated by from_rel_node")
     }
 
     /// Gets the column index.
@@ -174,7 +346,9 @@ impl OptRelNode for ColumnRefExpr {
     }
 
     fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
-        if rel_node.typ != OptRelNodeTyp::ColumnRef {
+        if rel_node.typ != OptRelNodeTyp::ColumnRef
+            || decode_column_index(rel_node.data.as_ref()).is_none()
+        {
             return None;
         }
         Expr::from_rel_node(rel_node).map(Self)
@@ -185,6 +359,49 @@ impl OptRelNode for ColumnRefExpr {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct ExternColumnRefExpr(pub Expr);
+
+impl ExternColumnRefExpr {
+    /// Creates a new `ExternColumnRef` expression, which refers to a column of the left child of the enclosing
+    /// `Apply`.
+    pub fn new(column_idx: usize) -> ExternColumnRefExpr {
+        ExternColumnRefExpr(Expr(
+            RelNode {
+                typ: OptRelNodeTyp::ExternColumnRef,
+                children: vec![],
+                data: Some(Value::Int(column_idx as i64)),
+            }
+            .into(),
+        ))
+    }
+
+    /// Gets the column index in the left child of the enclosing `Apply`.
+    pub fn index(&self) -> usize {
+        decode_column_index(self.0 .0.data.as_ref())
+            .expect("the column index is validated by from_rel_node")
+    }
+}
+
+impl OptRelNode for ExternColumnRefExpr {
+    fn into_rel_node(self) -> OptRelNodeRef {
+        self.0.into_rel_node()
+    }
+
+    fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
+        if rel_node.typ != OptRelNodeTyp::ExternColumnRef
+            || decode_column_index(rel_node.data.as_ref()).is_none()
+        {
+            return None;
+        }
+        Expr::from_rel_node(rel_node).map(Self)
+    }
+
+    fn dispatch_explain(&self) -> Pretty<'static> {
+        Pretty::display(&format!("Extern(#{})", self.index()))
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum UnOpType {
     Neg = 1,
@@ -197,6 +414,18 @@ impl Display for UnOpType {
     }
 }
 
+impl FromStr for UnOpType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "Neg" => Self::Neg,
+            "Not" => Self::Not,
+            _ => anyhow::bail!("unknown UnOpType `{}`", s),
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct UnOpExpr(Expr);
 
@@ -262,6 +491,8 @@ pub enum BinOpType {
     And,
     Or,
     Xor,
+    /// Equality that treats two nulls as equal, i.e., `IS NOT DISTINCT FROM`.
+    IsNotDistinctFrom,
 }
 
 impl Display for BinOpType {
@@ -270,6 +501,31 @@ impl Display for BinOpType {
     }
 }
 
+impl FromStr for BinOpType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "Add" => Self::Add,
+            "Sub" => Self::Sub,
+            "Mul" => Self::Mul,
+            "Div" => Self::Div,
+            "Mod" => Self::Mod,
+            "Eq" => Self::Eq,
+            "Neq" => Self::Neq,
+            "Gt" => Self::Gt,
+            "Lt" => Self::Lt,
+            "Geq" => Self::Geq,
+            "Leq" => Self::Leq,
+            "And" => Self::And,
+            "Or" => Self::Or,
+            "Xor" => Self::Xor,
+            "IsNotDistinctFrom" => Self::IsNotDistinctFrom,
+            _ => anyhow::bail!("unknown BinOpType `{}`", s),
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct BinOpExpr(pub Expr);
 
@@ -327,6 +583,8 @@ impl OptRelNode for BinOpExpr {
 pub enum FuncType {
     Scalar(datafusion_expr::BuiltinScalarFunction),
     Agg(datafusion_expr::AggregateFunction),
+    /// A window function that is not an aggregation, which is only valid in the expressions of a window.
+    Window(datafusion_expr::BuiltInWindowFunction),
     Case,
 }
 
@@ -336,6 +594,37 @@ impl std::fmt::Display for FuncType {
     }
 }
 
+impl FromStr for FuncType {
+    type Err = anyhow::Error;
+
+    /// Parses the `Debug` output of the function type, e.g., `Scalar(DatePart)`, `Agg(Sum)` or `Window(RowNumber)`.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        fn to_snake_case(s: &str) -> String {
+            let mut result = String::new();
+            for (idx, c) in s.chars().enumerate() {
+                if c.is_ascii_uppercase() && idx != 0 {
+                    result.push('_');
+                }
+                result.push(c.to_ascii_lowercase());
+            }
+            result
+        }
+        if s == "Case" {
+            return Ok(Self::Case);
+        }
+        let Some((kind, func)) = s.strip_suffix(')').and_then(|x| x.split_once('(')) else {
+            anyhow::bail!("unknown FuncType `{}`", s)
+        };
+        let func = to_snake_case(func);
+        Ok(match kind {
+            "Scalar" => Self::Scalar(func.parse()?),
+            "Agg" => Self::Agg(func.parse()?),
+            "Window" => Self::Window(func.parse()?),
+            _ => anyhow::bail!("unknown FuncType `{}`", s),
+        })
+    }
+}
+
 impl FuncType {
     pub fn new_scalar(func_id: datafusion_expr::BuiltinScalarFunction) -> Self {
         FuncType::Scalar(func_id)
@@ -344,6 +633,10 @@ impl FuncType {
     pub fn new_agg(func_id: datafusion_expr::AggregateFunction) -> Self {
         FuncType::Agg(func_id)
     }
+
+    pub fn new_window(func_id: datafusion_expr::BuiltInWindowFunction) -> Self {
+        FuncType::Window(func_id)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -414,6 +707,18 @@ impl Display for SortOrderType {
     }
 }
 
+impl FromStr for SortOrderType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "Asc" => Self::Asc,
+            "Desc" => Self::Desc,
+            _ => anyhow::bail!("unknown SortOrderType `{}`", s),
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SortOrderExpr(Expr);
 
@@ -475,6 +780,18 @@ impl Display for LogOpType {
     }
 }
 
+impl FromStr for LogOpType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "And" => Self::And,
+            "Or" => Self::Or,
+            _ => anyhow::bail!("unknown LogOpType `{}`", s),
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LogOpExpr(pub Expr);
 
@@ -1,8 +1,9 @@
 use core::fmt;
 use std::fmt::Display;
+use std::str::FromStr;
 
 use super::macros::define_plan_node;
-use super::{Expr, ExprList, OptRelNode, OptRelNodeRef, OptRelNodeTyp, PlanNode};
+use super::{ConstantExpr, Expr, ExprList, OptRelNode, OptRelNodeRef, OptRelNodeTyp, PlanNode};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum JoinType {
@@ -17,12 +18,45 @@ pub enum JoinType {
     RightAnti,
 }
 
+impl JoinType {
+    pub const ALL: [JoinType; 9] = [
+        Self::Inner,
+        Self::FullOuter,
+        Self::LeftOuter,
+        Self::RightOuter,
+        Self::Cross,
+        Self::LeftSemi,
+        Self::RightSemi,
+        Self::LeftAnti,
+        Self::RightAnti,
+    ];
+}
+
 impl Display for JoinType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{:?}", self)
     }
 }
 
+impl FromStr for JoinType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "Inner" => Self::Inner,
+            "FullOuter" => Self::FullOuter,
+            "LeftOuter" => Self::LeftOuter,
+            "RightOuter" => Self::RightOuter,
+            "Cross" => Self::Cross,
+            "LeftSemi" => Self::LeftSemi,
+            "RightSemi" => Self::RightSemi,
+            "LeftAnti" => Self::LeftAnti,
+            "RightAnti" => Self::RightAnti,
+            _ => anyhow::bail!("unknown JoinType `{}`", s),
+        })
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LogicalJoin(pub PlanNode);
 
@@ -49,6 +83,9 @@ define_plan_node!(
     ], { join_type: JoinType }
 );
 
+/// Joins the rows with equal `left_keys` and `right_keys`, and then filters the joined rows with the residual `cond`,
+/// which refers to the columns of the left side followed by the columns of the right side. Null keys are equal to
+/// each other if `null_equals_null` is true, as in the keys compared with `IS NOT DISTINCT FROM`.
 #[derive(Clone, Debug)]
 pub struct PhysicalHashJoin(pub PlanNode);
 
@@ -59,6 +96,31 @@ define_plan_node!(
         { 1, right: PlanNode }
     ], [
         { 2, left_keys: ExprList },
-        { 3, right_keys: ExprList }
+        { 3, right_keys: ExprList },
+        { 4, cond: Expr },
+        { 5, null_equals_null: ConstantExpr }
+    ], { join_type: JoinType }
+);
+
+impl PhysicalHashJoin {
+    pub fn nulls_equal(&self) -> bool {
+        self.null_equals_null().value().as_bool() == Some(true)
+    }
+}
+
+/// Joins the rows with equal `left_keys` and `right_keys` by sorting both sides on the keys and merging them. The
+/// residual `cond` has the same meaning as in `PhysicalHashJoin`.
+#[derive(Clone, Debug)]
+pub struct PhysicalSortMergeJoin(pub PlanNode);
+
+define_plan_node!(
+    PhysicalSortMergeJoin : PlanNode,
+    PhysicalSortMergeJoin, [
+        { 0, left: PlanNode },
+        { 1, right: PlanNode }
+    ], [
+        { 2, left_keys: ExprList },
+        { 3, right_keys: ExprList },
+        { 4, cond: Expr }
     ], { join_type: JoinType }
 );
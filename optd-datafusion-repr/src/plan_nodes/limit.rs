@@ -0,0 +1,195 @@
+use optd_core::rel_node::{RelNode, Value};
+use pretty_xmlish::Pretty;
+
+use super::{replace_typ, ExprList, OptRelNode, OptRelNodeRef, OptRelNodeTyp, PlanNode};
+
+/// Encodes the number of rows skipped and the maximum number of rows returned by a limit as the data of the node, so
+/// that the cost model can use them.
+fn limit_data(skip: usize, fetch: Option<usize>) -> Value {
+    let fetch = fetch.map_or(Value::Null, |x| Value::Int(x as i64));
+    Value::List(vec![Value::Int(skip as i64), fetch].into())
+}
+
+/// Decodes the data of a `Limit`, `PhysicalLimit` or `PhysicalTopN` node into the number of rows skipped and the
+/// maximum number of rows returned, which is `None` if all remaining rows are returned. Returns `None` if the data is
+/// not a valid limit.
+pub fn decode_limit(data: Option<&Value>) -> Option<(usize, Option<usize>)> {
+    let data = data?.as_list()?;
+    let [skip, fetch] = &data[..] else {
+        return None;
+    };
+    let skip = usize::try_from(skip.as_i64()?).ok()?;
+    let fetch = match fetch {
+        Value::Null => None,
+        fetch => Some(usize::try_from(fetch.as_i64()?).ok()?),
+    };
+    Some((skip, fetch))
+}
+
+/// The limit of a node, whose data is validated by `from_rel_node`.
+fn limit_of(rel_node: &OptRelNodeRef) -> (usize, Option<usize>) {
+    decode_limit(rel_node.data.as_ref()).expect("limit data is validated by from_rel_node")
+}
+
+fn explain_limit(rel_node: &OptRelNodeRef) -> Vec<(&'static str, Pretty<'static>)> {
+    let (skip, fetch) = limit_of(rel_node);
+    vec![
+        ("skip", skip.to_string().into()),
+        (
+            "fetch",
+            fetch.map_or("None".to_string(), |x| x.to_string()).into(),
+        ),
+    ]
+}
+
+/// Skips the first `skip` rows of the child and returns at most `fetch` of the remaining rows.
+#[derive(Clone, Debug)]
+pub struct LogicalLimit(pub PlanNode);
+
+impl OptRelNode for LogicalLimit {
+    fn into_rel_node(self) -> OptRelNodeRef {
+        self.0.into_rel_node()
+    }
+
+    fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
+        if rel_node.typ != OptRelNodeTyp::Limit || decode_limit(rel_node.data.as_ref()).is_none() {
+            return None;
+        }
+        PlanNode::from_rel_node(rel_node).map(Self)
+    }
+
+    fn dispatch_explain(&self) -> Pretty<'static> {
+        Pretty::simple_record(
+            "LogicalLimit",
+            explain_limit(&self.clone().into_rel_node()),
+            vec![self.child().explain()],
+        )
+    }
+}
+
+impl LogicalLimit {
+    pub fn new(child: PlanNode, skip: usize, fetch: Option<usize>) -> LogicalLimit {
+        LogicalLimit(PlanNode(
+            RelNode {
+                typ: OptRelNodeTyp::Limit,
+                children: vec![child.into_rel_node()],
+                data: Some(limit_data(skip, fetch)),
+            }
+            .into(),
+        ))
+    }
+
+    pub fn child(&self) -> PlanNode {
+        PlanNode::from_rel_node(self.clone().into_rel_node().child(0)).unwrap()
+    }
+
+    pub fn skip(&self) -> usize {
+        limit_of(&self.clone().into_rel_node()).0
+    }
+
+    pub fn fetch(&self) -> Option<usize> {
+        limit_of(&self.clone().into_rel_node()).1
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PhysicalLimit(pub PlanNode);
+
+impl OptRelNode for PhysicalLimit {
+    fn into_rel_node(self) -> OptRelNodeRef {
+        replace_typ(self.0.into_rel_node(), OptRelNodeTyp::PhysicalLimit)
+    }
+
+    fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
+        if rel_node.typ != OptRelNodeTyp::PhysicalLimit
+            || decode_limit(rel_node.data.as_ref()).is_none()
+        {
+            return None;
+        }
+        PlanNode::from_rel_node(rel_node).map(Self)
+    }
+
+    fn dispatch_explain(&self) -> Pretty<'static> {
+        Pretty::simple_record(
+            "PhysicalLimit",
+            explain_limit(&self.clone().into_rel_node()),
+            vec![self.child().explain()],
+        )
+    }
+}
+
+impl PhysicalLimit {
+    pub fn new(node: PlanNode) -> PhysicalLimit {
+        Self(node)
+    }
+
+    pub fn child(&self) -> PlanNode {
+        PlanNode::from_rel_node(self.clone().into_rel_node().child(0)).unwrap()
+    }
+
+    pub fn skip(&self) -> usize {
+        limit_of(&self.clone().into_rel_node()).0
+    }
+
+    pub fn fetch(&self) -> Option<usize> {
+        limit_of(&self.clone().into_rel_node()).1
+    }
+}
+
+/// Sorts the child by `exprs` and returns the rows of the sorted output that a `Limit` with the same `skip` and
+/// `fetch` would return, without sorting all rows.
+#[derive(Clone, Debug)]
+pub struct PhysicalTopN(pub PlanNode);
+
+impl OptRelNode for PhysicalTopN {
+    fn into_rel_node(self) -> OptRelNodeRef {
+        self.0.into_rel_node()
+    }
+
+    fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
+        // a top-n always has a fetch
+        if rel_node.typ != OptRelNodeTyp::PhysicalTopN
+            || !matches!(decode_limit(rel_node.data.as_ref()), Some((_, Some(_))))
+        {
+            return None;
+        }
+        PlanNode::from_rel_node(rel_node).map(Self)
+    }
+
+    fn dispatch_explain(&self) -> Pretty<'static> {
+        let mut fields = vec![("exprs", self.exprs().explain())];
+        fields.extend(explain_limit(&self.clone().into_rel_node()));
+        Pretty::simple_record("PhysicalTopN", fields, vec![self.child().explain()])
+    }
+}
+
+impl PhysicalTopN {
+    pub fn new(child: PlanNode, exprs: ExprList, skip: usize, fetch: usize) -> PhysicalTopN {
+        PhysicalTopN(PlanNode(
+            RelNode {
+                typ: OptRelNodeTyp::PhysicalTopN,
+                children: vec![child.into_rel_node(), exprs.into_rel_node()],
+                data: Some(limit_data(skip, Some(fetch))),
+            }
+            .into(),
+        ))
+    }
+
+    pub fn child(&self) -> PlanNode {
+        PlanNode::from_rel_node(self.clone().into_rel_node().child(0)).unwrap()
+    }
+
+    pub fn exprs(&self) -> ExprList {
+        ExprList::from_rel_node(self.clone().into_rel_node().child(1)).unwrap()
+    }
+
+    pub fn skip(&self) -> usize {
+        limit_of(&self.clone().into_rel_node()).0
+    }
+
+    pub fn fetch(&self) -> usize {
+        limit_of(&self.clone().into_rel_node())
+            .1
+            .expect("the fetch of a top-n is validated by from_rel_node")
+    }
+}
@@ -6,6 +6,11 @@ use optd_core::rel_node::{RelNode, Value};
 
 use super::{replace_typ, OptRelNode, OptRelNodeRef, OptRelNodeTyp, PlanNode};
 
+/// The table name of a `Scan` or `PhysicalScan` node, `None` if the data is not a string.
+fn table_of(rel_node: &OptRelNodeRef) -> Option<Arc<str>> {
+    rel_node.data.as_ref()?.as_str()
+}
+
 #[derive(Clone, Debug)]
 pub struct LogicalScan(pub PlanNode);
 
@@ -15,7 +20,7 @@ impl OptRelNode for LogicalScan {
     }
 
     fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
-        if rel_node.typ != OptRelNodeTyp::Scan {
+        if rel_node.typ != OptRelNodeTyp::Scan || table_of(&rel_node).is_none() {
             return None;
         }
         PlanNode::from_rel_node(rel_node).map(Self)
@@ -42,7 +47,7 @@ impl LogicalScan {
     }
 
     pub fn table(&self) -> Arc<str> {
-        self.clone().into_rel_node().data.as_ref().unwrap().as_str()
+        table_of(&self.clone().into_rel_node()).expect("the table is validated by from_rel_node")
     }
 }
 
@@ -55,7 +60,7 @@ impl OptRelNode for PhysicalScan {
     }
 
     fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
-        if rel_node.typ != OptRelNodeTyp::PhysicalScan {
+        if rel_node.typ != OptRelNodeTyp::PhysicalScan || table_of(&rel_node).is_none() {
             return None;
         }
         PlanNode::from_rel_node(rel_node).map(Self)
@@ -75,6 +80,6 @@ impl PhysicalScan {
     }
 
     pub fn table(&self) -> Arc<str> {
-        self.clone().into_rel_node().data.as_ref().unwrap().as_str()
+        table_of(&self.clone().into_rel_node()).expect("the table is validated by from_rel_node")
     }
 }
@@ -0,0 +1,149 @@
+use core::fmt;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use pretty_xmlish::Pretty;
+
+use optd_core::rel_node::RelNode;
+
+use super::{OptRelNode, OptRelNodeRef, OptRelNodeTyp, PlanNode};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum UnionType {
+    /// Keeps the duplicate rows, i.e., `UNION ALL`.
+    All = 1,
+    /// Removes the duplicate rows, i.e., `UNION`.
+    Distinct,
+}
+
+impl UnionType {
+    pub const ALL: [UnionType; 2] = [Self::All, Self::Distinct];
+}
+
+impl Display for UnionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for UnionType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        Ok(match s {
+            "All" => Self::All,
+            "Distinct" => Self::Distinct,
+            _ => anyhow::bail!("unknown UnionType `{}`", s),
+        })
+    }
+}
+
+/// The rows of both children, which have the same number of columns with the same types. The output columns are named
+/// after the columns of the left child.
+#[derive(Clone, Debug)]
+pub struct LogicalUnion(pub PlanNode);
+
+impl OptRelNode for LogicalUnion {
+    fn into_rel_node(self) -> OptRelNodeRef {
+        self.0.into_rel_node()
+    }
+
+    fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
+        if let OptRelNodeTyp::Union(_) = rel_node.typ {
+            PlanNode::from_rel_node(rel_node).map(Self)
+        } else {
+            None
+        }
+    }
+
+    fn dispatch_explain(&self) -> Pretty<'static> {
+        Pretty::simple_record(
+            "LogicalUnion",
+            vec![("typ", self.union_type().to_string().into())],
+            vec![self.left_child().explain(), self.right_child().explain()],
+        )
+    }
+}
+
+impl LogicalUnion {
+    pub fn new(left: PlanNode, right: PlanNode, union_type: UnionType) -> LogicalUnion {
+        LogicalUnion(PlanNode(
+            RelNode {
+                typ: OptRelNodeTyp::Union(union_type),
+                children: vec![left.into_rel_node(), right.into_rel_node()],
+                data: None,
+            }
+            .into(),
+        ))
+    }
+
+    pub fn left_child(&self) -> PlanNode {
+        PlanNode::from_rel_node(self.clone().into_rel_node().child(0)).unwrap()
+    }
+
+    pub fn right_child(&self) -> PlanNode {
+        PlanNode::from_rel_node(self.clone().into_rel_node().child(1)).unwrap()
+    }
+
+    pub fn union_type(&self) -> UnionType {
+        if let OptRelNodeTyp::Union(union_type) = self.0 .0.typ {
+            union_type
+        } else {
+            unreachable!()
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PhysicalUnion(pub PlanNode);
+
+impl OptRelNode for PhysicalUnion {
+    fn into_rel_node(self) -> OptRelNodeRef {
+        self.0.into_rel_node()
+    }
+
+    fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
+        if let OptRelNodeTyp::PhysicalUnion(_) = rel_node.typ {
+            PlanNode::from_rel_node(rel_node).map(Self)
+        } else {
+            None
+        }
+    }
+
+    fn dispatch_explain(&self) -> Pretty<'static> {
+        Pretty::simple_record(
+            "PhysicalUnion",
+            vec![("typ", self.union_type().to_string().into())],
+            vec![self.left_child().explain(), self.right_child().explain()],
+        )
+    }
+}
+
+impl PhysicalUnion {
+    pub fn new(left: PlanNode, right: PlanNode, union_type: UnionType) -> PhysicalUnion {
+        PhysicalUnion(PlanNode(
+            RelNode {
+                typ: OptRelNodeTyp::PhysicalUnion(union_type),
+                children: vec![left.into_rel_node(), right.into_rel_node()],
+                data: None,
+            }
+            .into(),
+        ))
+    }
+
+    pub fn left_child(&self) -> PlanNode {
+        PlanNode::from_rel_node(self.clone().into_rel_node().child(0)).unwrap()
+    }
+
+    pub fn right_child(&self) -> PlanNode {
+        PlanNode::from_rel_node(self.clone().into_rel_node().child(1)).unwrap()
+    }
+
+    pub fn union_type(&self) -> UnionType {
+        if let OptRelNodeTyp::PhysicalUnion(union_type) = self.0 .0.typ {
+            union_type
+        } else {
+            unreachable!()
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use optd_core::rel_node::{RelNode, Value};
+use pretty_xmlish::Pretty;
+
+use super::{replace_typ, ExprList, OptRelNode, OptRelNodeRef, OptRelNodeTyp, PlanNode};
+use crate::properties::schema::Field;
+
+/// Encodes the output fields of a node without a child, whose schema cannot be derived from its children, as a list of
+/// `[name, type, nullable]` lists.
+fn fields_data(fields: &[Field]) -> Value {
+    let fields = fields
+        .iter()
+        .map(|field| {
+            Value::List(
+                vec![
+                    Value::String(field.name.as_str().into()),
+                    Value::String(format!("{:?}", field.typ).into()),
+                    Value::Bool(field.nullable),
+                ]
+                .into(),
+            )
+        })
+        .collect::<Vec<_>>();
+    Value::List(fields.into())
+}
+
+fn decode_fields(data: &Value) -> Option<Vec<Field>> {
+    data.as_list()?
+        .iter()
+        .map(|field| {
+            let field = field.as_list()?;
+            let [name, typ, nullable] = &field[..] else {
+                return None;
+            };
+            Some(Field {
+                name: name.as_str()?.to_string(),
+                typ: typ.as_str()?.parse().ok()?,
+                nullable: nullable.as_bool()?,
+            })
+        })
+        .collect()
+}
+
+/// Decodes the data of an `EmptyRelation` or `PhysicalEmptyRelation` node into its output fields. Returns `None` if the
+/// data is not valid.
+pub fn decode_empty_relation(data: Option<&Value>) -> Option<Vec<Field>> {
+    decode_fields(data?)
+}
+
+/// Decodes the data of a `Values` or `PhysicalValues` node into the number of rows and the output fields. Returns
+/// `None` if the data is not valid.
+pub fn decode_values(data: Option<&Value>) -> Option<(usize, Vec<Field>)> {
+    let data = data?.as_list()?;
+    let [row_cnt, fields] = &data[..] else {
+        return None;
+    };
+    Some((
+        usize::try_from(row_cnt.as_i64()?).ok()?,
+        decode_fields(fields)?,
+    ))
+}
+
+/// The fields of an empty relation, whose data is validated by `from_rel_node`.
+fn empty_relation_of(rel_node: &OptRelNodeRef) -> Vec<Field> {
+    decode_empty_relation(rel_node.data.as_ref())
+        .expect("empty relation data is validated by from_rel_node")
+}
+
+/// The number of rows and fields of a values node, whose data is validated by `from_rel_node`.
+fn values_of(rel_node: &OptRelNodeRef) -> (usize, Vec<Field>) {
+    decode_values(rel_node.data.as_ref()).expect("values data is validated by from_rel_node")
+}
+
+fn explain_fields(fields: &[Field]) -> Pretty<'static> {
+    Pretty::Array(
+        fields
+            .iter()
+            .map(|field| Pretty::display(&field.name))
+            .collect(),
+    )
+}
+
+/// A relation without rows, which has the given output fields.
+#[derive(Clone, Debug)]
+pub struct LogicalEmptyRelation(pub PlanNode);
+
+impl OptRelNode for LogicalEmptyRelation {
+    fn into_rel_node(self) -> OptRelNodeRef {
+        self.0.into_rel_node()
+    }
+
+    fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
+        if rel_node.typ != OptRelNodeTyp::EmptyRelation
+            || decode_empty_relation(rel_node.data.as_ref()).is_none()
+        {
+            return None;
+        }
+        PlanNode::from_rel_node(rel_node).map(Self)
+    }
+
+    fn dispatch_explain(&self) -> Pretty<'static> {
+        Pretty::childless_record(
+            "LogicalEmptyRelation",
+            vec![("fields", explain_fields(&self.fields()))],
+        )
+    }
+}
+
+impl LogicalEmptyRelation {
+    pub fn new(fields: &[Field]) -> LogicalEmptyRelation {
+        LogicalEmptyRelation(PlanNode(
+            RelNode {
+                typ: OptRelNodeTyp::EmptyRelation,
+                children: vec![],
+                data: Some(fields_data(fields)),
+            }
+            .into(),
+        ))
+    }
+
+    pub fn fields(&self) -> Vec<Field> {
+        empty_relation_of(&self.clone().into_rel_node())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PhysicalEmptyRelation(pub PlanNode);
+
+impl OptRelNode for PhysicalEmptyRelation {
+    fn into_rel_node(self) -> OptRelNodeRef {
+        replace_typ(self.0.into_rel_node(), OptRelNodeTyp::PhysicalEmptyRelation)
+    }
+
+    fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
+        if rel_node.typ != OptRelNodeTyp::PhysicalEmptyRelation
+            || decode_empty_relation(rel_node.data.as_ref()).is_none()
+        {
+            return None;
+        }
+        PlanNode::from_rel_node(rel_node).map(Self)
+    }
+
+    fn dispatch_explain(&self) -> Pretty<'static> {
+        Pretty::childless_record(
+            "PhysicalEmptyRelation",
+            vec![("fields", explain_fields(&self.fields()))],
+        )
+    }
+}
+
+impl PhysicalEmptyRelation {
+    pub fn new(node: PlanNode) -> PhysicalEmptyRelation {
+        Self(node)
+    }
+
+    pub fn fields(&self) -> Vec<Field> {
+        empty_relation_of(&self.clone().into_rel_node())
+    }
+}
+
+/// A relation of `row_cnt` rows, whose values are the expressions in `values` in row-major order. The number of rows
+/// is stored in the data of the node, as a row without columns has no expressions.
+#[derive(Clone, Debug)]
+pub struct LogicalValues(pub PlanNode);
+
+impl OptRelNode for LogicalValues {
+    fn into_rel_node(self) -> OptRelNodeRef {
+        self.0.into_rel_node()
+    }
+
+    fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
+        if rel_node.typ != OptRelNodeTyp::Values || decode_values(rel_node.data.as_ref()).is_none()
+        {
+            return None;
+        }
+        PlanNode::from_rel_node(rel_node).map(Self)
+    }
+
+    fn dispatch_explain(&self) -> Pretty<'static> {
+        Pretty::childless_record(
+            "LogicalValues",
+            vec![
+                ("fields", explain_fields(&self.fields())),
+                ("values", self.values().explain()),
+            ],
+        )
+    }
+}
+
+impl LogicalValues {
+    pub fn new(values: ExprList, row_cnt: usize, fields: &[Field]) -> LogicalValues {
+        assert_eq!(values.len(), row_cnt * fields.len());
+        LogicalValues(PlanNode(
+            RelNode {
+                typ: OptRelNodeTyp::Values,
+                children: vec![values.into_rel_node()],
+                data: Some(Value::List(
+                    vec![Value::Int(row_cnt as i64), fields_data(fields)].into(),
+                )),
+            }
+            .into(),
+        ))
+    }
+
+    pub fn values(&self) -> ExprList {
+        ExprList::from_rel_node(self.clone().into_rel_node().child(0)).unwrap()
+    }
+
+    pub fn row_cnt(&self) -> usize {
+        values_of(&self.clone().into_rel_node()).0
+    }
+
+    pub fn fields(&self) -> Vec<Field> {
+        values_of(&self.clone().into_rel_node()).1
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PhysicalValues(pub PlanNode);
+
+impl OptRelNode for PhysicalValues {
+    fn into_rel_node(self) -> OptRelNodeRef {
+        replace_typ(self.0.into_rel_node(), OptRelNodeTyp::PhysicalValues)
+    }
+
+    fn from_rel_node(rel_node: OptRelNodeRef) -> Option<Self> {
+        if rel_node.typ != OptRelNodeTyp::PhysicalValues
+            || decode_values(rel_node.data.as_ref()).is_none()
+        {
+            return None;
+        }
+        PlanNode::from_rel_node(rel_node).map(Self)
+    }
+
+    fn dispatch_explain(&self) -> Pretty<'static> {
+        Pretty::childless_record(
+            "PhysicalValues",
+            vec![
+                ("fields", explain_fields(&self.fields())),
+                ("values", self.values().explain()),
+            ],
+        )
+    }
+}
+
+impl PhysicalValues {
+    pub fn new(node: PlanNode) -> PhysicalValues {
+        Self(node)
+    }
+
+    pub fn values(&self) -> ExprList {
+        ExprList::from_rel_node(self.clone().into_rel_node().child(0)).unwrap()
+    }
+
+    pub fn row_cnt(&self) -> usize {
+        values_of(&self.clone().into_rel_node()).0
+    }
+
+    pub fn fields(&self) -> Vec<Field> {
+        values_of(&self.clone().into_rel_node()).1
+    }
+}
@@ -0,0 +1,34 @@
+use super::expr::ExprList;
+use super::macros::define_plan_node;
+
+use super::{OptRelNode, OptRelNodeRef, OptRelNodeTyp, PlanNode};
+
+/// Evaluates the window functions in `exprs` over the partitions of the child by `partition_by`, each ordered by
+/// `order_by`. The output columns are the columns of the child followed by the window functions.
+#[derive(Clone, Debug)]
+pub struct LogicalWindow(pub PlanNode);
+
+define_plan_node!(
+    LogicalWindow : PlanNode,
+    Window, [
+        { 0, child: PlanNode }
+    ], [
+        { 1, exprs: ExprList },
+        { 2, partition_by: ExprList },
+        { 3, order_by: ExprList }
+    ]
+);
+
+#[derive(Clone, Debug)]
+pub struct PhysicalWindow(pub PlanNode);
+
+define_plan_node!(
+    PhysicalWindow : PlanNode,
+    PhysicalWindow, [
+        { 0, child: PlanNode }
+    ], [
+        { 1, exprs: ExprList },
+        { 2, partition_by: ExprList },
+        { 3, order_by: ExprList }
+    ]
+);
@@ -1 +1,5 @@
+pub mod column_equivalence;
+pub mod expr_info;
+pub mod functional_dependency;
+pub mod outer_references;
 pub mod schema;
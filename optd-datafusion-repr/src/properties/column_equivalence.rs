@@ -0,0 +1,246 @@
+use std::collections::BTreeSet;
+
+use optd_core::{property::PropertyBuilder, rel_node::Value};
+
+use super::{expr_info::ExprInfo, schema::Catalog};
+use crate::plan_nodes::{decode_empty_relation, decode_values, JoinType, OptRelNodeTyp};
+
+/// Disjoint sets of columns that have the same value in every row.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EquivalenceClasses(Vec<BTreeSet<usize>>);
+
+impl EquivalenceClasses {
+    pub fn classes(&self) -> &[BTreeSet<usize>] {
+        &self.0
+    }
+
+    pub fn class_of(&self, column: usize) -> Option<&BTreeSet<usize>> {
+        self.0.iter().find(|x| x.contains(&column))
+    }
+
+    pub fn are_equivalent(&self, a: usize, b: usize) -> bool {
+        a == b || self.class_of(a).map_or(false, |x| x.contains(&b))
+    }
+
+    pub fn add_equality(&mut self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+        let mut merged = BTreeSet::from([a, b]);
+        self.0.retain(|class| {
+            if class.contains(&a) || class.contains(&b) {
+                merged.extend(class.iter().copied());
+                false
+            } else {
+                true
+            }
+        });
+        self.0.push(merged);
+        self.0.sort();
+    }
+
+    pub fn merge(&mut self, other: &EquivalenceClasses) {
+        for class in &other.0 {
+            let first = *class.first().unwrap();
+            for &column in class {
+                self.add_equality(first, column);
+            }
+        }
+    }
+
+    fn shift(&self, offset: usize) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|class| class.iter().map(|x| x + offset).collect())
+                .collect(),
+        )
+    }
+
+    /// Map the classes to the output columns, where `mapping[i]` is the input column of the output column `i`.
+    fn project(&self, mapping: &[Option<usize>]) -> Self {
+        let mut result = Self::default();
+        for (i, input_i) in mapping.iter().enumerate() {
+            let Some(input_i) = input_i else { continue };
+            for (j, input_j) in mapping.iter().enumerate().skip(i + 1) {
+                if let Some(input_j) = input_j {
+                    if self.are_equivalent(*input_i, *input_j) {
+                        result.add_equality(i, j);
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ColumnEquivalence {
+    /// The equivalence classes of the output columns of a plan node.
+    Plan {
+        column_count: usize,
+        classes: EquivalenceClasses,
+    },
+    Expr(ExprInfo),
+}
+
+impl ColumnEquivalence {
+    fn plan(column_count: usize, classes: EquivalenceClasses) -> Self {
+        Self::Plan {
+            column_count,
+            classes,
+        }
+    }
+
+    pub fn column_count(&self) -> usize {
+        match self {
+            Self::Plan { column_count, .. } => *column_count,
+            Self::Expr(_) => 0,
+        }
+    }
+
+    /// The equivalence classes of a plan node, empty for expressions.
+    pub fn classes(&self) -> EquivalenceClasses {
+        match self {
+            Self::Plan { classes, .. } => classes.clone(),
+            Self::Expr(_) => EquivalenceClasses::default(),
+        }
+    }
+
+    pub fn expr_info(&self) -> ExprInfo {
+        match self {
+            Self::Plan { .. } => ExprInfo::default(),
+            Self::Expr(info) => info.clone(),
+        }
+    }
+
+    /// The equivalence classes implied by a predicate.
+    fn implied(cond: &ExprInfo) -> EquivalenceClasses {
+        let mut classes = EquivalenceClasses::default();
+        for &(a, b) in &cond.equalities {
+            classes.add_equality(a, b);
+        }
+        classes
+    }
+
+    /// The equalities implied by the join condition only hold for the inner joins.
+    fn join(left: &Self, right: &Self, join_type: JoinType, implied: &EquivalenceClasses) -> Self {
+        let mut classes = left.classes();
+        classes.merge(&right.classes().shift(left.column_count()));
+        match join_type {
+            JoinType::Inner | JoinType::Cross => classes.merge(implied),
+            JoinType::LeftOuter | JoinType::RightOuter | JoinType::FullOuter => {}
+            JoinType::LeftSemi | JoinType::LeftAnti => return left.clone(),
+            JoinType::RightSemi | JoinType::RightAnti => return right.clone(),
+        }
+        Self::plan(left.column_count() + right.column_count(), classes)
+    }
+}
+
+/// Derives the equivalence classes of the output columns of each plan node, i.e., columns known to be equal from the
+/// filter and join predicates below it.
+pub struct ColumnEquivalencePropertyBuilder {
+    catalog: Box<dyn Catalog>,
+}
+
+impl ColumnEquivalencePropertyBuilder {
+    pub fn new(catalog: Box<dyn Catalog>) -> Self {
+        Self { catalog }
+    }
+}
+
+impl PropertyBuilder<OptRelNodeTyp> for ColumnEquivalencePropertyBuilder {
+    type Prop = ColumnEquivalence;
+
+    fn derive(
+        &self,
+        typ: OptRelNodeTyp,
+        data: Option<Value>,
+        children: &[&Self::Prop],
+    ) -> Self::Prop {
+        match typ {
+            OptRelNodeTyp::Scan | OptRelNodeTyp::PhysicalScan => {
+                let column_count = data
+                    .as_ref()
+                    .and_then(|x| x.as_str())
+                    .map_or(0, |name| self.catalog.get(&name).len());
+                ColumnEquivalence::plan(column_count, Default::default())
+            }
+            OptRelNodeTyp::Values | OptRelNodeTyp::PhysicalValues => {
+                let (_, fields) = decode_values(data.as_ref()).unwrap_or_default();
+                ColumnEquivalence::plan(fields.len(), Default::default())
+            }
+            OptRelNodeTyp::EmptyRelation | OptRelNodeTyp::PhysicalEmptyRelation => {
+                let fields = decode_empty_relation(data.as_ref()).unwrap_or_default();
+                ColumnEquivalence::plan(fields.len(), Default::default())
+            }
+            OptRelNodeTyp::Filter | OptRelNodeTyp::PhysicalFilter => {
+                let mut classes = children[0].classes();
+                classes.merge(&ColumnEquivalence::implied(&children[1].expr_info()));
+                ColumnEquivalence::plan(children[0].column_count(), classes)
+            }
+            OptRelNodeTyp::Projection | OptRelNodeTyp::PhysicalProjection => {
+                let mapping = children[1].expr_info().element_columns();
+                ColumnEquivalence::plan(mapping.len(), children[0].classes().project(&mapping))
+            }
+            OptRelNodeTyp::Agg | OptRelNodeTyp::PhysicalAgg => {
+                // the output columns are the group by columns followed by the aggregations
+                let mut mapping = children[2].expr_info().element_columns();
+                mapping
+                    .extend(std::iter::repeat(None).take(children[1].expr_info().elements.len()));
+                ColumnEquivalence::plan(mapping.len(), children[0].classes().project(&mapping))
+            }
+            OptRelNodeTyp::Window | OptRelNodeTyp::PhysicalWindow => {
+                let column_count =
+                    children[0].column_count() + children[1].expr_info().elements.len();
+                ColumnEquivalence::plan(column_count, children[0].classes())
+            }
+            OptRelNodeTyp::Distinct
+            | OptRelNodeTyp::Sort
+            | OptRelNodeTyp::PhysicalSort
+            | OptRelNodeTyp::Limit
+            | OptRelNodeTyp::PhysicalLimit
+            | OptRelNodeTyp::PhysicalTopN
+            | OptRelNodeTyp::PhysicalCollector(_) => children[0].clone(),
+            OptRelNodeTyp::Join(join_type) | OptRelNodeTyp::PhysicalNestedLoopJoin(join_type) => {
+                let implied = ColumnEquivalence::implied(&children[2].expr_info());
+                ColumnEquivalence::join(children[0], children[1], join_type, &implied)
+            }
+            OptRelNodeTyp::PhysicalHashJoin(join_type)
+            | OptRelNodeTyp::PhysicalSortMergeJoin(join_type) => {
+                let (left, right) = (children[0], children[1]);
+                let left_keys = children[2].expr_info().element_columns();
+                let right_keys = children[3].expr_info().element_columns();
+                let mut implied = ColumnEquivalence::implied(&children[4].expr_info());
+                for (left_key, right_key) in left_keys.into_iter().zip(right_keys) {
+                    if let (Some(x), Some(y)) = (left_key, right_key) {
+                        implied.add_equality(x, y + left.column_count());
+                    }
+                }
+                ColumnEquivalence::join(left, right, join_type, &implied)
+            }
+            OptRelNodeTyp::Union(_) | OptRelNodeTyp::PhysicalUnion(_) => {
+                ColumnEquivalence::plan(children[0].column_count(), Default::default())
+            }
+            OptRelNodeTyp::Apply(apply_type) => {
+                let implied = ColumnEquivalence::implied(&children[2].expr_info());
+                ColumnEquivalence::join(
+                    children[0],
+                    children[1],
+                    apply_type.to_join_type(),
+                    &implied,
+                )
+            }
+            OptRelNodeTyp::Placeholder(_) => unreachable!("should not derive a placeholder"),
+            _ => {
+                let children = children.iter().map(|x| x.expr_info()).collect::<Vec<_>>();
+                let children = children.iter().collect::<Vec<_>>();
+                ColumnEquivalence::Expr(ExprInfo::derive(&typ, &data, &children))
+            }
+        }
+    }
+
+    fn property_name(&self) -> &'static str {
+        "column_equivalence"
+    }
+}
@@ -0,0 +1,68 @@
+use optd_core::rel_node::Value;
+
+use crate::plan_nodes::{decode_column_index, BinOpType, LogOpType, OptRelNodeTyp};
+
+/// The column relationships of a scalar expression, derived by the column equivalence and functional dependency
+/// property builders on expression nodes.
+#[derive(Clone, Debug, Default)]
+pub struct ExprInfo {
+    /// The column index if the expression is a bare column reference.
+    pub column: Option<usize>,
+    /// Whether the expression is a constant.
+    pub is_constant: bool,
+    /// Pairs of columns that are equal if the expression evaluates to true.
+    pub equalities: Vec<(usize, usize)>,
+    /// Columns that are equal to a constant if the expression evaluates to true.
+    pub constant_columns: Vec<usize>,
+    /// The information of each element if the expression is a list.
+    pub elements: Vec<ExprInfo>,
+}
+
+impl ExprInfo {
+    pub fn derive(typ: &OptRelNodeTyp, data: &Option<Value>, children: &[&ExprInfo]) -> Self {
+        match typ {
+            OptRelNodeTyp::ColumnRef => ExprInfo {
+                column: decode_column_index(data.as_ref()),
+                ..Default::default()
+            },
+            OptRelNodeTyp::Constant(_) => ExprInfo {
+                is_constant: true,
+                ..Default::default()
+            },
+            OptRelNodeTyp::BinOp(BinOpType::Eq | BinOpType::IsNotDistinctFrom) => {
+                let (left, right) = (children[0], children[1]);
+                let mut info = ExprInfo::default();
+                match (left.column, right.column) {
+                    (Some(x), Some(y)) => info.equalities.push((x, y)),
+                    (Some(x), None) if right.is_constant => info.constant_columns.push(x),
+                    (None, Some(y)) if left.is_constant => info.constant_columns.push(y),
+                    _ => {}
+                }
+                info
+            }
+            OptRelNodeTyp::BinOp(BinOpType::And) => Self::conjunction(children.iter().copied()),
+            OptRelNodeTyp::LogOp(LogOpType::And) => Self::conjunction(children[0].elements.iter()),
+            OptRelNodeTyp::List => ExprInfo {
+                elements: children.iter().map(|x| (*x).clone()).collect(),
+                ..Default::default()
+            },
+            _ => ExprInfo::default(),
+        }
+    }
+
+    /// All facts of the expressions hold if their conjunction evaluates to true.
+    fn conjunction<'a>(exprs: impl Iterator<Item = &'a ExprInfo>) -> Self {
+        let mut info = ExprInfo::default();
+        for expr in exprs {
+            info.equalities.extend(expr.equalities.iter().copied());
+            info.constant_columns
+                .extend(expr.constant_columns.iter().copied());
+        }
+        info
+    }
+
+    /// The input column of each element of an expression list, if the element is a bare column reference.
+    pub fn element_columns(&self) -> Vec<Option<usize>> {
+        self.elements.iter().map(|x| x.column).collect()
+    }
+}
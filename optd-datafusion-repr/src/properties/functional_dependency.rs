@@ -0,0 +1,498 @@
+use std::collections::{BTreeSet, HashMap};
+
+use optd_core::{property::PropertyBuilder, rel_node::Value};
+
+use super::{expr_info::ExprInfo, schema::Catalog};
+use crate::plan_nodes::{decode_empty_relation, decode_values, JoinType, OptRelNodeTyp, UnionType};
+
+/// The `dependent` columns are determined by the `determinant` columns. An empty determinant means that the
+/// dependent columns are constant.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionalDependency {
+    pub determinant: BTreeSet<usize>,
+    pub dependent: BTreeSet<usize>,
+}
+
+/// The candidate keys and functional dependencies of the output columns of a plan node.
+#[derive(Clone, Debug, Default)]
+pub struct FunctionalDependencies {
+    pub column_count: usize,
+    /// Minimal sets of columns that are unique among all rows.
+    pub keys: Vec<BTreeSet<usize>>,
+    pub deps: Vec<FunctionalDependency>,
+}
+
+impl FunctionalDependencies {
+    fn new(column_count: usize) -> Self {
+        Self {
+            column_count,
+            ..Default::default()
+        }
+    }
+
+    /// All columns determined by the given columns.
+    pub fn closure(&self, columns: &BTreeSet<usize>) -> BTreeSet<usize> {
+        let mut closure = columns.clone();
+        loop {
+            let mut changed = false;
+            for dep in &self.deps {
+                if dep.determinant.is_subset(&closure) && !dep.dependent.is_subset(&closure) {
+                    closure.extend(dep.dependent.iter().copied());
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        if self.keys.iter().any(|key| key.is_subset(&closure)) {
+            return (0..self.column_count).collect();
+        }
+        closure
+    }
+
+    pub fn determines(&self, determinant: &BTreeSet<usize>, dependent: &BTreeSet<usize>) -> bool {
+        dependent.is_subset(&self.closure(determinant))
+    }
+
+    /// Whether the given columns are unique among all rows, i.e., they determine a candidate key.
+    pub fn is_unique(&self, columns: &BTreeSet<usize>) -> bool {
+        let closure = self.closure(columns);
+        self.keys.iter().any(|key| key.is_subset(&closure))
+    }
+
+    pub fn add_dependency(&mut self, determinant: BTreeSet<usize>, dependent: BTreeSet<usize>) {
+        let dependent = &dependent - &determinant;
+        if dependent.is_empty() || self.determines(&determinant, &dependent) {
+            return;
+        }
+        self.deps.push(FunctionalDependency {
+            determinant,
+            dependent,
+        });
+    }
+
+    pub fn add_key(&mut self, key: BTreeSet<usize>) {
+        if self.keys.iter().any(|x| x.is_subset(&key)) {
+            return;
+        }
+        self.keys.retain(|x| !key.is_subset(x));
+        self.keys.push(key);
+    }
+
+    fn add_predicate(&mut self, cond: &ExprInfo) {
+        for &(a, b) in &cond.equalities {
+            self.add_dependency([a].into(), [b].into());
+            self.add_dependency([b].into(), [a].into());
+        }
+        for &column in &cond.constant_columns {
+            self.add_dependency(BTreeSet::new(), [column].into());
+        }
+    }
+
+    fn shift(&self, offset: usize) -> Self {
+        let shift = |x: &BTreeSet<usize>| x.iter().map(|x| x + offset).collect::<BTreeSet<_>>();
+        Self {
+            column_count: self.column_count,
+            keys: self.keys.iter().map(shift).collect(),
+            deps: self
+                .deps
+                .iter()
+                .map(|dep| FunctionalDependency {
+                    determinant: shift(&dep.determinant),
+                    dependent: shift(&dep.dependent),
+                })
+                .collect(),
+        }
+    }
+
+    /// Map the dependencies to the output columns, where `mapping[i]` is the input column of the output column `i`.
+    fn project(&self, mapping: &[Option<usize>]) -> Self {
+        let mut outputs: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (output, input) in mapping.iter().enumerate() {
+            if let Some(input) = input {
+                outputs.entry(*input).or_default().push(output);
+            }
+        }
+        let map_all = |columns: &BTreeSet<usize>| -> BTreeSet<usize> {
+            columns
+                .iter()
+                .flat_map(|x| outputs.get(x).into_iter().flatten().copied())
+                .collect()
+        };
+        let map_first = |columns: &BTreeSet<usize>| -> Option<BTreeSet<usize>> {
+            columns
+                .iter()
+                .map(|x| outputs.get(x).map(|x| x[0]))
+                .collect()
+        };
+        let mut result = Self::new(mapping.len());
+        for key in &self.keys {
+            if let Some(key) = map_first(key) {
+                result.add_key(key);
+            }
+        }
+        for dep in &self.deps {
+            if let Some(determinant) = map_first(&dep.determinant) {
+                result.add_dependency(determinant, map_all(&self.closure(&dep.determinant)));
+            }
+        }
+        for columns in outputs.values() {
+            for &column in &columns[1..] {
+                result.add_dependency([columns[0]].into(), [column].into());
+                result.add_dependency([column].into(), [columns[0]].into());
+            }
+        }
+        result
+    }
+
+    /// Whether each row of `self` matches at most one row of `other` on the equal columns.
+    fn matches_at_most_one(&self, other: &Self, equalities: &[(usize, usize)]) -> bool {
+        let equal_columns = equalities.iter().map(|(_, x)| *x).collect();
+        other.is_unique(&equal_columns)
+    }
+
+    /// `equalities` are pairs of (left column, right column) that are equal in the joined rows, where the right
+    /// columns are not shifted.
+    fn join(
+        left: &Self,
+        right: &Self,
+        join_type: JoinType,
+        equalities: &[(usize, usize)],
+        cond: &ExprInfo,
+    ) -> Self {
+        let offset = left.column_count;
+        let shifted = right.shift(offset);
+        let mut result = Self::new(left.column_count + right.column_count);
+        // the dependencies of a side padded with nulls do not hold for the padded rows, e.g., a constant column is
+        // null in them, so only the dependencies of the sides that are never padded are kept
+        let (keep_left_deps, keep_right_deps) = match join_type {
+            JoinType::LeftOuter => (true, false),
+            JoinType::RightOuter => (false, true),
+            JoinType::FullOuter => (false, false),
+            _ => (true, true),
+        };
+        if keep_left_deps {
+            result.deps.extend(left.deps.iter().cloned());
+        }
+        if keep_right_deps {
+            result.deps.extend(shifted.deps.iter().cloned());
+        }
+        for left_key in &left.keys {
+            for right_key in &shifted.keys {
+                result.add_key(left_key | right_key);
+            }
+        }
+        let swapped = equalities.iter().map(|(x, y)| (*y, *x)).collect::<Vec<_>>();
+        let preserve_left_keys = left.matches_at_most_one(right, equalities);
+        let preserve_right_keys = right.matches_at_most_one(left, &swapped);
+        match join_type {
+            JoinType::Inner | JoinType::Cross => {
+                for &(x, y) in equalities {
+                    result.add_dependency([x].into(), [y + offset].into());
+                    result.add_dependency([y + offset].into(), [x].into());
+                }
+                result.add_predicate(cond);
+                if preserve_left_keys {
+                    left.keys.iter().for_each(|x| result.add_key(x.clone()));
+                }
+                if preserve_right_keys {
+                    shifted.keys.iter().for_each(|x| result.add_key(x.clone()));
+                }
+            }
+            JoinType::LeftOuter if preserve_left_keys => {
+                left.keys.iter().for_each(|x| result.add_key(x.clone()));
+            }
+            JoinType::RightOuter if preserve_right_keys => {
+                shifted.keys.iter().for_each(|x| result.add_key(x.clone()));
+            }
+            JoinType::LeftOuter | JoinType::RightOuter | JoinType::FullOuter => {}
+            JoinType::LeftSemi | JoinType::LeftAnti => return left.clone(),
+            JoinType::RightSemi | JoinType::RightAnti => return right.clone(),
+        }
+        result
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ColumnDependency {
+    Plan(FunctionalDependencies),
+    Expr(ExprInfo),
+}
+
+impl ColumnDependency {
+    /// The functional dependencies of a plan node, empty for expressions.
+    pub fn dependencies(&self) -> FunctionalDependencies {
+        match self {
+            Self::Plan(deps) => deps.clone(),
+            Self::Expr(_) => FunctionalDependencies::default(),
+        }
+    }
+
+    pub fn expr_info(&self) -> ExprInfo {
+        match self {
+            Self::Plan(_) => ExprInfo::default(),
+            Self::Expr(info) => info.clone(),
+        }
+    }
+}
+
+/// Split the equal columns of a join condition into (left column, right column) pairs, where the right columns are
+/// not shifted.
+fn join_equalities(cond: &ExprInfo, left_column_count: usize) -> Vec<(usize, usize)> {
+    cond.equalities
+        .iter()
+        .filter_map(|&(x, y)| {
+            if x < left_column_count && y >= left_column_count {
+                Some((x, y - left_column_count))
+            } else if y < left_column_count && x >= left_column_count {
+                Some((y, x - left_column_count))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Derives the candidate keys and functional dependencies of the output columns of each plan node. Keys of base
+/// tables come from the catalog, and are propagated through filters, joins, projections and aggregations.
+pub struct FunctionalDependencyPropertyBuilder {
+    catalog: Box<dyn Catalog>,
+}
+
+impl FunctionalDependencyPropertyBuilder {
+    pub fn new(catalog: Box<dyn Catalog>) -> Self {
+        Self { catalog }
+    }
+}
+
+impl PropertyBuilder<OptRelNodeTyp> for FunctionalDependencyPropertyBuilder {
+    type Prop = ColumnDependency;
+
+    fn derive(
+        &self,
+        typ: OptRelNodeTyp,
+        data: Option<Value>,
+        children: &[&Self::Prop],
+    ) -> Self::Prop {
+        let deps = match typ {
+            OptRelNodeTyp::Scan | OptRelNodeTyp::PhysicalScan => {
+                let Some(name) = data.as_ref().and_then(|x| x.as_str()) else {
+                    return ColumnDependency::Plan(FunctionalDependencies::new(0));
+                };
+                let mut deps = FunctionalDependencies::new(self.catalog.get(&name).len());
+                for key in self.catalog.get_keys(&name) {
+                    deps.add_key(key.into_iter().collect());
+                }
+                deps
+            }
+            OptRelNodeTyp::Values | OptRelNodeTyp::PhysicalValues => {
+                let (row_cnt, fields) = decode_values(data.as_ref()).unwrap_or_default();
+                let mut deps = FunctionalDependencies::new(fields.len());
+                if row_cnt <= 1 {
+                    // any set of columns, even an empty one, is unique in at most one row
+                    deps.add_key(BTreeSet::new());
+                }
+                deps
+            }
+            OptRelNodeTyp::EmptyRelation | OptRelNodeTyp::PhysicalEmptyRelation => {
+                let fields = decode_empty_relation(data.as_ref()).unwrap_or_default();
+                let mut deps = FunctionalDependencies::new(fields.len());
+                deps.add_key(BTreeSet::new());
+                deps
+            }
+            OptRelNodeTyp::Distinct => {
+                let mut deps = children[0].dependencies();
+                deps.add_key((0..deps.column_count).collect());
+                deps
+            }
+            OptRelNodeTyp::Filter | OptRelNodeTyp::PhysicalFilter => {
+                let mut deps = children[0].dependencies();
+                deps.add_predicate(&children[1].expr_info());
+                deps
+            }
+            OptRelNodeTyp::Projection | OptRelNodeTyp::PhysicalProjection => {
+                let mapping = children[1].expr_info().element_columns();
+                children[0].dependencies().project(&mapping)
+            }
+            OptRelNodeTyp::Agg | OptRelNodeTyp::PhysicalAgg => {
+                // the output columns are the group by columns followed by the aggregations, and the group by columns
+                // are unique in the output
+                let group_mapping = children[2].expr_info().element_columns();
+                let agg_count = children[1].expr_info().elements.len();
+                let mut deps = children[0].dependencies().project(&group_mapping);
+                deps.column_count += agg_count;
+                deps.add_key((0..group_mapping.len()).collect());
+                deps
+            }
+            OptRelNodeTyp::Window | OptRelNodeTyp::PhysicalWindow => {
+                // a window keeps all rows of its child and appends the window functions
+                let mut deps = children[0].dependencies();
+                deps.column_count += children[1].expr_info().elements.len();
+                deps
+            }
+            OptRelNodeTyp::Sort
+            | OptRelNodeTyp::PhysicalSort
+            | OptRelNodeTyp::Limit
+            | OptRelNodeTyp::PhysicalLimit
+            | OptRelNodeTyp::PhysicalTopN
+            | OptRelNodeTyp::PhysicalCollector(_) => children[0].dependencies(),
+            OptRelNodeTyp::Join(join_type) | OptRelNodeTyp::PhysicalNestedLoopJoin(join_type) => {
+                let (left, right) = (children[0].dependencies(), children[1].dependencies());
+                let cond = children[2].expr_info();
+                let equalities = join_equalities(&cond, left.column_count);
+                FunctionalDependencies::join(&left, &right, join_type, &equalities, &cond)
+            }
+            OptRelNodeTyp::PhysicalHashJoin(join_type)
+            | OptRelNodeTyp::PhysicalSortMergeJoin(join_type) => {
+                let (left, right) = (children[0].dependencies(), children[1].dependencies());
+                let left_keys = children[2].expr_info().element_columns();
+                let right_keys = children[3].expr_info().element_columns();
+                let cond = children[4].expr_info();
+                let mut equalities = left_keys
+                    .into_iter()
+                    .zip(right_keys)
+                    .filter_map(|(x, y)| Some((x?, y?)))
+                    .collect::<Vec<_>>();
+                equalities.extend(join_equalities(&cond, left.column_count));
+                FunctionalDependencies::join(&left, &right, join_type, &equalities, &cond)
+            }
+            OptRelNodeTyp::Apply(apply_type) => {
+                let (left, right) = (children[0].dependencies(), children[1].dependencies());
+                let cond = children[2].expr_info();
+                let equalities = join_equalities(&cond, left.column_count);
+                FunctionalDependencies::join(
+                    &left,
+                    &right,
+                    apply_type.to_join_type(),
+                    &equalities,
+                    &cond,
+                )
+            }
+            OptRelNodeTyp::Union(union_type) | OptRelNodeTyp::PhysicalUnion(union_type) => {
+                // the dependencies of one side do not hold for the rows of the other side
+                let column_count = children[0].dependencies().column_count;
+                let mut deps = FunctionalDependencies::new(column_count);
+                if union_type == UnionType::Distinct {
+                    deps.add_key((0..column_count).collect());
+                }
+                deps
+            }
+            OptRelNodeTyp::Placeholder(_) => unreachable!("should not derive a placeholder"),
+            _ => {
+                let children = children.iter().map(|x| x.expr_info()).collect::<Vec<_>>();
+                let children = children.iter().collect::<Vec<_>>();
+                return ColumnDependency::Expr(ExprInfo::derive(&typ, &data, &children));
+            }
+        };
+        ColumnDependency::Plan(deps)
+    }
+
+    fn property_name(&self) -> &'static str {
+        "functional_dependency"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use optd_core::{property::PropertyBuilder, rel_node::RelNode};
+
+    use super::{FunctionalDependencies, FunctionalDependencyPropertyBuilder};
+    use crate::{plan_nodes::OptRelNodeTyp, testing::TestCatalog};
+
+    fn derive(plan: &str) -> FunctionalDependencies {
+        fn derive_node(
+            builder: &FunctionalDependencyPropertyBuilder,
+            node: &RelNode<OptRelNodeTyp>,
+        ) -> super::ColumnDependency {
+            let children = node
+                .children
+                .iter()
+                .map(|x| derive_node(builder, x))
+                .collect::<Vec<_>>();
+            let children = children.iter().collect::<Vec<_>>();
+            builder.derive(node.typ.clone(), node.data.clone(), &children)
+        }
+        let builder = FunctionalDependencyPropertyBuilder::new(Box::new(TestCatalog));
+        derive_node(&builder, &plan.parse().unwrap()).dependencies()
+    }
+
+    fn set<const N: usize>(columns: [usize; N]) -> BTreeSet<usize> {
+        columns.into()
+    }
+
+    #[test]
+    fn scan() {
+        let deps = derive(r#"(Scan "t1")"#);
+        assert_eq!(deps.column_count, 3);
+        assert_eq!(deps.keys, vec![set([0])]);
+        assert!(deps.determines(&set([0]), &set([1, 2])));
+        assert!(!deps.is_unique(&set([1, 2])));
+    }
+
+    #[test]
+    fn filter() {
+        let deps = derive(
+            r#"(Filter (Scan "t1") (BinOp(And) (BinOp(Eq) (ColumnRef 1) (ColumnRef 2)) (BinOp(Eq) (ColumnRef 0) (Constant(Int) 1))))"#,
+        );
+        assert!(deps.determines(&set([1]), &set([2])));
+        assert!(deps.determines(&set([2]), &set([1])));
+        // the key is constant, so there is at most one row
+        assert!(deps.is_unique(&set([])));
+    }
+
+    #[test]
+    fn join() {
+        let join = |join_type: &str| {
+            derive(&format!(
+                r#"(Join({join_type}) (Scan "t1") (Filter (Scan "t2") (BinOp(Eq) (ColumnRef 1) (Constant(Int) 1))) (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))"#
+            ))
+        };
+
+        let deps = join("Inner");
+        assert_eq!(deps.column_count, 6);
+        // both sides match at most one row on their keys
+        assert!(deps.is_unique(&set([0])));
+        assert!(deps.is_unique(&set([3])));
+        assert!(deps.determines(&set([]), &set([4])));
+
+        let deps = join("LeftOuter");
+        assert!(deps.is_unique(&set([0])));
+        assert!(!deps.is_unique(&set([3])));
+        // the constant of the right side is null in the rows padded with nulls
+        assert!(!deps.determines(&set([]), &set([4])));
+
+        let deps = join("RightOuter");
+        assert!(deps.is_unique(&set([3])));
+        assert!(deps.determines(&set([]), &set([4])));
+
+        let deps = join("FullOuter");
+        assert!(deps.is_unique(&set([0, 3])));
+        assert!(!deps.is_unique(&set([0])));
+        assert!(!deps.determines(&set([]), &set([4])));
+
+        let deps = join("LeftSemi");
+        assert_eq!(deps.column_count, 3);
+        assert!(deps.is_unique(&set([0])));
+    }
+
+    #[test]
+    fn agg() {
+        // group by b, sum(c)
+        let deps = derive(
+            r#"(Agg (Scan "t1") (List (Func(Agg(Sum)) (List (ColumnRef 2)))) (List (ColumnRef 1)))"#,
+        );
+        assert_eq!(deps.column_count, 2);
+        assert_eq!(deps.keys, vec![set([0])]);
+
+        // group by a, b, where a is a key of the input
+        let deps = derive(
+            r#"(Agg (Scan "t1") (List (Func(Agg(Sum)) (List (ColumnRef 2)))) (List (ColumnRef 0) (ColumnRef 1)))"#,
+        );
+        assert_eq!(deps.column_count, 3);
+        assert!(deps.is_unique(&set([0])));
+        assert!(deps.determines(&set([0]), &set([1, 2])));
+    }
+}
@@ -0,0 +1,41 @@
+use std::collections::BTreeSet;
+
+use optd_core::{property::PropertyBuilder, rel_node::Value};
+
+use crate::plan_nodes::{decode_column_index, OptRelNodeTyp};
+
+/// Derives the columns of the left child of the enclosing `Apply` that a plan node or an expression refers to through
+/// `ExternColumnRef`s. A node without outer references is not correlated, and an `Apply` with such a right child can
+/// be evaluated as a join.
+pub struct OuterReferencesPropertyBuilder;
+
+impl OuterReferencesPropertyBuilder {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl PropertyBuilder<OptRelNodeTyp> for OuterReferencesPropertyBuilder {
+    type Prop = BTreeSet<usize>;
+
+    fn derive(
+        &self,
+        typ: OptRelNodeTyp,
+        data: Option<Value>,
+        children: &[&Self::Prop],
+    ) -> Self::Prop {
+        match typ {
+            OptRelNodeTyp::ExternColumnRef => {
+                decode_column_index(data.as_ref()).into_iter().collect()
+            }
+            // the outer references of the right child are bound to the left child
+            OptRelNodeTyp::Apply(_) => children[0].union(children[2]).copied().collect(),
+            OptRelNodeTyp::Placeholder(_) => unreachable!("should not derive a placeholder"),
+            _ => children.iter().flat_map(|x| x.iter().copied()).collect(),
+        }
+    }
+
+    fn property_name(&self) -> &'static str {
+        "outer_references"
+    }
+}
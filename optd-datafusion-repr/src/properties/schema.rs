@@ -1,23 +1,312 @@
-use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
+use std::sync::Arc;
+
+use itertools::Itertools;
+use optd_core::{property::PropertyBuilder, rel_node::Value};
+
+use crate::plan_nodes::{
+    decode_column_index, decode_empty_relation, decode_values, BinOpType, ConstantType, FuncType,
+    JoinType, LogOpType, OptRelNodeTyp, SortOrderType, UnOpType,
 };
 
-use optd_core::property::PropertyBuilder;
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub typ: ConstantType,
+    pub nullable: bool,
+}
 
-use crate::plan_nodes::{ConstantType, OptRelNodeTyp};
+impl Field {
+    fn into_nullable(self) -> Self {
+        Self {
+            nullable: true,
+            ..self
+        }
+    }
+}
 
-#[derive(Clone, Debug)]
-pub struct Schema(pub Vec<ConstantType>);
+#[derive(Clone, Debug, Default)]
+pub struct Schema {
+    pub fields: Vec<Field>,
+    /// The output fields of an expression (or of each element of an expression list), which are resolved against the
+    /// input schema of the plan node using the expression. Empty for plan nodes.
+    exprs: Vec<ExprField>,
+}
 
 impl Schema {
+    pub fn new(fields: Vec<Field>) -> Self {
+        Self {
+            fields,
+            exprs: vec![],
+        }
+    }
+
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    fn expr(expr: ExprField) -> Self {
+        Self {
+            fields: vec![],
+            exprs: vec![expr],
+        }
+    }
+
+    /// Resolve the output fields of the expressions against the input schema.
+    fn resolve_exprs(&self, input: &Schema) -> Vec<Field> {
+        self.exprs.iter().map(|x| x.resolve(input)).collect()
+    }
+}
+
+/// How to display an expression given the names of its inputs.
+#[derive(Clone, Debug)]
+enum ExprName {
+    Literal(String),
+    Prefix(&'static str),
+    Infix(&'static str),
+    Postfix(&'static str),
+    Call(String),
+    /// `CASE WHEN <cond> THEN <then> ELSE <else> END`, whose type is the common type of the branches.
+    Case,
+}
+
+/// The output field of an expression before the types of the input columns are known.
+#[derive(Clone, Debug)]
+enum ExprField {
+    Column(usize),
+    Derived {
+        name: ExprName,
+        /// The output type, or the common type of the inputs if `None`.
+        typ: Option<ConstantType>,
+        /// Whether the output is nullable, or whether any input is nullable if `None`.
+        nullable: Option<bool>,
+        inputs: Vec<ExprField>,
+    },
+}
+
+impl ExprField {
+    fn resolve(&self, input: &Schema) -> Field {
+        match self {
+            Self::Column(idx) => input.fields.get(*idx).cloned().unwrap_or_else(|| Field {
+                name: format!("#{}", idx),
+                typ: ConstantType::Any,
+                nullable: true,
+            }),
+            Self::Derived {
+                name,
+                typ,
+                nullable,
+                inputs,
+            } => {
+                let inputs = inputs.iter().map(|x| x.resolve(input)).collect_vec();
+                let typed_inputs = match name {
+                    ExprName::Case => &inputs[1..],
+                    _ => &inputs[..],
+                };
+                let typ = typ.unwrap_or_else(|| common_type(typed_inputs.iter().map(|x| x.typ)));
+                let nullable = nullable.unwrap_or_else(|| typed_inputs.iter().any(|x| x.nullable));
+                let name = match name {
+                    ExprName::Literal(x) => x.clone(),
+                    ExprName::Prefix(op) => format!("{}{}", op, inputs[0].name),
+                    ExprName::Infix(op) => inputs.iter().map(|x| &x.name).join(op),
+                    ExprName::Postfix(op) => format!("{}{}", inputs[0].name, op),
+                    ExprName::Call(func) => {
+                        format!("{}({})", func, inputs.iter().map(|x| &x.name).join(", "))
+                    }
+                    ExprName::Case => format!(
+                        "CASE WHEN {} THEN {} ELSE {} END",
+                        inputs[0].name, inputs[1].name, inputs[2].name
+                    ),
+                };
+                Field {
+                    name,
+                    typ,
+                    nullable,
+                }
+            }
+        }
+    }
+}
+
+/// The type that all the given types can be converted to.
+fn common_type(types: impl Iterator<Item = ConstantType>) -> ConstantType {
+    types
+        .filter(|x| *x != ConstantType::Any)
+        .reduce(|x, y| match (x, y) {
+            (x, y) if x == y => x,
+            (ConstantType::Int, x @ ConstantType::Decimal(..))
+            | (x @ ConstantType::Decimal(..), ConstantType::Int) => x,
+            (ConstantType::Decimal(p1, s1), ConstantType::Decimal(p2, s2)) => {
+                ConstantType::Decimal(p1.max(p2), s1.max(s2))
+            }
+            (ConstantType::Int | ConstantType::Decimal(..), ConstantType::Float)
+            | (ConstantType::Float, ConstantType::Int | ConstantType::Decimal(..)) => {
+                ConstantType::Float
+            }
+            (x @ (ConstantType::Date | ConstantType::Timestamp(..)), ConstantType::Interval(_))
+            | (ConstantType::Interval(_), x @ (ConstantType::Date | ConstantType::Timestamp(..))) => {
+                x
+            }
+            _ => ConstantType::Any,
+        })
+        .unwrap_or(ConstantType::Any)
+}
+
+fn derive_expr(typ: &OptRelNodeTyp, data: Option<Value>, children: &[&Schema]) -> ExprField {
+    let inputs = |children: &[&Schema]| {
+        children
+            .iter()
+            .flat_map(|x| x.exprs.iter().cloned())
+            .collect_vec()
+    };
+    let derived = |name, typ, nullable, inputs| ExprField::Derived {
+        name,
+        typ,
+        nullable,
+        inputs,
+    };
+    match typ {
+        OptRelNodeTyp::ColumnRef => match decode_column_index(data.as_ref()) {
+            Some(idx) => ExprField::Column(idx),
+            None => derived(
+                ExprName::Literal("#?".to_string()),
+                Some(ConstantType::Any),
+                Some(true),
+                vec![],
+            ),
+        },
+        OptRelNodeTyp::ExternColumnRef => {
+            // the schema of the left child of the enclosing apply is not known here
+            let idx = decode_column_index(data.as_ref()).map_or("?".to_string(), |x| x.to_string());
+            derived(
+                ExprName::Literal(format!("Extern(#{})", idx)),
+                None,
+                Some(true),
+                vec![],
+            )
+        }
+        OptRelNodeTyp::Constant(typ) => {
+            let value = data.unwrap_or(Value::Null);
+            let nullable = value.is_null();
+            derived(
+                ExprName::Literal(value.to_string()),
+                Some(*typ),
+                Some(nullable),
+                vec![],
+            )
+        }
+        OptRelNodeTyp::UnOp(op) => {
+            let (name, typ) = match op {
+                UnOpType::Neg => ("-", None),
+                UnOpType::Not => ("NOT ", Some(ConstantType::Bool)),
+            };
+            derived(ExprName::Prefix(name), typ, None, inputs(children))
+        }
+        OptRelNodeTyp::BinOp(op) => {
+            let (name, typ) = match op {
+                BinOpType::Add => (" + ", None),
+                BinOpType::Sub => (" - ", None),
+                BinOpType::Mul => (" * ", None),
+                BinOpType::Div => (" / ", None),
+                BinOpType::Mod => (" % ", None),
+                BinOpType::Eq => (" = ", Some(ConstantType::Bool)),
+                BinOpType::Neq => (" != ", Some(ConstantType::Bool)),
+                BinOpType::Gt => (" > ", Some(ConstantType::Bool)),
+                BinOpType::Lt => (" < ", Some(ConstantType::Bool)),
+                BinOpType::Geq => (" >= ", Some(ConstantType::Bool)),
+                BinOpType::Leq => (" <= ", Some(ConstantType::Bool)),
+                BinOpType::And => (" AND ", Some(ConstantType::Bool)),
+                BinOpType::Or => (" OR ", Some(ConstantType::Bool)),
+                BinOpType::Xor => (" XOR ", Some(ConstantType::Bool)),
+                BinOpType::IsNotDistinctFrom => {
+                    (" IS NOT DISTINCT FROM ", Some(ConstantType::Bool))
+                }
+            };
+            derived(ExprName::Infix(name), typ, None, inputs(children))
+        }
+        OptRelNodeTyp::LogOp(op) => {
+            let name = match op {
+                LogOpType::And => " AND ",
+                LogOpType::Or => " OR ",
+            };
+            derived(
+                ExprName::Infix(name),
+                Some(ConstantType::Bool),
+                None,
+                inputs(children),
+            )
+        }
+        OptRelNodeTyp::Func(FuncType::Case) => {
+            derived(ExprName::Case, None, None, inputs(children))
+        }
+        OptRelNodeTyp::Func(FuncType::Scalar(func)) => derived(
+            ExprName::Call(func.to_string()),
+            None,
+            None,
+            inputs(children),
+        ),
+        OptRelNodeTyp::Func(FuncType::Agg(func)) => {
+            use datafusion_expr::AggregateFunction;
+            let (typ, nullable) = match func {
+                AggregateFunction::Count => (Some(ConstantType::Int), Some(false)),
+                // the average of decimals is a decimal with a larger scale, which is not tracked here
+                AggregateFunction::Avg => (Some(ConstantType::Float), Some(true)),
+                _ => (None, Some(true)),
+            };
+            derived(
+                ExprName::Call(func.to_string()),
+                typ,
+                nullable,
+                inputs(children),
+            )
+        }
+        OptRelNodeTyp::Func(FuncType::Window(func)) => {
+            use datafusion_expr::BuiltInWindowFunction;
+            let (typ, nullable) = match func {
+                BuiltInWindowFunction::RowNumber
+                | BuiltInWindowFunction::Rank
+                | BuiltInWindowFunction::DenseRank
+                | BuiltInWindowFunction::Ntile => (Some(ConstantType::Int), Some(false)),
+                _ => (None, Some(true)),
+            };
+            derived(
+                ExprName::Call(func.to_string()),
+                typ,
+                nullable,
+                inputs(children),
+            )
+        }
+        OptRelNodeTyp::SortOrder(order) => {
+            let name = match order {
+                SortOrderType::Asc => " ASC",
+                SortOrderType::Desc => " DESC",
+            };
+            derived(ExprName::Postfix(name), None, None, inputs(children))
+        }
+        _ => unreachable!("{} is not an expression", typ),
     }
 }
 
 pub trait Catalog: Send + Sync + 'static {
     fn get(&self, name: &str) -> Schema;
+
+    /// The candidate keys of the table, each as a list of column indices.
+    fn get_keys(&self, _name: &str) -> Vec<Vec<usize>> {
+        vec![]
+    }
+}
+
+impl<C: Catalog + ?Sized> Catalog for Arc<C> {
+    fn get(&self, name: &str) -> Schema {
+        (**self).get(name)
+    }
+
+    fn get_keys(&self, name: &str) -> Vec<Vec<usize>> {
+        (**self).get_keys(name)
+    }
 }
 
 pub struct SchemaPropertyBuilder {
@@ -40,19 +329,75 @@ impl PropertyBuilder<OptRelNodeTyp> for SchemaPropertyBuilder {
         children: &[&Self::Prop],
     ) -> Self::Prop {
         match typ {
-            OptRelNodeTyp::Scan => {
-                let name = data.unwrap().as_str().to_string();
+            OptRelNodeTyp::Scan | OptRelNodeTyp::PhysicalScan => {
+                let Some(name) = data.as_ref().and_then(|x| x.as_str()) else {
+                    return Schema::new(vec![]);
+                };
                 self.catalog.get(&name)
             }
-            OptRelNodeTyp::Projection => children[1].clone(),
-            OptRelNodeTyp::Filter => children[0].clone(),
-            OptRelNodeTyp::Join(_) => {
-                let mut schema = children[0].clone();
-                schema.0.extend(children[1].clone().0);
-                schema
+            OptRelNodeTyp::Values | OptRelNodeTyp::PhysicalValues => Schema::new(
+                decode_values(data.as_ref())
+                    .map(|(_, fields)| fields)
+                    .unwrap_or_default(),
+            ),
+            OptRelNodeTyp::EmptyRelation | OptRelNodeTyp::PhysicalEmptyRelation => {
+                Schema::new(decode_empty_relation(data.as_ref()).unwrap_or_default())
+            }
+            OptRelNodeTyp::Projection | OptRelNodeTyp::PhysicalProjection => {
+                Schema::new(children[1].resolve_exprs(children[0]))
             }
-            OptRelNodeTyp::List => Schema(vec![ConstantType::Any; children.len()]),
-            _ => Schema(vec![]),
+            OptRelNodeTyp::Agg | OptRelNodeTyp::PhysicalAgg => {
+                // the output columns are the group by columns followed by the aggregations
+                let mut fields = children[2].resolve_exprs(children[0]);
+                fields.extend(children[1].resolve_exprs(children[0]));
+                Schema::new(fields)
+            }
+            OptRelNodeTyp::Window | OptRelNodeTyp::PhysicalWindow => {
+                // the output columns are the columns of the child followed by the window functions
+                let mut fields = children[0].fields.clone();
+                fields.extend(children[1].resolve_exprs(children[0]));
+                Schema::new(fields)
+            }
+            OptRelNodeTyp::Filter
+            | OptRelNodeTyp::PhysicalFilter
+            | OptRelNodeTyp::Distinct
+            | OptRelNodeTyp::Sort
+            | OptRelNodeTyp::PhysicalSort
+            | OptRelNodeTyp::Limit
+            | OptRelNodeTyp::PhysicalLimit
+            | OptRelNodeTyp::PhysicalTopN
+            | OptRelNodeTyp::PhysicalCollector(_) => children[0].clone(),
+            OptRelNodeTyp::Join(join_type)
+            | OptRelNodeTyp::PhysicalNestedLoopJoin(join_type)
+            | OptRelNodeTyp::PhysicalHashJoin(join_type)
+            | OptRelNodeTyp::PhysicalSortMergeJoin(join_type) => {
+                join_schema(children[0], children[1], join_type)
+            }
+            OptRelNodeTyp::Apply(apply_type) => {
+                join_schema(children[0], children[1], apply_type.to_join_type())
+            }
+            OptRelNodeTyp::Union(_) | OptRelNodeTyp::PhysicalUnion(_) => {
+                // the columns are named after the left child, and are nullable if either side is nullable
+                let fields = children[0]
+                    .fields
+                    .iter()
+                    .zip(&children[1].fields)
+                    .map(|(left, right)| Field {
+                        nullable: left.nullable || right.nullable,
+                        ..left.clone()
+                    })
+                    .collect();
+                Schema::new(fields)
+            }
+            OptRelNodeTyp::List => Schema {
+                fields: vec![],
+                exprs: children
+                    .iter()
+                    .flat_map(|x| x.exprs.iter().cloned())
+                    .collect(),
+            },
+            OptRelNodeTyp::Placeholder(_) => unreachable!("should not derive a placeholder"),
+            _ => Schema::expr(derive_expr(&typ, data, children)),
         }
     }
 
@@ -60,3 +405,26 @@ impl PropertyBuilder<OptRelNodeTyp> for SchemaPropertyBuilder {
         "schema"
     }
 }
+
+fn join_schema(left: &Schema, right: &Schema, join_type: JoinType) -> Schema {
+    let (left_nullable, right_nullable) = match join_type {
+        JoinType::Inner | JoinType::Cross => (false, false),
+        JoinType::LeftOuter => (false, true),
+        JoinType::RightOuter => (true, false),
+        JoinType::FullOuter => (true, true),
+        JoinType::LeftSemi | JoinType::LeftAnti => return left.clone(),
+        JoinType::RightSemi | JoinType::RightAnti => return right.clone(),
+    };
+    let left = left
+        .fields
+        .iter()
+        .cloned()
+        .map(|x| if left_nullable { x.into_nullable() } else { x });
+    let right =
+        right
+            .fields
+            .iter()
+            .cloned()
+            .map(|x| if right_nullable { x.into_nullable() } else { x });
+    Schema::new(left.chain(right).collect())
+}
@@ -1,8 +1,32 @@
-// mod filter_join;
+mod decorrelation;
+mod distinct;
+mod eager_aggregation;
+mod empty_relation;
+mod filter_pushdown;
 mod joins;
+mod limit;
 mod macros;
+mod outer_joins;
 mod physical;
+mod projection_pushdown;
 
-// pub use filter_join::FilterJoinPullUpRule;
-pub use joins::{HashJoinRule, JoinAssocRule, JoinCommuteRule, ProjectionPullUpJoin};
+pub use decorrelation::{
+    ApplyFilterPullUpRule, ApplyProjectionPullUpRule, ApplyScalarAggRule, ApplyToJoinRule,
+};
+pub use distinct::DistinctToAggRule;
+pub use eager_aggregation::EagerAggregationRule;
+pub use empty_relation::{EliminateFilterRule, EmptyRelationPropagationRule};
+pub use filter_pushdown::{
+    FilterAggTransposeRule, FilterJoinPushdownRule, FilterMergeRule, FilterProjectionTransposeRule,
+    FilterUnionTransposeRule, JoinCondPushdownRule,
+};
+pub use joins::{
+    HashJoinRule, JoinAssocRule, JoinCommuteRule, ProjectionPullUpJoin, SortMergeJoinRule,
+};
+pub use limit::TopNRule;
+pub use outer_joins::{LeftJoinAssocRule, LeftJoinCommuteRule, OuterJoinToInnerRule};
 pub use physical::PhysicalConversionRule;
+pub use projection_pushdown::{
+    AggInputPruningRule, EliminateProjectionRule, ProjectionFilterTransposeRule,
+    ProjectionJoinTransposeRule, ProjectionMergeRule, ProjectionUnionTransposeRule,
+};
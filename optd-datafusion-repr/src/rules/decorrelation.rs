@@ -0,0 +1,584 @@
+//! Rules that rewrite a correlated `Apply` into joins and aggregations, by pulling the nodes of its right child that
+//! refer to the left child up into the apply, until the right child is no longer correlated and the apply becomes a
+//! join.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use datafusion_expr::AggregateFunction;
+use optd_core::optimizer::Optimizer;
+use optd_core::rel_node::RelNode;
+use optd_core::rules::{Rule, RuleMatcher};
+
+use super::filter_pushdown::{
+    filter_if_needed, merge_conjunction, rewrite_column_refs, split_conjunction,
+};
+use super::outer_joins::propagates_nulls;
+use crate::plan_nodes::{
+    ApplyType, BinOpExpr, BinOpType, ColumnRefExpr, Expr, ExprList, ExternColumnRefExpr, FuncType,
+    JoinType, LogicalAgg, LogicalApply, LogicalJoin, LogicalProjection, OptRelNode, OptRelNodeRef,
+    OptRelNodeTyp, PlanNode,
+};
+use crate::properties::outer_references::OuterReferencesPropertyBuilder;
+use crate::properties::schema::SchemaPropertyBuilder;
+
+const LEFT: usize = 0;
+const COND: usize = 1;
+const RIGHT: usize = 2;
+/// The predicate of a filter or the expressions of a projection in the right child.
+const RIGHT_EXPRS: usize = 3;
+const AGG_EXPRS: usize = 4;
+const AGG_GROUPS: usize = 5;
+
+/// Matches an `Apply` of the given type, whose right child is matched by `right`.
+fn apply_matcher(
+    apply_type: ApplyType,
+    right: RuleMatcher<OptRelNodeTyp>,
+) -> RuleMatcher<OptRelNodeTyp> {
+    RuleMatcher::MatchNode {
+        typ: OptRelNodeTyp::Apply(apply_type),
+        children: vec![
+            RuleMatcher::PickOne {
+                pick_to: LEFT,
+                expand: false,
+            },
+            right,
+            RuleMatcher::PickOne {
+                pick_to: COND,
+                expand: true,
+            },
+        ],
+    }
+}
+
+/// Matches a filter or a projection, whose children are the right child of the apply and its expressions.
+fn right_matcher(typ: OptRelNodeTyp) -> RuleMatcher<OptRelNodeTyp> {
+    RuleMatcher::MatchNode {
+        typ,
+        children: vec![
+            RuleMatcher::PickOne {
+                pick_to: RIGHT,
+                expand: false,
+            },
+            RuleMatcher::PickOne {
+                pick_to: RIGHT_EXPRS,
+                expand: true,
+            },
+        ],
+    }
+}
+
+fn size_of(optimizer: &impl Optimizer<OptRelNodeTyp>, node: &RelNode<OptRelNodeTyp>) -> usize {
+    optimizer
+        .get_property::<SchemaPropertyBuilder>(Arc::new(node.clone()), 0)
+        .len()
+}
+
+fn is_correlated(optimizer: &impl Optimizer<OptRelNodeTyp>, node: &RelNode<OptRelNodeTyp>) -> bool {
+    !optimizer
+        .get_property::<OuterReferencesPropertyBuilder>(Arc::new(node.clone()), 3)
+        .is_empty()
+}
+
+/// Whether the expression contains an `ExternColumnRef`.
+pub(crate) fn has_outer_refs(expr: &OptRelNodeRef) -> bool {
+    expr.typ == OptRelNodeTyp::ExternColumnRef || expr.children.iter().any(has_outer_refs)
+}
+
+/// Rewrite an expression on the right child of an apply to an expression on the output of the apply, where the
+/// outer references become references to the left child.
+fn bind_outer_refs(expr: Expr, left_size: usize) -> Expr {
+    fn bind(expr: OptRelNodeRef, left_size: usize) -> OptRelNodeRef {
+        if let Some(column) = ColumnRefExpr::from_rel_node(expr.clone()) {
+            return ColumnRefExpr::new(column.index() + left_size).into_rel_node();
+        }
+        if let Some(column) = ExternColumnRefExpr::from_rel_node(expr.clone()) {
+            return ColumnRefExpr::new(column.index()).into_rel_node();
+        }
+        RelNode {
+            typ: expr.typ.clone(),
+            children: expr
+                .children
+                .iter()
+                .map(|x| bind(x.clone(), left_size))
+                .collect(),
+            data: expr.data.clone(),
+        }
+        .into()
+    }
+    Expr::from_rel_node(bind(expr.into_rel_node(), left_size)).unwrap()
+}
+
+/// Rewrites an apply whose right child is not correlated into a join.
+pub struct ApplyToJoinRule {
+    matcher: RuleMatcher<OptRelNodeTyp>,
+    apply_type: ApplyType,
+    name: &'static str,
+}
+
+impl ApplyToJoinRule {
+    pub fn new(apply_type: ApplyType) -> Self {
+        Self {
+            matcher: apply_matcher(
+                apply_type,
+                RuleMatcher::PickOne {
+                    pick_to: RIGHT,
+                    expand: false,
+                },
+            ),
+            apply_type,
+            name: match apply_type {
+                ApplyType::Cross => "apply_to_join_cross",
+                ApplyType::LeftOuter => "apply_to_join_left_outer",
+                ApplyType::Semi => "apply_to_join_semi",
+                ApplyType::AntiSemi => "apply_to_join_anti_semi",
+            },
+        }
+    }
+
+    pub fn all_apply_types<O: Optimizer<OptRelNodeTyp>>() -> Vec<Arc<dyn Rule<OptRelNodeTyp, O>>> {
+        ApplyType::ALL
+            .into_iter()
+            .map(|x| Arc::new(Self::new(x)) as Arc<dyn Rule<OptRelNodeTyp, O>>)
+            .collect()
+    }
+}
+
+impl<O: Optimizer<OptRelNodeTyp>> Rule<OptRelNodeTyp, O> for ApplyToJoinRule {
+    fn matcher(&self) -> &RuleMatcher<OptRelNodeTyp> {
+        &self.matcher
+    }
+
+    fn apply(
+        &self,
+        optimizer: &O,
+        mut input: HashMap<usize, RelNode<OptRelNodeTyp>>,
+    ) -> Vec<RelNode<OptRelNodeTyp>> {
+        let left = input.remove(&LEFT).unwrap();
+        let right = input.remove(&RIGHT).unwrap();
+        let cond = input.remove(&COND).unwrap();
+        if is_correlated(optimizer, &right) {
+            return vec![];
+        }
+        let node = LogicalJoin::new(
+            PlanNode::from_group(left.into()),
+            PlanNode::from_group(right.into()),
+            Expr::from_rel_node(cond.into()).unwrap(),
+            // the condition of a cross apply may have been pulled up from the right child
+            match self.apply_type {
+                ApplyType::Cross => JoinType::Inner,
+                apply_type => apply_type.to_join_type(),
+            },
+        );
+        vec![node.into_rel_node().as_ref().clone()]
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        "rewrite an apply whose right child is not correlated into a join"
+    }
+}
+
+/// Apply(A, Filter(B, p), c) -> Apply(A, B, c and p), which holds for all types of apply.
+pub struct ApplyFilterPullUpRule {
+    matcher: RuleMatcher<OptRelNodeTyp>,
+    apply_type: ApplyType,
+    name: &'static str,
+}
+
+impl ApplyFilterPullUpRule {
+    pub fn new(apply_type: ApplyType) -> Self {
+        Self {
+            matcher: apply_matcher(apply_type, right_matcher(OptRelNodeTyp::Filter)),
+            apply_type,
+            name: match apply_type {
+                ApplyType::Cross => "apply_filter_pull_up_cross",
+                ApplyType::LeftOuter => "apply_filter_pull_up_left_outer",
+                ApplyType::Semi => "apply_filter_pull_up_semi",
+                ApplyType::AntiSemi => "apply_filter_pull_up_anti_semi",
+            },
+        }
+    }
+
+    pub fn all_apply_types<O: Optimizer<OptRelNodeTyp>>() -> Vec<Arc<dyn Rule<OptRelNodeTyp, O>>> {
+        ApplyType::ALL
+            .into_iter()
+            .map(|x| Arc::new(Self::new(x)) as Arc<dyn Rule<OptRelNodeTyp, O>>)
+            .collect()
+    }
+}
+
+impl<O: Optimizer<OptRelNodeTyp>> Rule<OptRelNodeTyp, O> for ApplyFilterPullUpRule {
+    fn matcher(&self) -> &RuleMatcher<OptRelNodeTyp> {
+        &self.matcher
+    }
+
+    fn apply(
+        &self,
+        optimizer: &O,
+        mut input: HashMap<usize, RelNode<OptRelNodeTyp>>,
+    ) -> Vec<RelNode<OptRelNodeTyp>> {
+        let left = input.remove(&LEFT).unwrap();
+        let right = input.remove(&RIGHT).unwrap();
+        let cond = Expr::from_rel_node(input.remove(&COND).unwrap().into()).unwrap();
+        let pred = Expr::from_rel_node(input.remove(&RIGHT_EXPRS).unwrap().into()).unwrap();
+        let mut conjuncts = split_conjunction(cond);
+        conjuncts.extend(split_conjunction(bind_outer_refs(
+            pred,
+            size_of(optimizer, &left),
+        )));
+        let node = LogicalApply::new(
+            PlanNode::from_group(left.into()),
+            PlanNode::from_group(right.into()),
+            merge_conjunction(conjuncts),
+            self.apply_type,
+        );
+        vec![node.into_rel_node().as_ref().clone()]
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        "pull a filter out of the right child of an apply into the apply condition"
+    }
+}
+
+/// Apply(A, Proj(B), c) -> Proj(Apply(A, B, c)), where the projected expressions are substituted into the condition.
+/// Semi and anti applies only output the columns of A, so no projection is needed. For left outer applies, the
+/// projected expressions must be null on the rows padded with nulls.
+pub struct ApplyProjectionPullUpRule {
+    matcher: RuleMatcher<OptRelNodeTyp>,
+    apply_type: ApplyType,
+    name: &'static str,
+}
+
+impl ApplyProjectionPullUpRule {
+    pub fn new(apply_type: ApplyType) -> Self {
+        Self {
+            matcher: apply_matcher(apply_type, right_matcher(OptRelNodeTyp::Projection)),
+            apply_type,
+            name: match apply_type {
+                ApplyType::Cross => "apply_projection_pull_up_cross",
+                ApplyType::LeftOuter => "apply_projection_pull_up_left_outer",
+                ApplyType::Semi => "apply_projection_pull_up_semi",
+                ApplyType::AntiSemi => "apply_projection_pull_up_anti_semi",
+            },
+        }
+    }
+
+    pub fn all_apply_types<O: Optimizer<OptRelNodeTyp>>() -> Vec<Arc<dyn Rule<OptRelNodeTyp, O>>> {
+        ApplyType::ALL
+            .into_iter()
+            .map(|x| Arc::new(Self::new(x)) as Arc<dyn Rule<OptRelNodeTyp, O>>)
+            .collect()
+    }
+}
+
+impl<O: Optimizer<OptRelNodeTyp>> Rule<OptRelNodeTyp, O> for ApplyProjectionPullUpRule {
+    fn matcher(&self) -> &RuleMatcher<OptRelNodeTyp> {
+        &self.matcher
+    }
+
+    fn apply(
+        &self,
+        optimizer: &O,
+        mut input: HashMap<usize, RelNode<OptRelNodeTyp>>,
+    ) -> Vec<RelNode<OptRelNodeTyp>> {
+        let left = input.remove(&LEFT).unwrap();
+        let right = input.remove(&RIGHT).unwrap();
+        let cond = Expr::from_rel_node(input.remove(&COND).unwrap().into()).unwrap();
+        let exprs = ExprList::from_rel_node(input.remove(&RIGHT_EXPRS).unwrap().into()).unwrap();
+        let (left_size, right_size) = (size_of(optimizer, &left), size_of(optimizer, &right));
+        let exprs = exprs
+            .to_vec()
+            .into_iter()
+            .map(|x| bind_outer_refs(x, left_size))
+            .collect::<Vec<_>>();
+        if self.apply_type == ApplyType::LeftOuter
+            && !exprs
+                .iter()
+                .all(|x| propagates_nulls(x, left_size..left_size + right_size))
+        {
+            return vec![];
+        }
+        let cond = rewrite_column_refs(cond, &|x| {
+            Some(if x < left_size {
+                ColumnRefExpr::new(x).into_expr()
+            } else {
+                exprs[x - left_size].clone()
+            })
+        })
+        .unwrap();
+        let node = LogicalApply::new(
+            PlanNode::from_group(left.into()),
+            PlanNode::from_group(right.into()),
+            cond,
+            self.apply_type,
+        );
+        let node = match self.apply_type {
+            ApplyType::Semi | ApplyType::AntiSemi => node.into_plan_node(),
+            ApplyType::Cross | ApplyType::LeftOuter => {
+                let mut output = (0..left_size)
+                    .map(|x| ColumnRefExpr::new(x).into_expr())
+                    .collect::<Vec<_>>();
+                output.extend(exprs);
+                LogicalProjection::new(node.into_plan_node(), ExprList::new(output))
+                    .into_plan_node()
+            }
+        };
+        vec![node.into_rel_node().as_ref().clone()]
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        "pull a projection out of the right child of an apply"
+    }
+}
+
+/// Apply(A, Agg(Filter(B, B.x = A.y and p)), true) -> Proj(A left join Agg(Filter(B, p)) group by B.x on A.y = B.x),
+/// for an aggregation without group by columns, which always outputs exactly one row. Aggregations that are not null
+/// on an empty input (i.e., counts) are not decorrelated, as the left outer join would pad them with nulls.
+pub struct ApplyScalarAggRule {
+    matcher: RuleMatcher<OptRelNodeTyp>,
+    name: &'static str,
+}
+
+impl ApplyScalarAggRule {
+    pub fn new(apply_type: ApplyType) -> Self {
+        Self {
+            matcher: apply_matcher(
+                apply_type,
+                RuleMatcher::MatchNode {
+                    typ: OptRelNodeTyp::Agg,
+                    children: vec![
+                        right_matcher(OptRelNodeTyp::Filter),
+                        RuleMatcher::PickOne {
+                            pick_to: AGG_EXPRS,
+                            expand: true,
+                        },
+                        RuleMatcher::PickOne {
+                            pick_to: AGG_GROUPS,
+                            expand: true,
+                        },
+                    ],
+                },
+            ),
+            name: match apply_type {
+                ApplyType::Cross => "apply_scalar_agg_cross",
+                ApplyType::LeftOuter => "apply_scalar_agg_left_outer",
+                _ => unimplemented!(
+                    "scalar aggregations are only decorrelated for cross and left outer applies"
+                ),
+            },
+        }
+    }
+
+    pub fn all_apply_types<O: Optimizer<OptRelNodeTyp>>() -> Vec<Arc<dyn Rule<OptRelNodeTyp, O>>> {
+        [ApplyType::Cross, ApplyType::LeftOuter]
+            .into_iter()
+            .map(|x| Arc::new(Self::new(x)) as Arc<dyn Rule<OptRelNodeTyp, O>>)
+            .collect()
+    }
+}
+
+impl<O: Optimizer<OptRelNodeTyp>> Rule<OptRelNodeTyp, O> for ApplyScalarAggRule {
+    fn matcher(&self) -> &RuleMatcher<OptRelNodeTyp> {
+        &self.matcher
+    }
+
+    fn apply(
+        &self,
+        optimizer: &O,
+        mut input: HashMap<usize, RelNode<OptRelNodeTyp>>,
+    ) -> Vec<RelNode<OptRelNodeTyp>> {
+        let left = input.remove(&LEFT).unwrap();
+        let right = input.remove(&RIGHT).unwrap();
+        let cond = Expr::from_rel_node(input.remove(&COND).unwrap().into()).unwrap();
+        let pred = Expr::from_rel_node(input.remove(&RIGHT_EXPRS).unwrap().into()).unwrap();
+        let exprs = ExprList::from_rel_node(input.remove(&AGG_EXPRS).unwrap().into()).unwrap();
+        let groups = ExprList::from_rel_node(input.remove(&AGG_GROUPS).unwrap().into()).unwrap();
+        if !groups.is_empty() || !split_conjunction(cond).is_empty() {
+            return vec![];
+        }
+        fn has_count(expr: &OptRelNodeRef) -> bool {
+            expr.typ == OptRelNodeTyp::Func(FuncType::Agg(AggregateFunction::Count))
+                || expr.children.iter().any(has_count)
+        }
+        let exprs_node = exprs.clone().into_rel_node();
+        if has_count(&exprs_node) || has_outer_refs(&exprs_node) || is_correlated(optimizer, &right)
+        {
+            return vec![];
+        }
+        // split the predicate into the equalities with outer references, and the rest that is not correlated
+        let mut outer_keys = vec![];
+        let mut inner_keys = vec![];
+        let mut rest = vec![];
+        for conjunct in split_conjunction(pred) {
+            let node = conjunct.clone().into_rel_node();
+            if !has_outer_refs(&node) {
+                rest.push(conjunct);
+                continue;
+            }
+            if node.typ != OptRelNodeTyp::BinOp(BinOpType::Eq) {
+                return vec![];
+            }
+            let (outer, inner) = match (
+                ExternColumnRefExpr::from_rel_node(node.child(0)),
+                ExternColumnRefExpr::from_rel_node(node.child(1)),
+            ) {
+                (Some(outer), None) => (outer, node.child(1)),
+                (None, Some(outer)) => (outer, node.child(0)),
+                _ => return vec![],
+            };
+            if has_outer_refs(&inner) {
+                return vec![];
+            }
+            outer_keys.push(outer.index());
+            inner_keys.push(Expr::from_rel_node(inner).unwrap());
+        }
+        if outer_keys.is_empty() {
+            return vec![];
+        }
+        let left_size = size_of(optimizer, &left);
+        let key_count = inner_keys.len();
+        let agg_count = exprs.len();
+        let agg = LogicalAgg::new(
+            filter_if_needed(PlanNode::from_group(right.into()), rest),
+            exprs,
+            ExprList::new(inner_keys),
+        );
+        // the output columns of the aggregation are the keys followed by the aggregations
+        let join_cond = outer_keys
+            .into_iter()
+            .enumerate()
+            .map(|(idx, outer)| {
+                BinOpExpr::new(
+                    ColumnRefExpr::new(outer).into_expr(),
+                    ColumnRefExpr::new(left_size + idx).into_expr(),
+                    BinOpType::Eq,
+                )
+                .into_expr()
+            })
+            .collect();
+        let join = LogicalJoin::new(
+            PlanNode::from_group(left.into()),
+            agg.into_plan_node(),
+            merge_conjunction(join_cond),
+            JoinType::LeftOuter,
+        );
+        let output = (0..left_size)
+            .chain(left_size + key_count..left_size + key_count + agg_count)
+            .map(|x| ColumnRefExpr::new(x).into_expr())
+            .collect();
+        let node = LogicalProjection::new(join.into_plan_node(), ExprList::new(output));
+        vec![node.into_rel_node().as_ref().clone()]
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        "decorrelate an apply over a scalar aggregation into a left outer join and an aggregation"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{ApplyProjectionPullUpRule, ApplyScalarAggRule};
+    use crate::{plan_nodes::ApplyType, testing::assert_rule};
+
+    #[test]
+    fn apply_projection_pull_up() {
+        let input = |apply_type: &str, expr: &str| {
+            format!(
+                r#"
+                    (Apply({apply_type}) (Scan "t1") (Projection (Scan "t2") (List {expr})) (Constant(Bool) true))
+                "#
+            )
+        };
+        let expected = |apply_type: &str, expr: &str| {
+            format!(
+                r#"
+                    (Projection
+                        (Apply({apply_type}) (Scan "t1") (Scan "t2") (Constant(Bool) true))
+                        (List (ColumnRef 0) (ColumnRef 1) (ColumnRef 2) {expr}))
+                "#
+            )
+        };
+        assert_rule(
+            Arc::new(ApplyProjectionPullUpRule::new(ApplyType::Cross)),
+            &input("Cross", "(Constant(Int) 1)"),
+            &[&expected("Cross", "(Constant(Int) 1)")],
+        );
+        assert_rule(
+            Arc::new(ApplyProjectionPullUpRule::new(ApplyType::LeftOuter)),
+            &input(
+                "LeftOuter",
+                "(BinOp(Add) (ColumnRef 0) (ExternColumnRef 1))",
+            ),
+            &[&expected(
+                "LeftOuter",
+                "(BinOp(Add) (ColumnRef 3) (ColumnRef 1))",
+            )],
+        );
+        // a constant is not null on the rows padded with nulls by a left outer apply
+        assert_rule(
+            Arc::new(ApplyProjectionPullUpRule::new(ApplyType::LeftOuter)),
+            &input("LeftOuter", "(Constant(Int) 1)"),
+            &[],
+        );
+    }
+
+    #[test]
+    fn apply_scalar_agg() {
+        let input = |agg: &str, pred: &str| {
+            format!(
+                r#"
+                    (Apply(Cross)
+                        (Scan "t1")
+                        (Agg (Filter (Scan "t2") {pred}) (List {agg}) (List))
+                        (Constant(Bool) true))
+                "#
+            )
+        };
+        let sum = "(Func(Agg(Sum)) (List (ColumnRef 2)))";
+        assert_rule(
+            Arc::new(ApplyScalarAggRule::new(ApplyType::Cross)),
+            &input(sum, "(BinOp(Eq) (ColumnRef 0) (ExternColumnRef 1))"),
+            &[r#"
+                (Projection
+                    (Join(LeftOuter)
+                        (Scan "t1")
+                        (Agg (Scan "t2") (List (Func(Agg(Sum)) (List (ColumnRef 2)))) (List (ColumnRef 0)))
+                        (BinOp(Eq) (ColumnRef 1) (ColumnRef 3)))
+                    (List (ColumnRef 0) (ColumnRef 1) (ColumnRef 2) (ColumnRef 4)))
+            "#],
+        );
+        // a count over no rows is 0, but the left outer join would pad it with a null
+        assert_rule(
+            Arc::new(ApplyScalarAggRule::new(ApplyType::Cross)),
+            &input(
+                "(Func(Agg(Count)) (List (Constant(Int) 1)))",
+                "(BinOp(Eq) (ColumnRef 0) (ExternColumnRef 1))",
+            ),
+            &[],
+        );
+        // only equalities with outer references become join keys
+        assert_rule(
+            Arc::new(ApplyScalarAggRule::new(ApplyType::Cross)),
+            &input(sum, "(BinOp(Lt) (ColumnRef 0) (ExternColumnRef 1))"),
+            &[],
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use optd_core::optimizer::Optimizer;
+use optd_core::rel_node::RelNode;
+use optd_core::rules::{Rule, RuleMatcher};
+
+use super::macros::define_rule;
+use crate::plan_nodes::{ColumnRefExpr, ExprList, LogicalAgg, OptRelNode, OptRelNodeTyp, PlanNode};
+use crate::properties::schema::SchemaPropertyBuilder;
+
+define_rule!(
+    /// Distinct A -> Agg A grouping by all columns of A without aggregations
+    DistinctToAggRule,
+    apply_distinct_to_agg,
+    (Distinct, child)
+);
+
+fn apply_distinct_to_agg(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    DistinctToAggRulePicks { child }: DistinctToAggRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let schema = optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(child.clone()), 0);
+    let groups = (0..schema.len())
+        .map(|x| ColumnRefExpr::new(x).into_expr())
+        .collect();
+    let node = LogicalAgg::new(
+        PlanNode::from_group(child.into()),
+        ExprList::new(vec![]),
+        ExprList::new(groups),
+    );
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::DistinctToAggRule;
+    use crate::testing::assert_rule;
+
+    #[test]
+    fn distinct_to_agg() {
+        assert_rule(
+            Arc::new(DistinctToAggRule::new()),
+            r#"(Distinct (Scan "t1"))"#,
+            &[r#"(Agg (Scan "t1") (List) (List (ColumnRef 0) (ColumnRef 1) (ColumnRef 2)))"#],
+        );
+        assert_rule(
+            Arc::new(DistinctToAggRule::new()),
+            r#"(Distinct (Projection (Scan "t1") (List (ColumnRef 2))))"#,
+            &[r#"(Agg (Projection (Scan "t1") (List (ColumnRef 2))) (List) (List (ColumnRef 0)))"#],
+        );
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+use std::sync::Arc;
+
+use datafusion_expr::AggregateFunction;
+use optd_core::optimizer::Optimizer;
+use optd_core::rel_node::RelNode;
+use optd_core::rules::{Rule, RuleMatcher};
+
+use super::filter_pushdown::{referenced_columns, rewrite_column_refs};
+use super::macros::define_rule;
+use crate::plan_nodes::{
+    ColumnRefExpr, Expr, ExprList, FuncExpr, FuncType, JoinType, LogicalAgg, LogicalJoin,
+    OptRelNode, OptRelNodeTyp, PlanNode,
+};
+use crate::properties::functional_dependency::FunctionalDependencyPropertyBuilder;
+use crate::properties::schema::SchemaPropertyBuilder;
+
+/// The aggregation that combines the partial results of an aggregation, if it is decomposable.
+fn final_agg(func: &AggregateFunction) -> Option<AggregateFunction> {
+    match func {
+        AggregateFunction::Sum | AggregateFunction::Min | AggregateFunction::Max => {
+            Some(func.clone())
+        }
+        AggregateFunction::Count => Some(AggregateFunction::Sum),
+        _ => None,
+    }
+}
+
+define_rule!(
+    /// Agg (A join B) -> Agg ((Agg A) join B), and the same for B. The partial aggregation groups A by the columns of A
+    /// used by the join condition and the group by columns, and the final aggregation combines the partial results.
+    EagerAggregationRule,
+    apply_eager_aggregation,
+    (
+        Agg,
+        (Join(JoinType::Inner), left, right, [cond]),
+        [exprs],
+        [groups]
+    )
+);
+
+fn apply_eager_aggregation(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    EagerAggregationRulePicks {
+        left,
+        right,
+        cond,
+        exprs,
+        groups,
+    }: EagerAggregationRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let left_size = optimizer
+        .get_property::<SchemaPropertyBuilder>(Arc::new(left.clone()), 0)
+        .len();
+    let right_size = optimizer
+        .get_property::<SchemaPropertyBuilder>(Arc::new(right.clone()), 0)
+        .len();
+    let cond = Expr::from_rel_node(cond.into()).unwrap();
+    let exprs = ExprList::from_rel_node(exprs.into()).unwrap();
+    let groups = ExprList::from_rel_node(groups.into()).unwrap();
+    let mut result = vec![];
+    for push_left in [true, false] {
+        let (side, columns) = if push_left {
+            (&left, 0..left_size)
+        } else {
+            (&right, left_size..left_size + right_size)
+        };
+        let Some((partial, required)) =
+            partial_agg(optimizer, side, columns.clone(), &cond, &exprs, &groups)
+        else {
+            continue;
+        };
+        // the partial aggregation outputs the required columns followed by the partial results
+        let required = required.into_iter().collect::<Vec<_>>();
+        let partial_size = required.len() + exprs.len();
+        let (offset, other_offset) = if push_left {
+            (0, partial_size)
+        } else {
+            (left_size, 0)
+        };
+        let rewrite = |expr: Expr| {
+            rewrite_column_refs(expr, &|x| {
+                let idx = if columns.contains(&x) {
+                    offset + required.binary_search(&x).ok()?
+                } else if push_left {
+                    other_offset + x - left_size
+                } else {
+                    x
+                };
+                Some(ColumnRefExpr::new(idx).into_expr())
+            })
+            .unwrap()
+        };
+        let (new_left, new_right) = if push_left {
+            (partial, PlanNode::from_group(right.clone().into()))
+        } else {
+            (PlanNode::from_group(left.clone().into()), partial)
+        };
+        let join = LogicalJoin::new(new_left, new_right, rewrite(cond.clone()), JoinType::Inner);
+        let final_exprs = exprs
+            .to_vec()
+            .into_iter()
+            .enumerate()
+            .map(|(idx, expr)| {
+                let FuncType::Agg(func) = FuncExpr::from_rel_node(expr.into_rel_node())
+                    .unwrap()
+                    .func()
+                else {
+                    unreachable!()
+                };
+                let partial_result = ColumnRefExpr::new(offset + required.len() + idx);
+                FuncExpr::new(
+                    FuncType::Agg(final_agg(&func).unwrap()),
+                    ExprList::new(vec![partial_result.into_expr()]),
+                )
+                .into_expr()
+            })
+            .collect();
+        let node = LogicalAgg::new(
+            join.into_plan_node(),
+            ExprList::new(final_exprs),
+            ExprList::new(groups.to_vec().into_iter().map(rewrite).collect()),
+        );
+        result.push(node.into_rel_node().as_ref().clone());
+    }
+    result
+}
+
+/// The partial aggregation of one side of the join with the output columns `columns`, and the columns of the side it
+/// groups by, if all aggregations are decomposable and only refer to this side.
+fn partial_agg(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    side: &RelNode<OptRelNodeTyp>,
+    columns: Range<usize>,
+    cond: &Expr,
+    exprs: &ExprList,
+    groups: &ExprList,
+) -> Option<(PlanNode, BTreeSet<usize>)> {
+    let local = |expr: Expr| {
+        rewrite_column_refs(expr, &|x| {
+            Some(ColumnRefExpr::new(x - columns.start).into_expr())
+        })
+        .unwrap()
+    };
+    let mut partial_exprs = vec![];
+    for expr in exprs.to_vec() {
+        let func = FuncExpr::from_rel_node(expr.clone().into_rel_node())?;
+        let FuncType::Agg(agg) = func.func() else {
+            return None;
+        };
+        final_agg(&agg)?;
+        if agg == AggregateFunction::Count && groups.is_empty() {
+            // the sum of the partial counts is null instead of zero if the join is empty
+            return None;
+        }
+        if !referenced_columns(&expr)
+            .iter()
+            .all(|x| columns.contains(x))
+        {
+            return None;
+        }
+        partial_exprs.push(local(expr));
+    }
+    let mut required = referenced_columns(cond);
+    for group in groups.to_vec() {
+        required.extend(referenced_columns(&group));
+    }
+    let required = required
+        .into_iter()
+        .filter(|x| columns.contains(x))
+        .map(|x| x - columns.start)
+        .collect::<BTreeSet<_>>();
+    // the partial aggregation does not reduce the number of rows if the grouping columns are already unique, and an
+    // aggregation without grouping columns outputs a row even if the side is empty
+    let deps = optimizer
+        .get_property::<FunctionalDependencyPropertyBuilder>(Arc::new(side.clone()), 2)
+        .dependencies();
+    if required.is_empty() || deps.is_unique(&required) {
+        return None;
+    }
+    let node = LogicalAgg::new(
+        PlanNode::from_group(side.clone().into()),
+        ExprList::new(partial_exprs),
+        ExprList::new(
+            required
+                .iter()
+                .map(|&x| ColumnRefExpr::new(x).into_expr())
+                .collect(),
+        ),
+    );
+    let required = required.into_iter().map(|x| x + columns.start).collect();
+    Some((node.into_plan_node(), required))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::EagerAggregationRule;
+    use crate::testing::assert_rule;
+
+    #[test]
+    fn eager_aggregation() {
+        // t1.b = t2.a, sum(t1.c) group by t2.b
+        assert_rule(
+            Arc::new(EagerAggregationRule::new()),
+            r#"
+                (Agg
+                    (Join(Inner) (Scan "t1") (Scan "t2") (BinOp(Eq) (ColumnRef 1) (ColumnRef 3)))
+                    (List (Func(Agg(Sum)) (List (ColumnRef 2))))
+                    (List (ColumnRef 4)))
+            "#,
+            &[r#"
+                (Agg
+                    (Join(Inner)
+                        (Agg (Scan "t1") (List (Func(Agg(Sum)) (List (ColumnRef 2)))) (List (ColumnRef 1)))
+                        (Scan "t2")
+                        (BinOp(Eq) (ColumnRef 0) (ColumnRef 2)))
+                    (List (Func(Agg(Sum)) (List (ColumnRef 1))))
+                    (List (ColumnRef 3)))
+            "#],
+        );
+        // t1.a = t2.b, sum(t2.c) group by t1.b
+        assert_rule(
+            Arc::new(EagerAggregationRule::new()),
+            r#"
+                (Agg
+                    (Join(Inner) (Scan "t1") (Scan "t2") (BinOp(Eq) (ColumnRef 0) (ColumnRef 4)))
+                    (List (Func(Agg(Sum)) (List (ColumnRef 5))))
+                    (List (ColumnRef 1)))
+            "#,
+            &[r#"
+                (Agg
+                    (Join(Inner)
+                        (Scan "t1")
+                        (Agg (Scan "t2") (List (Func(Agg(Sum)) (List (ColumnRef 2)))) (List (ColumnRef 1)))
+                        (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))
+                    (List (Func(Agg(Sum)) (List (ColumnRef 4))))
+                    (List (ColumnRef 1)))
+            "#],
+        );
+    }
+
+    #[test]
+    fn eager_count() {
+        // the partial counts are summed
+        assert_rule(
+            Arc::new(EagerAggregationRule::new()),
+            r#"
+                (Agg
+                    (Join(Inner) (Scan "t1") (Scan "t2") (BinOp(Eq) (ColumnRef 1) (ColumnRef 3)))
+                    (List (Func(Agg(Count)) (List (ColumnRef 2))))
+                    (List (ColumnRef 4)))
+            "#,
+            &[r#"
+                (Agg
+                    (Join(Inner)
+                        (Agg (Scan "t1") (List (Func(Agg(Count)) (List (ColumnRef 2)))) (List (ColumnRef 1)))
+                        (Scan "t2")
+                        (BinOp(Eq) (ColumnRef 0) (ColumnRef 2)))
+                    (List (Func(Agg(Sum)) (List (ColumnRef 1))))
+                    (List (ColumnRef 3)))
+            "#],
+        );
+        // the sum of no partial counts is null instead of zero
+        assert_rule(
+            Arc::new(EagerAggregationRule::new()),
+            r#"
+                (Agg
+                    (Join(Inner) (Scan "t1") (Scan "t2") (BinOp(Eq) (ColumnRef 1) (ColumnRef 3)))
+                    (List (Func(Agg(Count)) (List (ColumnRef 2))))
+                    (List))
+            "#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn eager_aggregation_on_unique_columns() {
+        // t1 is already grouped by its key t1.a
+        assert_rule(
+            Arc::new(EagerAggregationRule::new()),
+            r#"
+                (Agg
+                    (Join(Inner) (Scan "t1") (Scan "t2") (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))
+                    (List (Func(Agg(Sum)) (List (ColumnRef 2))))
+                    (List (ColumnRef 0)))
+            "#,
+            &[],
+        );
+    }
+}
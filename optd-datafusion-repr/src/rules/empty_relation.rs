@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use optd_core::optimizer::Optimizer;
+use optd_core::rel_node::{RelNode, Value};
+use optd_core::rules::{Rule, RuleMatcher};
+
+use super::filter_pushdown::split_conjunction;
+use super::macros::define_rule;
+use crate::plan_nodes::{
+    ConstantExpr, Expr, ExprList, JoinType, LogicalEmptyRelation, OptRelNode, OptRelNodeTyp,
+};
+use crate::properties::schema::SchemaPropertyBuilder;
+
+define_rule!(
+    /// Filter A on true -> A, Filter A on false -> empty
+    EliminateFilterRule,
+    apply_eliminate_filter,
+    (Filter, child, [cond])
+);
+
+fn apply_eliminate_filter(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    EliminateFilterRulePicks { child, cond }: EliminateFilterRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let conjuncts = split_conjunction(Expr::from_rel_node(cond.into()).unwrap());
+    if conjuncts.is_empty() {
+        return vec![child];
+    }
+    // a null condition filters out all rows as well
+    let is_false = conjuncts.iter().any(|x| {
+        ConstantExpr::from_rel_node(x.clone().into_rel_node()).map_or(false, |x| {
+            matches!(x.value(), Value::Bool(false) | Value::Null)
+        })
+    });
+    if !is_false {
+        return vec![];
+    }
+    let schema = optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(child), 0);
+    let node = LogicalEmptyRelation::new(&schema.fields);
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+/// Folds a plan node with an empty child into an empty relation, if the node cannot output any rows without the rows
+/// of that child. An aggregation is only folded if it has group by columns, as an aggregation without them outputs a
+/// single row for an empty input.
+pub struct EmptyRelationPropagationRule {
+    matcher: RuleMatcher<OptRelNodeTyp>,
+    name: &'static str,
+}
+
+const NODE: usize = 0;
+const GROUPS: usize = 1;
+
+impl EmptyRelationPropagationRule {
+    /// Matches a node of type `typ`, whose child at `empty_child` is an empty relation.
+    pub fn new(typ: OptRelNodeTyp, empty_child: usize, name: &'static str) -> Self {
+        let mut children = (0..empty_child)
+            .map(|_| RuleMatcher::IgnoreOne)
+            .collect::<Vec<_>>();
+        children.push(RuleMatcher::MatchNode {
+            typ: OptRelNodeTyp::EmptyRelation,
+            children: vec![RuleMatcher::IgnoreMany],
+        });
+        if typ == OptRelNodeTyp::Agg {
+            children.push(RuleMatcher::IgnoreOne);
+            children.push(RuleMatcher::PickOne {
+                pick_to: GROUPS,
+                expand: true,
+            });
+        } else {
+            children.push(RuleMatcher::IgnoreMany);
+        }
+        Self {
+            matcher: RuleMatcher::MatchAndPickNode {
+                typ,
+                pick_to: NODE,
+                children,
+            },
+            name,
+        }
+    }
+
+    pub fn all_rules<O: Optimizer<OptRelNodeTyp>>() -> Vec<Arc<dyn Rule<OptRelNodeTyp, O>>> {
+        let mut rules = vec![
+            Self::new(OptRelNodeTyp::Projection, 0, "empty_projection"),
+            Self::new(OptRelNodeTyp::Filter, 0, "empty_filter"),
+            Self::new(OptRelNodeTyp::Sort, 0, "empty_sort"),
+            Self::new(OptRelNodeTyp::Limit, 0, "empty_limit"),
+            Self::new(OptRelNodeTyp::Window, 0, "empty_window"),
+            Self::new(OptRelNodeTyp::Distinct, 0, "empty_distinct"),
+            Self::new(OptRelNodeTyp::Agg, 0, "empty_agg"),
+        ];
+        // the joins that output no rows if their left or right child is empty
+        for (join_type, left_name, right_name) in [
+            (
+                JoinType::Inner,
+                "empty_inner_join_left",
+                "empty_inner_join_right",
+            ),
+            (
+                JoinType::Cross,
+                "empty_cross_join_left",
+                "empty_cross_join_right",
+            ),
+            (
+                JoinType::LeftSemi,
+                "empty_left_semi_join_left",
+                "empty_left_semi_join_right",
+            ),
+            (
+                JoinType::RightSemi,
+                "empty_right_semi_join_left",
+                "empty_right_semi_join_right",
+            ),
+        ] {
+            rules.push(Self::new(OptRelNodeTyp::Join(join_type), 0, left_name));
+            rules.push(Self::new(OptRelNodeTyp::Join(join_type), 1, right_name));
+        }
+        for (join_type, name) in [
+            (JoinType::LeftOuter, "empty_left_outer_join_left"),
+            (JoinType::LeftAnti, "empty_left_anti_join_left"),
+        ] {
+            rules.push(Self::new(OptRelNodeTyp::Join(join_type), 0, name));
+        }
+        for (join_type, name) in [
+            (JoinType::RightOuter, "empty_right_outer_join_right"),
+            (JoinType::RightAnti, "empty_right_anti_join_right"),
+        ] {
+            rules.push(Self::new(OptRelNodeTyp::Join(join_type), 1, name));
+        }
+        rules
+            .into_iter()
+            .map(|x| Arc::new(x) as Arc<dyn Rule<OptRelNodeTyp, O>>)
+            .collect()
+    }
+}
+
+impl<O: Optimizer<OptRelNodeTyp>> Rule<OptRelNodeTyp, O> for EmptyRelationPropagationRule {
+    fn matcher(&self) -> &RuleMatcher<OptRelNodeTyp> {
+        &self.matcher
+    }
+
+    fn apply(
+        &self,
+        optimizer: &O,
+        mut input: HashMap<usize, RelNode<OptRelNodeTyp>>,
+    ) -> Vec<RelNode<OptRelNodeTyp>> {
+        if let Some(groups) = input.remove(&GROUPS) {
+            if ExprList::from_rel_node(groups.into()).unwrap().is_empty() {
+                return vec![];
+            }
+        }
+        let node = input.remove(&NODE).unwrap();
+        let schema = optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(node), 0);
+        let node = LogicalEmptyRelation::new(&schema.fields);
+        vec![node.into_rel_node().as_ref().clone()]
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        "propagate empty relations through the operators above them"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{EliminateFilterRule, EmptyRelationPropagationRule};
+    use crate::{
+        plan_nodes::{JoinType, OptRelNodeTyp},
+        testing::assert_rule,
+    };
+
+    const EMPTY: &str =
+        r#"(EmptyRelation [["a", "Int", false], ["b", "Int", true], ["c", "Int", true]])"#;
+
+    #[test]
+    fn eliminate_filter() {
+        let filter = |cond: &str| format!(r#"(Filter (Scan "t1") {cond})"#);
+        assert_rule(
+            Arc::new(EliminateFilterRule::new()),
+            &filter("(Constant(Bool) true)"),
+            &[r#"(Scan "t1")"#],
+        );
+        assert_rule(
+            Arc::new(EliminateFilterRule::new()),
+            &filter(
+                "(LogOp(And) (List (BinOp(Gt) (ColumnRef 1) (Constant(Int) 1)) (Constant(Bool) false)))",
+            ),
+            &[EMPTY],
+        );
+        // a null condition does not keep any row either
+        assert_rule(
+            Arc::new(EliminateFilterRule::new()),
+            &filter("(Constant(Bool) null)"),
+            &[EMPTY],
+        );
+        assert_rule(
+            Arc::new(EliminateFilterRule::new()),
+            &filter("(BinOp(Gt) (ColumnRef 1) (Constant(Int) 1))"),
+            &[],
+        );
+    }
+
+    #[test]
+    fn empty_relation_propagation() {
+        assert_rule(
+            Arc::new(EmptyRelationPropagationRule::new(
+                OptRelNodeTyp::Filter,
+                0,
+                "empty_filter",
+            )),
+            &format!("(Filter {EMPTY} (BinOp(Gt) (ColumnRef 1) (Constant(Int) 1)))"),
+            &[EMPTY],
+        );
+        let inner_join_right = || {
+            Arc::new(EmptyRelationPropagationRule::new(
+                OptRelNodeTyp::Join(JoinType::Inner),
+                1,
+                "empty_inner_join_right",
+            ))
+        };
+        assert_rule(
+            inner_join_right(),
+            &format!(
+                r#"(Join(Inner) (Scan "t1") {EMPTY} (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))"#
+            ),
+            &[r#"
+                (EmptyRelation [
+                    ["a", "Int", false], ["b", "Int", true], ["c", "Int", true],
+                    ["a", "Int", false], ["b", "Int", true], ["c", "Int", true]
+                ])
+            "#],
+        );
+        // only the left child is empty
+        assert_rule(
+            inner_join_right(),
+            &format!(
+                r#"(Join(Inner) {EMPTY} (Scan "t1") (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))"#
+            ),
+            &[],
+        );
+    }
+
+    #[test]
+    fn empty_agg() {
+        let rule = || {
+            Arc::new(EmptyRelationPropagationRule::new(
+                OptRelNodeTyp::Agg,
+                0,
+                "empty_agg",
+            ))
+        };
+        assert_rule(
+            rule(),
+            &format!("(Agg {EMPTY} (List) (List (ColumnRef 1)))"),
+            &[r#"(EmptyRelation [["b", "Int", true]])"#],
+        );
+        // an aggregation without group by columns outputs a row for an empty input
+        assert_rule(
+            rule(),
+            &format!("(Agg {EMPTY} (List (Func(Agg(Sum)) (List (ColumnRef 2)))) (List))"),
+            &[],
+        );
+    }
+}
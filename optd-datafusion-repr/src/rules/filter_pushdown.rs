@@ -0,0 +1,537 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use optd_core::optimizer::Optimizer;
+use optd_core::rel_node::RelNode;
+use optd_core::rules::{Rule, RuleMatcher};
+
+use datafusion_expr::Volatility;
+
+use super::decorrelation::has_outer_refs;
+use super::macros::define_rule;
+use crate::plan_nodes::{
+    BinOpType, ColumnRefExpr, ConstantExpr, Expr, ExprList, FuncType, JoinType, LogOpExpr,
+    LogOpType, LogicalAgg, LogicalFilter, LogicalJoin, LogicalProjection, LogicalUnion, OptRelNode,
+    OptRelNodeRef, OptRelNodeTyp, PlanNode, UnionType,
+};
+use crate::properties::schema::SchemaPropertyBuilder;
+
+/// Split a predicate into its conjuncts. A `true` constant has no conjuncts.
+pub(crate) fn split_conjunction(cond: Expr) -> Vec<Expr> {
+    fn split(cond: OptRelNodeRef, conjuncts: &mut Vec<Expr>) {
+        match cond.typ {
+            OptRelNodeTyp::LogOp(LogOpType::And) => {
+                for child in &cond.child(0).children {
+                    split(child.clone(), conjuncts);
+                }
+            }
+            OptRelNodeTyp::BinOp(BinOpType::And) => {
+                split(cond.child(0), conjuncts);
+                split(cond.child(1), conjuncts);
+            }
+            _ => {
+                if let Some(constant) = ConstantExpr::from_rel_node(cond.clone()) {
+                    if constant.value().as_bool() == Some(true) {
+                        return;
+                    }
+                }
+                conjuncts.push(Expr::from_rel_node(cond).unwrap());
+            }
+        }
+    }
+    let mut conjuncts = vec![];
+    split(cond.into_rel_node(), &mut conjuncts);
+    conjuncts
+}
+
+/// Combine the conjuncts into a predicate. No conjuncts make a `true` constant.
+pub(crate) fn merge_conjunction(mut conjuncts: Vec<Expr>) -> Expr {
+    match conjuncts.len() {
+        0 => ConstantExpr::bool(true).into_expr(),
+        1 => conjuncts.remove(0),
+        _ => LogOpExpr::new(LogOpType::And, ExprList::new(conjuncts)).into_expr(),
+    }
+}
+
+/// The columns referenced by an expression.
+pub(crate) fn referenced_columns(expr: &Expr) -> BTreeSet<usize> {
+    fn collect(expr: &OptRelNodeRef, columns: &mut BTreeSet<usize>) {
+        if let Some(column) = ColumnRefExpr::from_rel_node(expr.clone()) {
+            columns.insert(column.index());
+            return;
+        }
+        for child in &expr.children {
+            collect(child, columns);
+        }
+    }
+    let mut columns = BTreeSet::new();
+    collect(&expr.clone().into_rel_node(), &mut columns);
+    columns
+}
+
+/// Whether the expression has the same result every time it is evaluated on the same row, i.e., it does not call
+/// volatile functions such as `random()`.
+pub(crate) fn is_deterministic(expr: &OptRelNodeRef) -> bool {
+    if let OptRelNodeTyp::Func(FuncType::Scalar(func)) = &expr.typ {
+        if func.volatility() == Volatility::Volatile {
+            return false;
+        }
+    }
+    expr.children.iter().all(is_deterministic)
+}
+
+/// Replace each column reference with the expression returned by `rewrite`, or return `None` if any of the columns
+/// cannot be rewritten.
+pub(crate) fn rewrite_column_refs(
+    expr: Expr,
+    rewrite: &impl Fn(usize) -> Option<Expr>,
+) -> Option<Expr> {
+    fn rewrite_node(
+        expr: OptRelNodeRef,
+        rewrite: &impl Fn(usize) -> Option<Expr>,
+    ) -> Option<OptRelNodeRef> {
+        if let Some(column) = ColumnRefExpr::from_rel_node(expr.clone()) {
+            return rewrite(column.index()).map(|x| x.into_rel_node());
+        }
+        let children = expr
+            .children
+            .iter()
+            .map(|x| rewrite_node(x.clone(), rewrite))
+            .collect::<Option<Vec<_>>>()?;
+        Some(
+            RelNode {
+                typ: expr.typ.clone(),
+                children,
+                data: expr.data.clone(),
+            }
+            .into(),
+        )
+    }
+    Expr::from_rel_node(rewrite_node(expr.into_rel_node(), rewrite)?)
+}
+
+/// Filter the node if there are any conjuncts.
+pub(crate) fn filter_if_needed(child: PlanNode, conjuncts: Vec<Expr>) -> PlanNode {
+    if conjuncts.is_empty() {
+        child
+    } else {
+        LogicalFilter::new(child, merge_conjunction(conjuncts)).into_plan_node()
+    }
+}
+
+/// The conjuncts of an inner join predicate, split by the side of the join they can be evaluated on. Conjuncts with
+/// outer references are kept in a filter above the join, where the decorrelation rules can pull them into the
+/// enclosing apply.
+struct JoinConjuncts {
+    left: Vec<Expr>,
+    right: Vec<Expr>,
+    join: Vec<Expr>,
+    correlated: Vec<Expr>,
+}
+
+impl JoinConjuncts {
+    fn split(conjuncts: Vec<Expr>, left_schema_size: usize) -> Self {
+        let mut result = Self {
+            left: vec![],
+            right: vec![],
+            join: vec![],
+            correlated: vec![],
+        };
+        for conjunct in conjuncts {
+            let columns = referenced_columns(&conjunct);
+            if has_outer_refs(&conjunct.clone().into_rel_node()) {
+                result.correlated.push(conjunct);
+            } else if columns.is_empty() {
+                result.join.push(conjunct);
+            } else if columns.iter().all(|&x| x < left_schema_size) {
+                result.left.push(conjunct);
+            } else if columns.iter().all(|&x| x >= left_schema_size) {
+                let conjunct = rewrite_column_refs(conjunct, &|x| {
+                    Some(ColumnRefExpr::new(x - left_schema_size).into_expr())
+                })
+                .unwrap();
+                result.right.push(conjunct);
+            } else {
+                result.join.push(conjunct);
+            }
+        }
+        result
+    }
+
+    fn into_join(
+        self,
+        left: RelNode<OptRelNodeTyp>,
+        right: RelNode<OptRelNodeTyp>,
+    ) -> RelNode<OptRelNodeTyp> {
+        let node = LogicalJoin::new(
+            filter_if_needed(PlanNode::from_group(left.into()), self.left),
+            filter_if_needed(PlanNode::from_group(right.into()), self.right),
+            merge_conjunction(self.join),
+            JoinType::Inner,
+        );
+        filter_if_needed(node.into_plan_node(), self.correlated)
+            .into_rel_node()
+            .as_ref()
+            .clone()
+    }
+}
+
+define_rule!(
+    /// Filter (Filter A) -> Filter A
+    FilterMergeRule,
+    apply_filter_merge,
+    (Filter, (Filter, child, [cond1]), [cond2])
+);
+
+fn apply_filter_merge(
+    _optimizer: &impl Optimizer<OptRelNodeTyp>,
+    FilterMergeRulePicks {
+        child,
+        cond1,
+        cond2,
+    }: FilterMergeRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let mut conjuncts = split_conjunction(Expr::from_rel_node(cond1.into()).unwrap());
+    conjuncts.extend(split_conjunction(
+        Expr::from_rel_node(cond2.into()).unwrap(),
+    ));
+    let node = LogicalFilter::new(
+        PlanNode::from_group(child.into()),
+        merge_conjunction(conjuncts),
+    );
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+define_rule!(
+    /// Filter (A join B) -> (Filter A) join (Filter B)
+    FilterJoinPushdownRule,
+    apply_filter_join_pushdown,
+    (
+        Filter,
+        (Join(JoinType::Inner), left, right, [join_cond]),
+        [cond]
+    )
+);
+
+fn apply_filter_join_pushdown(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    FilterJoinPushdownRulePicks {
+        left,
+        right,
+        join_cond,
+        cond,
+    }: FilterJoinPushdownRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let left_schema = optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(left.clone()), 0);
+    let mut conjuncts = split_conjunction(Expr::from_rel_node(join_cond.into()).unwrap());
+    conjuncts.extend(split_conjunction(Expr::from_rel_node(cond.into()).unwrap()));
+    let conjuncts = JoinConjuncts::split(conjuncts, left_schema.len());
+    vec![conjuncts.into_join(left, right)]
+}
+
+define_rule!(
+    /// A join B on (A.x = 1 and A.y = B.y) -> (Filter A.x = 1) join B on A.y = B.y
+    JoinCondPushdownRule,
+    apply_join_cond_pushdown,
+    (Join(JoinType::Inner), left, right, [cond])
+);
+
+fn apply_join_cond_pushdown(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    JoinCondPushdownRulePicks { left, right, cond }: JoinCondPushdownRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let left_schema = optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(left.clone()), 0);
+    let conjuncts = split_conjunction(Expr::from_rel_node(cond.into()).unwrap());
+    let conjuncts = JoinConjuncts::split(conjuncts, left_schema.len());
+    if conjuncts.left.is_empty() && conjuncts.right.is_empty() && conjuncts.correlated.is_empty() {
+        return vec![];
+    }
+    vec![conjuncts.into_join(left, right)]
+}
+
+define_rule!(
+    /// Filter (Proj A) -> Proj (Filter A)
+    FilterProjectionTransposeRule,
+    apply_filter_projection_transpose,
+    (Filter, (Projection, child, [exprs]), [cond])
+);
+
+fn apply_filter_projection_transpose(
+    _optimizer: &impl Optimizer<OptRelNodeTyp>,
+    FilterProjectionTransposeRulePicks { child, exprs, cond }: FilterProjectionTransposeRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let exprs = ExprList::from_rel_node(exprs.into()).unwrap();
+    let cond = Expr::from_rel_node(cond.into()).unwrap();
+    // the projection is evaluated on each row, so the filter can refer to the projected expressions directly, unless
+    // they are volatile and would be evaluated again with different results
+    let Some(cond) = rewrite_column_refs(cond, &|x| {
+        let expr = exprs.child(x);
+        is_deterministic(&expr.clone().into_rel_node()).then_some(expr)
+    }) else {
+        return vec![];
+    };
+    let node = LogicalProjection::new(
+        LogicalFilter::new(PlanNode::from_group(child.into()), cond).into_plan_node(),
+        exprs,
+    );
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+define_rule!(
+    /// Filter (Agg A) -> Agg (Filter A), for the conjuncts only on the group by columns
+    FilterAggTransposeRule,
+    apply_filter_agg_transpose,
+    (Filter, (Agg, child, [exprs], [groups]), [cond])
+);
+
+fn apply_filter_agg_transpose(
+    _optimizer: &impl Optimizer<OptRelNodeTyp>,
+    FilterAggTransposeRulePicks {
+        child,
+        exprs,
+        groups,
+        cond,
+    }: FilterAggTransposeRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let exprs = ExprList::from_rel_node(exprs.into()).unwrap();
+    let groups = ExprList::from_rel_node(groups.into()).unwrap();
+    let mut pushed = vec![];
+    let mut kept = vec![];
+    for conjunct in split_conjunction(Expr::from_rel_node(cond.into()).unwrap()) {
+        // the output columns are the group by columns followed by the aggregations
+        let pushed_conjunct = rewrite_column_refs(conjunct.clone(), &|x| {
+            (x < groups.len()).then(|| groups.child(x))
+        });
+        match pushed_conjunct {
+            Some(conjunct) if !referenced_columns(&conjunct).is_empty() => pushed.push(conjunct),
+            _ => kept.push(conjunct),
+        }
+    }
+    if pushed.is_empty() {
+        return vec![];
+    }
+    let node = LogicalAgg::new(
+        LogicalFilter::new(
+            PlanNode::from_group(child.into()),
+            merge_conjunction(pushed),
+        )
+        .into_plan_node(),
+        exprs,
+        groups,
+    );
+    let node = filter_if_needed(node.into_plan_node(), kept);
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+/// Pushes a filter into both children of a union, i.e., `Filter (A union B)` becomes `(Filter A) union (Filter B)`.
+/// Both children have the same columns as the union, so the condition is not rewritten.
+pub struct FilterUnionTransposeRule {
+    matcher: RuleMatcher<OptRelNodeTyp>,
+    union_type: UnionType,
+    name: &'static str,
+}
+
+const LEFT: usize = 0;
+const RIGHT: usize = 1;
+const COND: usize = 2;
+
+impl FilterUnionTransposeRule {
+    pub fn new(union_type: UnionType) -> Self {
+        Self {
+            matcher: RuleMatcher::MatchNode {
+                typ: OptRelNodeTyp::Filter,
+                children: vec![
+                    RuleMatcher::MatchNode {
+                        typ: OptRelNodeTyp::Union(union_type),
+                        children: vec![
+                            RuleMatcher::PickOne {
+                                pick_to: LEFT,
+                                expand: false,
+                            },
+                            RuleMatcher::PickOne {
+                                pick_to: RIGHT,
+                                expand: false,
+                            },
+                        ],
+                    },
+                    RuleMatcher::PickOne {
+                        pick_to: COND,
+                        expand: true,
+                    },
+                ],
+            },
+            union_type,
+            name: match union_type {
+                UnionType::All => "filter_union_all_transpose",
+                UnionType::Distinct => "filter_union_distinct_transpose",
+            },
+        }
+    }
+
+    pub fn all_union_types<O: Optimizer<OptRelNodeTyp>>() -> Vec<Arc<dyn Rule<OptRelNodeTyp, O>>> {
+        UnionType::ALL
+            .into_iter()
+            .map(|x| Arc::new(FilterUnionTransposeRule::new(x)) as Arc<dyn Rule<OptRelNodeTyp, O>>)
+            .collect()
+    }
+}
+
+impl<O: Optimizer<OptRelNodeTyp>> Rule<OptRelNodeTyp, O> for FilterUnionTransposeRule {
+    fn matcher(&self) -> &RuleMatcher<OptRelNodeTyp> {
+        &self.matcher
+    }
+
+    fn apply(
+        &self,
+        _optimizer: &O,
+        mut input: HashMap<usize, RelNode<OptRelNodeTyp>>,
+    ) -> Vec<RelNode<OptRelNodeTyp>> {
+        let left = input.remove(&LEFT).unwrap();
+        let right = input.remove(&RIGHT).unwrap();
+        let cond = Expr::from_rel_node(input.remove(&COND).unwrap().into()).unwrap();
+        let node = LogicalUnion::new(
+            LogicalFilter::new(PlanNode::from_group(left.into()), cond.clone()).into_plan_node(),
+            LogicalFilter::new(PlanNode::from_group(right.into()), cond).into_plan_node(),
+            self.union_type,
+        );
+        vec![node.into_rel_node().as_ref().clone()]
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        "push a filter into both children of a union"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use optd_core::rel_node::RelNode;
+
+    use super::{
+        merge_conjunction, rewrite_column_refs, split_conjunction, FilterProjectionTransposeRule,
+        JoinConjuncts,
+    };
+    use crate::{
+        plan_nodes::{ColumnRefExpr, Expr, OptRelNode, OptRelNodeTyp},
+        testing::assert_rule,
+    };
+
+    fn expr(expr: &str) -> Expr {
+        Expr::from_rel_node(Arc::new(expr.parse::<RelNode<OptRelNodeTyp>>().unwrap())).unwrap()
+    }
+
+    fn display(exprs: &[Expr]) -> Vec<String> {
+        exprs
+            .iter()
+            .map(|x| x.clone().into_rel_node().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn split_and_merge_conjunction() {
+        let cond = expr(
+            "(LogOp(And) (List (BinOp(Eq) (ColumnRef 0) (ColumnRef 1)) \
+             (BinOp(And) (Constant(Bool) true) (BinOp(Gt) (ColumnRef 2) (Constant(Int) 1)))))",
+        );
+        let conjuncts = split_conjunction(cond);
+        assert_eq!(
+            display(&conjuncts),
+            [
+                "(BinOp(Eq) (ColumnRef 0) (ColumnRef 1))",
+                "(BinOp(Gt) (ColumnRef 2) (Constant(Int) 1))",
+            ]
+        );
+        assert_eq!(
+            display(&[merge_conjunction(conjuncts.clone())]),
+            ["(LogOp(And) (List (BinOp(Eq) (ColumnRef 0) (ColumnRef 1)) (BinOp(Gt) (ColumnRef 2) (Constant(Int) 1))))"]
+        );
+        assert_eq!(
+            display(&[merge_conjunction(conjuncts[..1].to_vec())]),
+            ["(BinOp(Eq) (ColumnRef 0) (ColumnRef 1))"]
+        );
+        assert_eq!(
+            display(&[merge_conjunction(vec![])]),
+            ["(Constant(Bool) true)"]
+        );
+        assert!(split_conjunction(expr("(Constant(Bool) true)")).is_empty());
+    }
+
+    #[test]
+    fn rewrite() {
+        let cond = expr("(BinOp(Eq) (ColumnRef 0) (ColumnRef 2))");
+        let shifted = rewrite_column_refs(cond.clone(), &|x| {
+            Some(ColumnRefExpr::new(x + 1).into_expr())
+        });
+        assert_eq!(
+            display(&[shifted.unwrap()]),
+            ["(BinOp(Eq) (ColumnRef 1) (ColumnRef 3))"]
+        );
+        let partial = rewrite_column_refs(cond, &|x| {
+            (x < 1).then(|| ColumnRefExpr::new(x).into_expr())
+        });
+        assert!(partial.is_none());
+    }
+
+    #[test]
+    fn split_join_conjuncts() {
+        let conjuncts = vec![
+            expr("(BinOp(Eq) (ColumnRef 0) (Constant(Int) 1))"),
+            expr("(BinOp(Eq) (ColumnRef 4) (Constant(Int) 1))"),
+            expr("(BinOp(Eq) (ColumnRef 0) (ColumnRef 3))"),
+            expr("(BinOp(Eq) (ColumnRef 4) (ExternColumnRef 0))"),
+            expr("(BinOp(Eq) (ExternColumnRef 1) (Constant(Int) 1))"),
+        ];
+        let conjuncts = JoinConjuncts::split(conjuncts, 3);
+        assert_eq!(
+            display(&conjuncts.left),
+            ["(BinOp(Eq) (ColumnRef 0) (Constant(Int) 1))"]
+        );
+        assert_eq!(
+            display(&conjuncts.right),
+            ["(BinOp(Eq) (ColumnRef 1) (Constant(Int) 1))"]
+        );
+        assert_eq!(
+            display(&conjuncts.join),
+            ["(BinOp(Eq) (ColumnRef 0) (ColumnRef 3))"]
+        );
+        assert_eq!(
+            display(&conjuncts.correlated),
+            [
+                "(BinOp(Eq) (ColumnRef 4) (ExternColumnRef 0))",
+                "(BinOp(Eq) (ExternColumnRef 1) (Constant(Int) 1))",
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_projection_transpose() {
+        assert_rule(
+            Arc::new(FilterProjectionTransposeRule::new()),
+            r#"
+                (Filter
+                    (Projection (Scan "t1") (List (ColumnRef 2) (BinOp(Add) (ColumnRef 0) (ColumnRef 1))))
+                    (BinOp(Gt) (ColumnRef 1) (Constant(Int) 1)))
+            "#,
+            &[r#"
+                (Projection
+                    (Filter (Scan "t1") (BinOp(Gt) (BinOp(Add) (ColumnRef 0) (ColumnRef 1)) (Constant(Int) 1)))
+                    (List (ColumnRef 2) (BinOp(Add) (ColumnRef 0) (ColumnRef 1))))
+            "#],
+        );
+        // a volatile expression would be evaluated twice with different results
+        assert_rule(
+            Arc::new(FilterProjectionTransposeRule::new()),
+            r#"
+                (Filter
+                    (Projection (Scan "t1") (List (ColumnRef 0) (Func(Scalar(Random)) (List))))
+                    (BinOp(Gt) (ColumnRef 1) (Constant(Float) 0.5)))
+            "#,
+            &[],
+        );
+    }
+}
@@ -6,15 +6,19 @@ use optd_core::optimizer::Optimizer;
 use optd_core::rel_node::RelNode;
 use optd_core::rules::{Rule, RuleMatcher};
 
-use super::macros::{define_impl_rule, define_rule};
+use super::filter_pushdown::{
+    merge_conjunction, referenced_columns, rewrite_column_refs, split_conjunction,
+};
+use super::macros::define_rule;
 use crate::plan_nodes::{
-    BinOpExpr, BinOpType, ColumnRefExpr, Expr, ExprList, JoinType, LogicalJoin, LogicalProjection,
-    OptRelNode, OptRelNodeTyp, PhysicalHashJoin, PlanNode,
+    BinOpExpr, BinOpType, ColumnRefExpr, ConstantExpr, Expr, ExprList, JoinType, LogicalJoin,
+    LogicalProjection, OptRelNode, OptRelNodeTyp, PhysicalHashJoin, PhysicalSortMergeJoin,
+    PlanNode,
 };
 use crate::properties::schema::SchemaPropertyBuilder;
 
-// A join B -> B join A
 define_rule!(
+    /// A join B -> B join A
     JoinCommuteRule,
     apply_join_commute,
     (Join(JoinType::Inner), left, right, [cond])
@@ -66,7 +70,7 @@ fn apply_join_commute(
         cond,
         JoinType::Inner,
     );
-    let mut proj_expr = Vec::with_capacity(left_schema.0.len() + right_schema.0.len());
+    let mut proj_expr = Vec::with_capacity(left_schema.len() + right_schema.len());
     for i in 0..left_schema.len() {
         proj_expr.push(ColumnRefExpr::new(right_schema.len() + i).into_expr());
     }
@@ -78,8 +82,8 @@ fn apply_join_commute(
     vec![node.as_ref().clone()]
 }
 
-// (A join B) join C -> A join (B join C)
 define_rule!(
+    /// (A join B) join C -> A join (B join C)
     JoinAssocRule,
     apply_join_assoc,
     (
@@ -154,60 +158,277 @@ fn apply_join_assoc(
     vec![node]
 }
 
-define_impl_rule!(
-    HashJoinRule,
-    apply_hash_join,
-    (Join(JoinType::Inner), left, right, [cond])
-);
+/// Implements a join of the given type with a hash join, if the join condition has any equality between the two
+/// sides. Cross joins do not have such conditions.
+pub struct HashJoinRule {
+    matcher: RuleMatcher<OptRelNodeTyp>,
+    join_type: JoinType,
+    name: &'static str,
+}
+
+const LEFT: usize = 0;
+const RIGHT: usize = 1;
+const COND: usize = 2;
+
+impl HashJoinRule {
+    pub fn new(join_type: JoinType) -> Self {
+        Self {
+            matcher: RuleMatcher::MatchNode {
+                typ: OptRelNodeTyp::Join(join_type),
+                children: vec![
+                    RuleMatcher::PickOne {
+                        pick_to: LEFT,
+                        expand: false,
+                    },
+                    RuleMatcher::PickOne {
+                        pick_to: RIGHT,
+                        expand: false,
+                    },
+                    RuleMatcher::PickOne {
+                        pick_to: COND,
+                        expand: true,
+                    },
+                ],
+            },
+            join_type,
+            name: match join_type {
+                JoinType::Inner => "hash_join_inner",
+                JoinType::FullOuter => "hash_join_full_outer",
+                JoinType::LeftOuter => "hash_join_left_outer",
+                JoinType::RightOuter => "hash_join_right_outer",
+                JoinType::Cross => "hash_join_cross",
+                JoinType::LeftSemi => "hash_join_left_semi",
+                JoinType::RightSemi => "hash_join_right_semi",
+                JoinType::LeftAnti => "hash_join_left_anti",
+                JoinType::RightAnti => "hash_join_right_anti",
+            },
+        }
+    }
+
+    pub fn all_join_types<O: Optimizer<OptRelNodeTyp>>() -> Vec<Arc<dyn Rule<OptRelNodeTyp, O>>> {
+        JoinType::ALL
+            .into_iter()
+            .filter(|x| *x != JoinType::Cross)
+            .map(|x| Arc::new(HashJoinRule::new(x)) as Arc<dyn Rule<OptRelNodeTyp, O>>)
+            .collect()
+    }
+}
+
+impl<O: Optimizer<OptRelNodeTyp>> Rule<OptRelNodeTyp, O> for HashJoinRule {
+    fn matcher(&self) -> &RuleMatcher<OptRelNodeTyp> {
+        &self.matcher
+    }
+
+    fn apply(
+        &self,
+        optimizer: &O,
+        mut input: HashMap<usize, RelNode<OptRelNodeTyp>>,
+    ) -> Vec<RelNode<OptRelNodeTyp>> {
+        let left = input.remove(&LEFT).unwrap();
+        let right = input.remove(&RIGHT).unwrap();
+        let cond = input.remove(&COND).unwrap();
+        apply_hash_join(optimizer, left, right, cond, self.join_type)
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        "implement a join with an equality condition as a hash join"
+    }
+
+    fn is_impl_rule(&self) -> bool {
+        true
+    }
+}
 
 fn apply_hash_join(
     optimizer: &impl Optimizer<OptRelNodeTyp>,
-    HashJoinRulePicks { left, right, cond }: HashJoinRulePicks,
+    left: RelNode<OptRelNodeTyp>,
+    right: RelNode<OptRelNodeTyp>,
+    cond: RelNode<OptRelNodeTyp>,
+    join_type: JoinType,
 ) -> Vec<RelNode<OptRelNodeTyp>> {
-    if let OptRelNodeTyp::BinOp(BinOpType::Eq) = cond.typ {
-        let left_schema =
-            optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(left.clone()), 0);
-        // let right_schema =
-        //     optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(right.clone()), 0);
-        let op = BinOpExpr::from_rel_node(Arc::new(cond.clone())).unwrap();
-        let left_expr: Expr = op.left_child();
-        let right_expr = op.right_child();
-        let Some(mut left_expr) = ColumnRefExpr::from_rel_node(left_expr.into_rel_node()) else {
-            return vec![];
+    // the keys compared with `IS NOT DISTINCT FROM`, such as those of intersect and except, are only used if there is
+    // no equality, in which case they are left in the residual condition
+    let (keys, null_equals_null) =
+        match split_join_keys(optimizer, &left, cond.clone(), BinOpType::Eq) {
+            Some(keys) => (keys, false),
+            None => match split_join_keys(optimizer, &left, cond, BinOpType::IsNotDistinctFrom) {
+                Some(keys) => (keys, true),
+                None => return vec![],
+            },
         };
-        let Some(mut right_expr) = ColumnRefExpr::from_rel_node(right_expr.into_rel_node()) else {
-            return vec![];
-        };
-        let can_convert = if left_expr.index() < left_schema.0.len()
-            && right_expr.index() >= left_schema.0.len()
-        {
-            true
-        } else if right_expr.index() < left_schema.0.len()
-            && left_expr.index() >= left_schema.0.len()
-        {
-            (left_expr, right_expr) = (right_expr, left_expr);
-            true
+    let (left_keys, right_keys, residual) = keys;
+    let node = PhysicalHashJoin::new(
+        PlanNode::from_group(left.into()),
+        PlanNode::from_group(right.into()),
+        ExprList::new(left_keys),
+        ExprList::new(right_keys),
+        merge_conjunction(residual),
+        ConstantExpr::bool(null_equals_null),
+        join_type,
+    );
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+/// Splits a join condition into the keys of the left side, the keys of the right side, and the residual conjuncts,
+/// or returns `None` if there is no comparison with `key_op` between the two sides. The right keys refer to the
+/// columns of the right side.
+fn split_join_keys(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    left: &RelNode<OptRelNodeTyp>,
+    cond: RelNode<OptRelNodeTyp>,
+    key_op: BinOpType,
+) -> Option<(Vec<Expr>, Vec<Expr>, Vec<Expr>)> {
+    let left_schema = optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(left.clone()), 0);
+    let left_size = left_schema.len();
+    // the side of the join an expression can be evaluated on
+    let side = |expr: &Expr| {
+        let columns = referenced_columns(expr);
+        if columns.is_empty() {
+            None
+        } else if columns.iter().all(|&x| x < left_size) {
+            Some(true)
+        } else if columns.iter().all(|&x| x >= left_size) {
+            Some(false)
         } else {
-            false
+            None
+        }
+    };
+    let mut left_keys = vec![];
+    let mut right_keys = vec![];
+    let mut residual = vec![];
+    for conjunct in split_conjunction(Expr::from_rel_node(cond.into()).unwrap()) {
+        if let Some(op) = BinOpExpr::from_rel_node(conjunct.clone().into_rel_node()) {
+            if op.op_type() == key_op {
+                let (left_expr, right_expr) = (op.left_child(), op.right_child());
+                let keys = match (side(&left_expr), side(&right_expr)) {
+                    (Some(true), Some(false)) => Some((left_expr, right_expr)),
+                    (Some(false), Some(true)) => Some((right_expr, left_expr)),
+                    _ => None,
+                };
+                if let Some((left_key, right_key)) = keys {
+                    let right_key = rewrite_column_refs(right_key, &|x| {
+                        Some(ColumnRefExpr::new(x - left_size).into_expr())
+                    })
+                    .unwrap();
+                    left_keys.push(left_key);
+                    right_keys.push(right_key);
+                    continue;
+                }
+            }
+        }
+        residual.push(conjunct);
+    }
+    if left_keys.is_empty() {
+        return None;
+    }
+    Some((left_keys, right_keys, residual))
+}
+
+/// Implements a join of the given type with a sort-merge join, if the join condition has any equality between the two
+/// sides. Only inner joins can have a residual condition, which is evaluated on the joined rows. The rule is not
+/// registered by default, as the optimizer does not track the sort order of plan nodes, so the sorts of both sides are
+/// always charged and a sort-merge join never costs less than a hash join.
+pub struct SortMergeJoinRule {
+    matcher: RuleMatcher<OptRelNodeTyp>,
+    join_type: JoinType,
+    name: &'static str,
+}
+
+impl SortMergeJoinRule {
+    /// Returns `None` if the join type is not supported by `SortMergeJoinExec`.
+    pub fn new(join_type: JoinType) -> Option<Self> {
+        let name = match join_type {
+            JoinType::Inner => "sort_merge_join_inner",
+            JoinType::FullOuter => "sort_merge_join_full_outer",
+            JoinType::LeftOuter => "sort_merge_join_left_outer",
+            JoinType::RightOuter => "sort_merge_join_right_outer",
+            JoinType::LeftSemi => "sort_merge_join_left_semi",
+            JoinType::LeftAnti => "sort_merge_join_left_anti",
+            JoinType::Cross | JoinType::RightSemi | JoinType::RightAnti => return None,
         };
+        Some(Self {
+            matcher: RuleMatcher::MatchNode {
+                typ: OptRelNodeTyp::Join(join_type),
+                children: vec![
+                    RuleMatcher::PickOne {
+                        pick_to: LEFT,
+                        expand: false,
+                    },
+                    RuleMatcher::PickOne {
+                        pick_to: RIGHT,
+                        expand: false,
+                    },
+                    RuleMatcher::PickOne {
+                        pick_to: COND,
+                        expand: true,
+                    },
+                ],
+            },
+            join_type,
+            name,
+        })
+    }
 
-        if can_convert {
-            let right_expr = ColumnRefExpr::new(right_expr.index() - left_schema.0.len());
-            let node = PhysicalHashJoin::new(
-                PlanNode::from_group(left.into()),
-                PlanNode::from_group(right.into()),
-                ExprList::new(vec![left_expr.into_expr()]),
-                ExprList::new(vec![right_expr.into_expr()]),
-                JoinType::Inner,
-            );
-            return vec![node.into_rel_node().as_ref().clone()];
+    pub fn all_join_types<O: Optimizer<OptRelNodeTyp>>() -> Vec<Arc<dyn Rule<OptRelNodeTyp, O>>> {
+        JoinType::ALL
+            .into_iter()
+            .filter_map(SortMergeJoinRule::new)
+            .map(|x| Arc::new(x) as Arc<dyn Rule<OptRelNodeTyp, O>>)
+            .collect()
+    }
+}
+
+impl<O: Optimizer<OptRelNodeTyp>> Rule<OptRelNodeTyp, O> for SortMergeJoinRule {
+    fn matcher(&self) -> &RuleMatcher<OptRelNodeTyp> {
+        &self.matcher
+    }
+
+    fn apply(
+        &self,
+        optimizer: &O,
+        mut input: HashMap<usize, RelNode<OptRelNodeTyp>>,
+    ) -> Vec<RelNode<OptRelNodeTyp>> {
+        let left = input.remove(&LEFT).unwrap();
+        let right = input.remove(&RIGHT).unwrap();
+        let cond = input.remove(&COND).unwrap();
+        let Some((left_keys, right_keys, residual)) =
+            split_join_keys(optimizer, &left, cond, BinOpType::Eq)
+        else {
+            return vec![];
+        };
+        if !residual.is_empty() && self.join_type != JoinType::Inner {
+            return vec![];
         }
+        let node = PhysicalSortMergeJoin::new(
+            PlanNode::from_group(left.into()),
+            PlanNode::from_group(right.into()),
+            ExprList::new(left_keys),
+            ExprList::new(right_keys),
+            merge_conjunction(residual),
+            self.join_type,
+        );
+        vec![node.into_rel_node().as_ref().clone()]
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        "implement a join with an equality condition as a sort-merge join"
+    }
+
+    fn is_impl_rule(&self) -> bool {
+        true
     }
-    vec![]
 }
 
-// (Proj A) join B -> (Proj (A join B))
 define_rule!(
+    /// (Proj A) join B -> (Proj (A join B))
     ProjectionPullUpJoin,
     apply_projection_pull_up_join,
     (
@@ -345,3 +566,80 @@ fn apply_projection_pull_up_join(
     );
     vec![node.into_rel_node().as_ref().clone()]
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{HashJoinRule, JoinCommuteRule, SortMergeJoinRule};
+    use crate::{plan_nodes::JoinType, testing::assert_rule};
+
+    #[test]
+    fn join_commute() {
+        assert_rule(
+            Arc::new(JoinCommuteRule::new()),
+            r#"(Join(Inner) (Scan "t1") (Scan "t2") (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))"#,
+            &[r#"
+                (Projection
+                    (Join(Inner) (Scan "t2") (Scan "t1") (BinOp(Eq) (ColumnRef 3) (ColumnRef 0)))
+                    (List (ColumnRef 3) (ColumnRef 4) (ColumnRef 5) (ColumnRef 0) (ColumnRef 1) (ColumnRef 2)))
+            "#],
+        );
+        // only inner joins are commuted
+        assert_rule(
+            Arc::new(JoinCommuteRule::new()),
+            r#"(Join(LeftOuter) (Scan "t1") (Scan "t2") (Constant(Bool) true))"#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn hash_join_keys() {
+        let rule = || Arc::new(HashJoinRule::new(JoinType::LeftSemi));
+        // the keys of intersect and except match null keys
+        assert_rule(
+            rule(),
+            r#"(Join(LeftSemi) (Scan "t1") (Scan "t2") (BinOp(IsNotDistinctFrom) (ColumnRef 4) (ColumnRef 1)))"#,
+            &[r#"
+                (PhysicalHashJoin(LeftSemi) (Scan "t1") (Scan "t2")
+                    (List (ColumnRef 1)) (List (ColumnRef 1)) (Constant(Bool) true) (Constant(Bool) true))
+            "#],
+        );
+        // equalities are preferred as keys, and the other comparisons are left in the residual condition
+        assert_rule(
+            rule(),
+            r#"
+                (Join(LeftSemi) (Scan "t1") (Scan "t2")
+                    (LogOp(And) (List
+                        (BinOp(IsNotDistinctFrom) (ColumnRef 1) (ColumnRef 4))
+                        (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))))
+            "#,
+            &[r#"
+                (PhysicalHashJoin(LeftSemi) (Scan "t1") (Scan "t2")
+                    (List (ColumnRef 0)) (List (ColumnRef 0))
+                    (BinOp(IsNotDistinctFrom) (ColumnRef 1) (ColumnRef 4)) (Constant(Bool) false))
+            "#],
+        );
+    }
+
+    #[test]
+    fn sort_merge_join() {
+        assert!(SortMergeJoinRule::new(JoinType::Cross).is_none());
+        assert!(SortMergeJoinRule::new(JoinType::RightAnti).is_none());
+        let rule = || Arc::new(SortMergeJoinRule::new(JoinType::Inner).unwrap());
+        assert_rule(
+            rule(),
+            r#"(Join(Inner) (Scan "t1") (Scan "t2") (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))"#,
+            &[r#"
+                (PhysicalSortMergeJoin(Inner) (Scan "t1") (Scan "t2")
+                    (List (ColumnRef 0)) (List (ColumnRef 0)) (Constant(Bool) true))
+            "#],
+        );
+        // there are no keys to sort on
+        assert_rule(
+            rule(),
+            r#"(Join(Inner) (Scan "t1") (Scan "t2") (BinOp(Lt) (ColumnRef 0) (ColumnRef 3)))"#,
+            &[],
+        );
+    }
+}
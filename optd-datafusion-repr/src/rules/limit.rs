@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use optd_core::optimizer::Optimizer;
+use optd_core::rel_node::RelNode;
+use optd_core::rules::{Rule, RuleMatcher};
+
+use crate::plan_nodes::{
+    decode_limit, ExprList, OptRelNode, OptRelNodeTyp, PhysicalTopN, PlanNode,
+};
+
+/// Implements a limit over a sort with a top-n, which only keeps the first `skip + fetch` rows of the sorted order
+/// instead of sorting all rows. A limit without `fetch` returns all remaining rows and is not a top-n.
+pub struct TopNRule {
+    matcher: RuleMatcher<OptRelNodeTyp>,
+}
+
+const LIMIT: usize = 0;
+const CHILD: usize = 1;
+const EXPRS: usize = 2;
+
+impl TopNRule {
+    pub fn new() -> Self {
+        Self {
+            matcher: RuleMatcher::MatchAndPickNode {
+                typ: OptRelNodeTyp::Limit,
+                pick_to: LIMIT,
+                children: vec![RuleMatcher::MatchNode {
+                    typ: OptRelNodeTyp::Sort,
+                    children: vec![
+                        RuleMatcher::PickOne {
+                            pick_to: CHILD,
+                            expand: false,
+                        },
+                        RuleMatcher::PickOne {
+                            pick_to: EXPRS,
+                            expand: true,
+                        },
+                    ],
+                }],
+            },
+        }
+    }
+}
+
+impl<O: Optimizer<OptRelNodeTyp>> Rule<OptRelNodeTyp, O> for TopNRule {
+    fn matcher(&self) -> &RuleMatcher<OptRelNodeTyp> {
+        &self.matcher
+    }
+
+    fn apply(
+        &self,
+        _optimizer: &O,
+        mut input: HashMap<usize, RelNode<OptRelNodeTyp>>,
+    ) -> Vec<RelNode<OptRelNodeTyp>> {
+        let limit = input.remove(&LIMIT).unwrap();
+        let Some((skip, Some(fetch))) = decode_limit(limit.data.as_ref()) else {
+            return vec![];
+        };
+        let child = input.remove(&CHILD).unwrap();
+        let exprs = ExprList::from_rel_node(input.remove(&EXPRS).unwrap().into()).unwrap();
+        let node = PhysicalTopN::new(PlanNode::from_group(child.into()), exprs, skip, fetch);
+        vec![node.into_rel_node().as_ref().clone()]
+    }
+
+    fn name(&self) -> &'static str {
+        "top_n"
+    }
+
+    fn description(&self) -> &'static str {
+        "implement a limit over a sort as a top-n"
+    }
+
+    fn is_impl_rule(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::TopNRule;
+    use crate::testing::assert_rule;
+
+    #[test]
+    fn top_n() {
+        assert_rule(
+            Arc::new(TopNRule::new()),
+            r#"(Limit [2, 10] (Sort (Scan "t1") (List (SortOrder(Desc) (ColumnRef 1)))))"#,
+            &[r#"(PhysicalTopN [2, 10] (Scan "t1") (List (SortOrder(Desc) (ColumnRef 1))))"#],
+        );
+        // an offset without a limit returns all remaining rows
+        assert_rule(
+            Arc::new(TopNRule::new()),
+            r#"(Limit [2, null] (Sort (Scan "t1") (List (SortOrder(Desc) (ColumnRef 1)))))"#,
+            &[],
+        );
+    }
+}
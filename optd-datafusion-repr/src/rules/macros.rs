@@ -145,7 +145,8 @@ macro_rules! apply_matcher {
 }
 
 macro_rules! define_rule_inner {
-    ($is_impl_rule:expr, $name:ident, $apply:ident, $($matcher:tt)+) => {
+    ($is_impl_rule:expr, $(#[doc = $doc:literal])+ $name:ident, $apply:ident, $($matcher:tt)+) => {
+        $(#[doc = $doc])+
         pub struct $name {
             matcher: RuleMatcher<OptRelNodeTyp>,
         }
@@ -199,25 +200,24 @@ macro_rules! define_rule_inner {
             fn is_impl_rule(&self) -> bool {
                 $is_impl_rule
             }
+
+            fn description(&self) -> &'static str {
+                concat!($($doc),+).trim_start()
+            }
         }
     };
 }
 
+/// Define a rule from its documentation, which is also used as the description of the rule, the name of the rule, the
+/// function applying the rule, and the pattern matched by the rule.
 macro_rules! define_rule {
-    ($name:ident, $apply:ident, $($matcher:tt)+) => {
-        crate::rules::macros::define_rule_inner! { false, $name, $apply, $($matcher)+ }
-    };
-}
-
-macro_rules! define_impl_rule {
-    ($name:ident, $apply:ident, $($matcher:tt)+) => {
-        crate::rules::macros::define_rule_inner! { true, $name, $apply, $($matcher)+ }
+    ($(#[doc = $doc:literal])+ $name:ident, $apply:ident, $($matcher:tt)+) => {
+        crate::rules::macros::define_rule_inner! { false, $(#[doc = $doc])+ $name, $apply, $($matcher)+ }
     };
 }
 
 pub(crate) use apply_matcher;
 pub(crate) use collect_picks;
-pub(crate) use define_impl_rule;
 pub(crate) use define_matcher;
 pub(crate) use define_picks;
 pub(crate) use define_picks_struct;
@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+
+use optd_core::optimizer::Optimizer;
+use optd_core::rel_node::RelNode;
+use optd_core::rules::{Rule, RuleMatcher};
+
+use super::filter_pushdown::rewrite_column_refs;
+use super::macros::define_rule;
+use crate::plan_nodes::{
+    BinOpType, ColumnRefExpr, Expr, ExprList, JoinType, LogOpType, LogicalFilter, LogicalJoin,
+    LogicalProjection, OptRelNode, OptRelNodeRef, OptRelNodeTyp, PlanNode, UnOpType,
+};
+use crate::properties::schema::SchemaPropertyBuilder;
+
+/// Whether the expression is null when all the columns in `columns` are null.
+pub(crate) fn propagates_nulls(expr: &Expr, columns: Range<usize>) -> bool {
+    is_null(&expr.clone().into_rel_node(), &columns)
+}
+
+fn is_null(expr: &OptRelNodeRef, columns: &Range<usize>) -> bool {
+    if let Some(column) = ColumnRefExpr::from_rel_node(expr.clone()) {
+        return columns.contains(&column.index());
+    }
+    match expr.typ {
+        OptRelNodeTyp::BinOp(BinOpType::And | BinOpType::Or | BinOpType::IsNotDistinctFrom) => {
+            false
+        }
+        OptRelNodeTyp::BinOp(_) => {
+            is_null(&expr.child(0), columns) || is_null(&expr.child(1), columns)
+        }
+        OptRelNodeTyp::UnOp(UnOpType::Neg | UnOpType::Not) => is_null(&expr.child(0), columns),
+        _ => false,
+    }
+}
+
+/// Whether the predicate is never true when all the columns in `columns` are null, i.e., whether it filters out the
+/// rows padded with nulls by an outer join.
+fn rejects_nulls(cond: &Expr, columns: Range<usize>) -> bool {
+    fn rejects(expr: &OptRelNodeRef, columns: &Range<usize>) -> bool {
+        match expr.typ {
+            OptRelNodeTyp::LogOp(LogOpType::And) => {
+                expr.child(0).children.iter().any(|x| rejects(x, columns))
+            }
+            OptRelNodeTyp::LogOp(LogOpType::Or) => {
+                expr.child(0).children.iter().all(|x| rejects(x, columns))
+            }
+            OptRelNodeTyp::BinOp(BinOpType::And) => {
+                rejects(&expr.child(0), columns) || rejects(&expr.child(1), columns)
+            }
+            OptRelNodeTyp::BinOp(BinOpType::Or) => {
+                rejects(&expr.child(0), columns) && rejects(&expr.child(1), columns)
+            }
+            _ => is_null(expr, columns),
+        }
+    }
+
+    rejects(&cond.clone().into_rel_node(), &columns)
+}
+
+define_rule!(
+    /// A left join B -> B right join A
+    LeftJoinCommuteRule,
+    apply_left_join_commute,
+    (Join(JoinType::LeftOuter), left, right, [cond])
+);
+
+fn apply_left_join_commute(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    LeftJoinCommuteRulePicks { left, right, cond }: LeftJoinCommuteRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let left_size = optimizer
+        .get_property::<SchemaPropertyBuilder>(Arc::new(left.clone()), 0)
+        .len();
+    let right_size = optimizer
+        .get_property::<SchemaPropertyBuilder>(Arc::new(right.clone()), 0)
+        .len();
+    let cond = rewrite_column_refs(Expr::from_rel_node(cond.into()).unwrap(), &|x| {
+        let idx = if x < left_size {
+            x + right_size
+        } else {
+            x - left_size
+        };
+        Some(ColumnRefExpr::new(idx).into_expr())
+    })
+    .unwrap();
+    let node = LogicalJoin::new(
+        PlanNode::from_group(right.into()),
+        PlanNode::from_group(left.into()),
+        cond,
+        JoinType::RightOuter,
+    );
+    let exprs = (right_size..right_size + left_size)
+        .chain(0..right_size)
+        .map(|x| ColumnRefExpr::new(x).into_expr())
+        .collect();
+    let node = LogicalProjection::new(node.into_plan_node(), ExprList::new(exprs));
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+define_rule!(
+    /// (A left join B) left join C -> A left join (B left join C), if the second condition only refers to B and C and
+    /// rejects nulls from B
+    LeftJoinAssocRule,
+    apply_left_join_assoc,
+    (
+        Join(JoinType::LeftOuter),
+        (Join(JoinType::LeftOuter), a, b, [cond1]),
+        c,
+        [cond2]
+    )
+);
+
+fn apply_left_join_assoc(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    LeftJoinAssocRulePicks {
+        a,
+        b,
+        c,
+        cond1,
+        cond2,
+    }: LeftJoinAssocRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let a_size = optimizer
+        .get_property::<SchemaPropertyBuilder>(Arc::new(a.clone()), 0)
+        .len();
+    let b_size = optimizer
+        .get_property::<SchemaPropertyBuilder>(Arc::new(b.clone()), 0)
+        .len();
+    let cond2 = Expr::from_rel_node(cond2.into()).unwrap();
+    // if a row of A does not match any row of B, the null padded row must not match any row of C either
+    if !rejects_nulls(&cond2, a_size..a_size + b_size) {
+        return vec![];
+    }
+    let Some(cond2) = rewrite_column_refs(cond2, &|x| {
+        (x >= a_size).then(|| ColumnRefExpr::new(x - a_size).into_expr())
+    }) else {
+        return vec![];
+    };
+    let node = LogicalJoin::new(
+        PlanNode::from_group(a.into()),
+        LogicalJoin::new(
+            PlanNode::from_group(b.into()),
+            PlanNode::from_group(c.into()),
+            cond2,
+            JoinType::LeftOuter,
+        )
+        .into_plan_node(),
+        Expr::from_rel_node(cond1.into()).unwrap(),
+        JoinType::LeftOuter,
+    );
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+/// Simplifies an outer join below a filter that rejects the null padded rows of one or both sides, e.g., `Filter
+/// (A left join B)` becomes `Filter (A join B)` if the filter rejects nulls from B.
+pub struct OuterJoinToInnerRule {
+    matcher: RuleMatcher<OptRelNodeTyp>,
+    join_type: JoinType,
+    name: &'static str,
+}
+
+const LEFT: usize = 0;
+const RIGHT: usize = 1;
+const JOIN_COND: usize = 2;
+const COND: usize = 3;
+
+impl OuterJoinToInnerRule {
+    pub fn new(join_type: JoinType) -> Self {
+        Self {
+            matcher: RuleMatcher::MatchNode {
+                typ: OptRelNodeTyp::Filter,
+                children: vec![
+                    RuleMatcher::MatchNode {
+                        typ: OptRelNodeTyp::Join(join_type),
+                        children: vec![
+                            RuleMatcher::PickOne {
+                                pick_to: LEFT,
+                                expand: false,
+                            },
+                            RuleMatcher::PickOne {
+                                pick_to: RIGHT,
+                                expand: false,
+                            },
+                            RuleMatcher::PickOne {
+                                pick_to: JOIN_COND,
+                                expand: true,
+                            },
+                        ],
+                    },
+                    RuleMatcher::PickOne {
+                        pick_to: COND,
+                        expand: true,
+                    },
+                ],
+            },
+            join_type,
+            name: match join_type {
+                JoinType::LeftOuter => "outer_join_to_inner_left_outer",
+                JoinType::RightOuter => "outer_join_to_inner_right_outer",
+                JoinType::FullOuter => "outer_join_to_inner_full_outer",
+                _ => unimplemented!("{} is not an outer join", join_type),
+            },
+        }
+    }
+
+    pub fn all_join_types<O: Optimizer<OptRelNodeTyp>>() -> Vec<Arc<dyn Rule<OptRelNodeTyp, O>>> {
+        [
+            JoinType::LeftOuter,
+            JoinType::RightOuter,
+            JoinType::FullOuter,
+        ]
+        .into_iter()
+        .map(|x| Arc::new(OuterJoinToInnerRule::new(x)) as Arc<dyn Rule<OptRelNodeTyp, O>>)
+        .collect()
+    }
+}
+
+impl<O: Optimizer<OptRelNodeTyp>> Rule<OptRelNodeTyp, O> for OuterJoinToInnerRule {
+    fn matcher(&self) -> &RuleMatcher<OptRelNodeTyp> {
+        &self.matcher
+    }
+
+    fn apply(
+        &self,
+        optimizer: &O,
+        mut input: HashMap<usize, RelNode<OptRelNodeTyp>>,
+    ) -> Vec<RelNode<OptRelNodeTyp>> {
+        let left = input.remove(&LEFT).unwrap();
+        let right = input.remove(&RIGHT).unwrap();
+        let join_cond = input.remove(&JOIN_COND).unwrap();
+        let cond = Expr::from_rel_node(input.remove(&COND).unwrap().into()).unwrap();
+        let left_size = optimizer
+            .get_property::<SchemaPropertyBuilder>(Arc::new(left.clone()), 0)
+            .len();
+        let right_size = optimizer
+            .get_property::<SchemaPropertyBuilder>(Arc::new(right.clone()), 0)
+            .len();
+        let left_rejected = rejects_nulls(&cond, 0..left_size);
+        let right_rejected = rejects_nulls(&cond, left_size..left_size + right_size);
+        let join_type = match (self.join_type, left_rejected, right_rejected) {
+            (JoinType::LeftOuter, _, true) | (JoinType::RightOuter, true, _) => JoinType::Inner,
+            (JoinType::FullOuter, true, true) => JoinType::Inner,
+            (JoinType::FullOuter, true, false) => JoinType::LeftOuter,
+            (JoinType::FullOuter, false, true) => JoinType::RightOuter,
+            _ => return vec![],
+        };
+        let node = LogicalFilter::new(
+            LogicalJoin::new(
+                PlanNode::from_group(left.into()),
+                PlanNode::from_group(right.into()),
+                Expr::from_rel_node(join_cond.into()).unwrap(),
+                join_type,
+            )
+            .into_plan_node(),
+            cond,
+        );
+        vec![node.into_rel_node().as_ref().clone()]
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        "simplify an outer join below a filter that rejects its null padded rows"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use optd_core::rel_node::RelNode;
+
+    use super::{rejects_nulls, LeftJoinAssocRule, LeftJoinCommuteRule, OuterJoinToInnerRule};
+    use crate::{
+        plan_nodes::{Expr, JoinType, OptRelNode, OptRelNodeTyp},
+        testing::assert_rule,
+    };
+
+    fn expr(expr: &str) -> Expr {
+        Expr::from_rel_node(Arc::new(expr.parse::<RelNode<OptRelNodeTyp>>().unwrap())).unwrap()
+    }
+
+    #[test]
+    fn rejects_nulls_of_columns() {
+        let cond = expr("(BinOp(Eq) (ColumnRef 0) (ColumnRef 3))");
+        assert!(rejects_nulls(&cond, 0..3));
+        assert!(rejects_nulls(&cond, 3..6));
+        assert!(!rejects_nulls(&cond, 6..9));
+        let cond = expr("(UnOp(Not) (BinOp(Gt) (BinOp(Add) (ColumnRef 4) (Constant(Int) 1)) (Constant(Int) 2)))");
+        assert!(rejects_nulls(&cond, 3..6));
+        // `b IS NULL`, which is true for the null padded rows
+        let cond = expr("(BinOp(IsNotDistinctFrom) (ColumnRef 4) (Constant(Int) null))");
+        assert!(!rejects_nulls(&cond, 3..6));
+        // a conjunction rejects nulls if any of its conjuncts does
+        let cond = expr(
+            "(LogOp(And) (List (BinOp(Eq) (ColumnRef 0) (ColumnRef 1)) (BinOp(IsNotDistinctFrom) (ColumnRef 4) (Constant(Int) null))))",
+        );
+        assert!(rejects_nulls(&cond, 0..3));
+        assert!(!rejects_nulls(&cond, 3..6));
+        // a disjunction rejects nulls only if all of its branches do
+        let cond = expr(
+            "(LogOp(Or) (List (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)) (BinOp(Gt) (ColumnRef 0) (Constant(Int) 1))))",
+        );
+        assert!(rejects_nulls(&cond, 0..3));
+        assert!(!rejects_nulls(&cond, 3..6));
+        assert!(!rejects_nulls(&expr("(Constant(Bool) true)"), 0..3));
+    }
+
+    #[test]
+    fn left_join_commute() {
+        assert_rule(
+            Arc::new(LeftJoinCommuteRule::new()),
+            r#"(Join(LeftOuter) (Scan "t1") (Scan "t2") (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))"#,
+            &[r#"
+                (Projection
+                    (Join(RightOuter) (Scan "t2") (Scan "t1") (BinOp(Eq) (ColumnRef 3) (ColumnRef 0)))
+                    (List (ColumnRef 3) (ColumnRef 4) (ColumnRef 5) (ColumnRef 0) (ColumnRef 1) (ColumnRef 2)))
+            "#],
+        );
+        assert_rule(
+            Arc::new(LeftJoinCommuteRule::new()),
+            r#"(Join(FullOuter) (Scan "t1") (Scan "t2") (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))"#,
+            &[],
+        );
+    }
+
+    #[test]
+    fn left_join_assoc() {
+        let input = |cond2: &str| {
+            format!(
+                r#"
+                    (Join(LeftOuter)
+                        (Join(LeftOuter) (Scan "t1") (Scan "t2") (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))
+                        (Scan "t3")
+                        {cond2})
+                "#
+            )
+        };
+        assert_rule(
+            Arc::new(LeftJoinAssocRule::new()),
+            &input("(BinOp(Eq) (ColumnRef 4) (ColumnRef 6))"),
+            &[r#"
+                (Join(LeftOuter)
+                    (Scan "t1")
+                    (Join(LeftOuter) (Scan "t2") (Scan "t3") (BinOp(Eq) (ColumnRef 1) (ColumnRef 3)))
+                    (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))
+            "#],
+        );
+        // `b IS NULL` matches the null padded rows of B, which would be lost after reassociating
+        assert_rule(
+            Arc::new(LeftJoinAssocRule::new()),
+            &input("(BinOp(IsNotDistinctFrom) (ColumnRef 4) (Constant(Int) null))"),
+            &[],
+        );
+        // only one branch of the disjunction rejects nulls from B
+        assert_rule(
+            Arc::new(LeftJoinAssocRule::new()),
+            &input(
+                "(LogOp(Or) (List (BinOp(Eq) (ColumnRef 4) (ColumnRef 6)) (BinOp(Gt) (ColumnRef 7) (Constant(Int) 1))))",
+            ),
+            &[],
+        );
+        // the second condition refers to A
+        assert_rule(
+            Arc::new(LeftJoinAssocRule::new()),
+            &input(
+                "(LogOp(And) (List (BinOp(Eq) (ColumnRef 4) (ColumnRef 6)) (BinOp(Eq) (ColumnRef 1) (ColumnRef 7))))",
+            ),
+            &[],
+        );
+    }
+
+    #[test]
+    fn outer_join_to_inner() {
+        let input = |join_type: &str, cond: &str| {
+            format!(
+                r#"
+                    (Filter
+                        (Join({join_type}) (Scan "t1") (Scan "t2") (BinOp(Eq) (ColumnRef 0) (ColumnRef 3)))
+                        {cond})
+                "#
+            )
+        };
+        let rejects_left = "(BinOp(Gt) (ColumnRef 1) (Constant(Int) 1))";
+        let rejects_right = "(BinOp(Gt) (ColumnRef 4) (Constant(Int) 1))";
+        let rejects_both =
+            "(LogOp(And) (List (BinOp(Gt) (ColumnRef 1) (Constant(Int) 1)) (BinOp(Gt) (ColumnRef 4) (Constant(Int) 1))))";
+        let rejects_either =
+            "(LogOp(Or) (List (BinOp(Gt) (ColumnRef 1) (Constant(Int) 1)) (BinOp(Gt) (ColumnRef 4) (Constant(Int) 1))))";
+        let cases = [
+            (JoinType::LeftOuter, rejects_right, Some("Inner")),
+            (JoinType::LeftOuter, rejects_left, None),
+            (JoinType::RightOuter, rejects_left, Some("Inner")),
+            (JoinType::RightOuter, rejects_right, None),
+            (JoinType::FullOuter, rejects_left, Some("LeftOuter")),
+            (JoinType::FullOuter, rejects_right, Some("RightOuter")),
+            (JoinType::FullOuter, rejects_both, Some("Inner")),
+            (JoinType::FullOuter, rejects_either, None),
+        ];
+        for (join_type, cond, expected) in cases {
+            let expected = expected.map(|x| input(x, cond));
+            assert_rule(
+                Arc::new(OuterJoinToInnerRule::new(join_type)),
+                &input(&join_type.to_string(), cond),
+                &expected.iter().map(|x| x.as_str()).collect::<Vec<_>>(),
+            );
+        }
+    }
+}
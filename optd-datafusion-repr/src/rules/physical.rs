@@ -5,15 +5,41 @@ use optd_core::optimizer::Optimizer;
 use optd_core::rel_node::RelNode;
 use optd_core::rules::{Rule, RuleMatcher};
 
-use crate::plan_nodes::{JoinType, OptRelNodeTyp};
+use crate::plan_nodes::{JoinType, OptRelNodeTyp, UnionType};
 
 pub struct PhysicalConversionRule {
     matcher: RuleMatcher<OptRelNodeTyp>,
+    name: &'static str,
 }
 
 impl PhysicalConversionRule {
     pub fn new(logical_typ: OptRelNodeTyp) -> Self {
+        let name = match logical_typ {
+            OptRelNodeTyp::Scan => "physical_conversion_scan",
+            OptRelNodeTyp::Projection => "physical_conversion_projection",
+            OptRelNodeTyp::Join(JoinType::Inner) => "physical_conversion_inner_join",
+            OptRelNodeTyp::Join(JoinType::FullOuter) => "physical_conversion_full_outer_join",
+            OptRelNodeTyp::Join(JoinType::LeftOuter) => "physical_conversion_left_outer_join",
+            OptRelNodeTyp::Join(JoinType::RightOuter) => "physical_conversion_right_outer_join",
+            OptRelNodeTyp::Join(JoinType::Cross) => "physical_conversion_cross_join",
+            OptRelNodeTyp::Join(JoinType::LeftSemi) => "physical_conversion_left_semi_join",
+            OptRelNodeTyp::Join(JoinType::RightSemi) => "physical_conversion_right_semi_join",
+            OptRelNodeTyp::Join(JoinType::LeftAnti) => "physical_conversion_left_anti_join",
+            OptRelNodeTyp::Join(JoinType::RightAnti) => "physical_conversion_right_anti_join",
+            OptRelNodeTyp::Filter => "physical_conversion_filter",
+            OptRelNodeTyp::Sort => "physical_conversion_sort",
+            OptRelNodeTyp::Agg => "physical_conversion_agg",
+            OptRelNodeTyp::Limit => "physical_conversion_limit",
+            OptRelNodeTyp::Union(UnionType::All) => "physical_conversion_union_all",
+            OptRelNodeTyp::Union(UnionType::Distinct) => "physical_conversion_union_distinct",
+            OptRelNodeTyp::Window => "physical_conversion_window",
+            OptRelNodeTyp::Values => "physical_conversion_values",
+            OptRelNodeTyp::EmptyRelation => "physical_conversion_empty_relation",
+            OptRelNodeTyp::Apply(_) => "physical_conversion_apply",
+            _ => unimplemented!("no physical conversion for {}", logical_typ),
+        };
         Self {
+            name,
             matcher: RuleMatcher::MatchAndPickNode {
                 typ: logical_typ,
                 pick_to: 0,
@@ -25,19 +51,30 @@ impl PhysicalConversionRule {
 
 impl PhysicalConversionRule {
     pub fn all_conversions<O: Optimizer<OptRelNodeTyp>>() -> Vec<Arc<dyn Rule<OptRelNodeTyp, O>>> {
-        vec![
+        let mut rules: Vec<Arc<dyn Rule<OptRelNodeTyp, O>>> = vec![
             Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Scan)),
             Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Projection)),
-            Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Join(
-                JoinType::Inner,
-            ))),
-            Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Join(
-                JoinType::LeftOuter,
-            ))),
-            Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Filter)),
-            Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Sort)),
-            Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Agg)),
-        ]
+        ];
+        for join_type in JoinType::ALL {
+            rules.push(Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Join(
+                join_type,
+            ))));
+        }
+        rules.push(Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Filter)));
+        rules.push(Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Sort)));
+        rules.push(Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Agg)));
+        rules.push(Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Limit)));
+        for union_type in UnionType::ALL {
+            rules.push(Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Union(
+                union_type,
+            ))));
+        }
+        rules.push(Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Window)));
+        rules.push(Arc::new(PhysicalConversionRule::new(OptRelNodeTyp::Values)));
+        rules.push(Arc::new(PhysicalConversionRule::new(
+            OptRelNodeTyp::EmptyRelation,
+        )));
+        rules
     }
 }
 
@@ -114,6 +151,46 @@ impl<O: Optimizer<OptRelNodeTyp>> Rule<OptRelNodeTyp, O> for PhysicalConversionR
                 };
                 vec![node]
             }
+            OptRelNodeTyp::Limit => {
+                let node = RelNode {
+                    typ: OptRelNodeTyp::PhysicalLimit,
+                    children,
+                    data,
+                };
+                vec![node]
+            }
+            OptRelNodeTyp::Union(x) => {
+                let node = RelNode {
+                    typ: OptRelNodeTyp::PhysicalUnion(x),
+                    children,
+                    data,
+                };
+                vec![node]
+            }
+            OptRelNodeTyp::Window => {
+                let node = RelNode {
+                    typ: OptRelNodeTyp::PhysicalWindow,
+                    children,
+                    data,
+                };
+                vec![node]
+            }
+            OptRelNodeTyp::Values => {
+                let node = RelNode {
+                    typ: OptRelNodeTyp::PhysicalValues,
+                    children,
+                    data,
+                };
+                vec![node]
+            }
+            OptRelNodeTyp::EmptyRelation => {
+                let node = RelNode {
+                    typ: OptRelNodeTyp::PhysicalEmptyRelation,
+                    children,
+                    data,
+                };
+                vec![node]
+            }
             _ => vec![],
         }
     }
@@ -123,6 +200,14 @@ impl<O: Optimizer<OptRelNodeTyp>> Rule<OptRelNodeTyp, O> for PhysicalConversionR
     }
 
     fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn category(&self) -> &'static str {
         "physical_conversion"
     }
+
+    fn description(&self) -> &'static str {
+        "convert a logical plan node into its physical counterpart"
+    }
 }
@@ -0,0 +1,375 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use optd_core::optimizer::Optimizer;
+use optd_core::rel_node::RelNode;
+use optd_core::rules::{Rule, RuleMatcher};
+
+use super::filter_pushdown::{is_deterministic, referenced_columns, rewrite_column_refs};
+use super::macros::define_rule;
+use crate::plan_nodes::{
+    ColumnRefExpr, Expr, ExprList, JoinType, LogicalAgg, LogicalFilter, LogicalJoin,
+    LogicalProjection, LogicalUnion, OptRelNode, OptRelNodeRef, OptRelNodeTyp, PlanNode, UnionType,
+};
+use crate::properties::schema::SchemaPropertyBuilder;
+
+/// The columns of a plan node that are required by its parent, and the positions they are mapped to after the
+/// unused columns are pruned.
+struct RequiredColumns {
+    columns: Vec<usize>,
+}
+
+impl RequiredColumns {
+    fn new(columns: BTreeSet<usize>) -> Self {
+        Self {
+            columns: columns.into_iter().collect(),
+        }
+    }
+
+    /// The columns referenced by any of the expressions. At least one column is required, so that the number of rows
+    /// is preserved after pruning.
+    fn of_exprs<'a>(exprs: impl IntoIterator<Item = &'a Expr>) -> BTreeSet<usize> {
+        let mut columns = exprs
+            .into_iter()
+            .flat_map(referenced_columns)
+            .collect::<BTreeSet<_>>();
+        if columns.is_empty() {
+            columns.insert(0);
+        }
+        columns
+    }
+
+    /// The projection that only keeps the required columns.
+    fn projection(&self) -> ExprList {
+        ExprList::new(
+            self.columns
+                .iter()
+                .map(|&x| ColumnRefExpr::new(x).into_expr())
+                .collect(),
+        )
+    }
+
+    /// Rewrite an expression on the original columns to the pruned columns.
+    fn rewrite(&self, expr: Expr) -> Expr {
+        rewrite_column_refs(expr, &|x| {
+            let idx = self.columns.binary_search(&x).ok()?;
+            Some(ColumnRefExpr::new(idx).into_expr())
+        })
+        .unwrap()
+    }
+
+    fn rewrite_list(&self, exprs: ExprList) -> ExprList {
+        ExprList::new(
+            exprs
+                .to_vec()
+                .into_iter()
+                .map(|x| self.rewrite(x))
+                .collect(),
+        )
+    }
+}
+
+/// Prune the unused columns of a node with `column_count` columns with a projection, if any column is unused.
+fn prune_columns(
+    child: PlanNode,
+    column_count: usize,
+    required: BTreeSet<usize>,
+) -> (PlanNode, RequiredColumns) {
+    let required = RequiredColumns::new(required);
+    if required.columns.len() >= column_count {
+        return (child, required);
+    }
+    let node = LogicalProjection::new(child, required.projection()).into_plan_node();
+    (node, required)
+}
+
+define_rule!(
+    /// Proj (Proj A) -> Proj A
+    ProjectionMergeRule,
+    apply_projection_merge,
+    (Projection, (Projection, child, [exprs1]), [exprs2])
+);
+
+fn apply_projection_merge(
+    _optimizer: &impl Optimizer<OptRelNodeTyp>,
+    ProjectionMergeRulePicks {
+        child,
+        exprs1,
+        exprs2,
+    }: ProjectionMergeRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let exprs1 = ExprList::from_rel_node(exprs1.into()).unwrap();
+    let exprs2 = ExprList::from_rel_node(exprs2.into()).unwrap();
+    // inlining a volatile expression more than once would evaluate it several times per row with different results
+    let mut uses = HashMap::new();
+    for expr in exprs2.to_vec() {
+        count_column_refs(&expr.into_rel_node(), &mut uses);
+    }
+    if uses
+        .iter()
+        .any(|(&idx, &count)| count > 1 && !is_deterministic(&exprs1.child(idx).into_rel_node()))
+    {
+        return vec![];
+    }
+    let Some(exprs) = exprs2
+        .to_vec()
+        .into_iter()
+        .map(|x| rewrite_column_refs(x, &|x| Some(exprs1.child(x))))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return vec![];
+    };
+    let node = LogicalProjection::new(PlanNode::from_group(child.into()), ExprList::new(exprs));
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+/// Count how many times each column is referenced by an expression.
+fn count_column_refs(expr: &OptRelNodeRef, counts: &mut HashMap<usize, usize>) {
+    if let Some(column) = ColumnRefExpr::from_rel_node(expr.clone()) {
+        *counts.entry(column.index()).or_default() += 1;
+        return;
+    }
+    for child in &expr.children {
+        count_column_refs(child, counts);
+    }
+}
+
+define_rule!(
+    /// Proj A -> A, if the projection outputs all columns of A in order
+    EliminateProjectionRule,
+    apply_eliminate_projection,
+    (Projection, child, [exprs])
+);
+
+fn apply_eliminate_projection(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    EliminateProjectionRulePicks { child, exprs }: EliminateProjectionRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let exprs = ExprList::from_rel_node(exprs.into()).unwrap();
+    let child_schema = optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(child.clone()), 0);
+    if exprs.len() != child_schema.len() {
+        return vec![];
+    }
+    for (idx, expr) in exprs.to_vec().into_iter().enumerate() {
+        match ColumnRefExpr::from_rel_node(expr.into_rel_node()) {
+            Some(column) if column.index() == idx => {}
+            _ => return vec![],
+        }
+    }
+    vec![child]
+}
+
+define_rule!(
+    /// Proj (Filter A) -> Proj (Filter (Proj A)), only keeping the columns used by the filter and the projection
+    ProjectionFilterTransposeRule,
+    apply_projection_filter_transpose,
+    (Projection, (Filter, child, [cond]), [exprs])
+);
+
+fn apply_projection_filter_transpose(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    ProjectionFilterTransposeRulePicks { child, cond, exprs }: ProjectionFilterTransposeRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let child_schema = optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(child.clone()), 0);
+    let exprs = ExprList::from_rel_node(exprs.into()).unwrap();
+    let cond = Expr::from_rel_node(cond.into()).unwrap();
+    let mut exprs_and_cond = exprs.to_vec();
+    exprs_and_cond.push(cond.clone());
+    let required = RequiredColumns::of_exprs(&exprs_and_cond);
+    if required.len() >= child_schema.len() {
+        return vec![];
+    }
+    let (child, required) = prune_columns(
+        PlanNode::from_group(child.into()),
+        child_schema.len(),
+        required,
+    );
+    let node = LogicalProjection::new(
+        LogicalFilter::new(child, required.rewrite(cond)).into_plan_node(),
+        required.rewrite_list(exprs),
+    );
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+define_rule!(
+    /// Proj (A join B) -> Proj ((Proj A) join (Proj B)), only keeping the columns used by the join and the projection
+    ProjectionJoinTransposeRule,
+    apply_projection_join_transpose,
+    (
+        Projection,
+        (Join(JoinType::Inner), left, right, [cond]),
+        [exprs]
+    )
+);
+
+fn apply_projection_join_transpose(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    ProjectionJoinTransposeRulePicks {
+        left,
+        right,
+        cond,
+        exprs,
+    }: ProjectionJoinTransposeRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let left_schema = optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(left.clone()), 0);
+    let right_schema = optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(right.clone()), 0);
+    let exprs = ExprList::from_rel_node(exprs.into()).unwrap();
+    let cond = Expr::from_rel_node(cond.into()).unwrap();
+    let mut exprs_and_cond = exprs.to_vec();
+    exprs_and_cond.push(cond.clone());
+    let required = exprs_and_cond
+        .iter()
+        .flat_map(referenced_columns)
+        .collect::<BTreeSet<_>>();
+    let mut left_required = required
+        .iter()
+        .copied()
+        .filter(|&x| x < left_schema.len())
+        .collect::<BTreeSet<_>>();
+    let mut right_required = required
+        .iter()
+        .filter(|&&x| x >= left_schema.len())
+        .map(|x| x - left_schema.len())
+        .collect::<BTreeSet<_>>();
+    // keep at least one column of each side, so that the number of rows is preserved
+    for side in [&mut left_required, &mut right_required] {
+        if side.is_empty() {
+            side.insert(0);
+        }
+    }
+    if left_required.len() >= left_schema.len() && right_required.len() >= right_schema.len() {
+        return vec![];
+    }
+    let (left, left_required) = prune_columns(
+        PlanNode::from_group(left.into()),
+        left_schema.len(),
+        left_required,
+    );
+    let (right, right_required) = prune_columns(
+        PlanNode::from_group(right.into()),
+        right_schema.len(),
+        right_required,
+    );
+    let (left_size, new_left_size) = (left_schema.len(), left_required.columns.len());
+    let rewrite = |expr: Expr| {
+        rewrite_column_refs(expr, &|x| {
+            let idx = if x < left_size {
+                left_required.columns.binary_search(&x).ok()?
+            } else {
+                new_left_size
+                    + right_required
+                        .columns
+                        .binary_search(&(x - left_size))
+                        .ok()?
+            };
+            Some(ColumnRefExpr::new(idx).into_expr())
+        })
+        .unwrap()
+    };
+    let node = LogicalProjection::new(
+        LogicalJoin::new(left, right, rewrite(cond), JoinType::Inner).into_plan_node(),
+        ExprList::new(exprs.to_vec().into_iter().map(rewrite).collect()),
+    );
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+define_rule!(
+    /// Proj (A union all B) -> (Proj A) union all (Proj B). Not applicable to a distinct union, as the projection can
+    /// make distinct rows equal.
+    ProjectionUnionTransposeRule,
+    apply_projection_union_transpose,
+    (Projection, (Union(UnionType::All), left, right), [exprs])
+);
+
+fn apply_projection_union_transpose(
+    _optimizer: &impl Optimizer<OptRelNodeTyp>,
+    ProjectionUnionTransposeRulePicks { left, right, exprs }: ProjectionUnionTransposeRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let exprs = ExprList::from_rel_node(exprs.into()).unwrap();
+    let node = LogicalUnion::new(
+        LogicalProjection::new(PlanNode::from_group(left.into()), exprs.clone()).into_plan_node(),
+        LogicalProjection::new(PlanNode::from_group(right.into()), exprs).into_plan_node(),
+        UnionType::All,
+    );
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+define_rule!(
+    /// Agg A -> Agg (Proj A), only keeping the columns used by the aggregation
+    AggInputPruningRule,
+    apply_agg_input_pruning,
+    (Agg, child, [exprs], [groups])
+);
+
+fn apply_agg_input_pruning(
+    optimizer: &impl Optimizer<OptRelNodeTyp>,
+    AggInputPruningRulePicks {
+        child,
+        exprs,
+        groups,
+    }: AggInputPruningRulePicks,
+) -> Vec<RelNode<OptRelNodeTyp>> {
+    let child_schema = optimizer.get_property::<SchemaPropertyBuilder>(Arc::new(child.clone()), 0);
+    let exprs = ExprList::from_rel_node(exprs.into()).unwrap();
+    let groups = ExprList::from_rel_node(groups.into()).unwrap();
+    let required = RequiredColumns::of_exprs(exprs.to_vec().iter().chain(groups.to_vec().iter()));
+    if required.len() >= child_schema.len() {
+        return vec![];
+    }
+    let (child, required) = prune_columns(
+        PlanNode::from_group(child.into()),
+        child_schema.len(),
+        required,
+    );
+    let node = LogicalAgg::new(
+        child,
+        required.rewrite_list(exprs),
+        required.rewrite_list(groups),
+    );
+    vec![node.into_rel_node().as_ref().clone()]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::ProjectionMergeRule;
+    use crate::testing::assert_rule;
+
+    #[test]
+    fn projection_merge() {
+        assert_rule(
+            Arc::new(ProjectionMergeRule::new()),
+            r#"
+                (Projection
+                    (Projection (Scan "t1") (List (ColumnRef 2) (BinOp(Add) (ColumnRef 0) (ColumnRef 1))))
+                    (List (ColumnRef 1) (ColumnRef 0)))
+            "#,
+            &[r#"
+                (Projection (Scan "t1") (List (BinOp(Add) (ColumnRef 0) (ColumnRef 1)) (ColumnRef 2)))
+            "#],
+        );
+        // a volatile expression referenced once is still evaluated once per row
+        assert_rule(
+            Arc::new(ProjectionMergeRule::new()),
+            r#"
+                (Projection
+                    (Projection (Scan "t1") (List (ColumnRef 0) (Func(Scalar(Random)) (List))))
+                    (List (BinOp(Add) (ColumnRef 0) (ColumnRef 1))))
+            "#,
+            &[r#"
+                (Projection (Scan "t1") (List (BinOp(Add) (ColumnRef 0) (Func(Scalar(Random)) (List)))))
+            "#],
+        );
+        // a volatile expression referenced twice would be evaluated twice with different results
+        assert_rule(
+            Arc::new(ProjectionMergeRule::new()),
+            r#"
+                (Projection
+                    (Projection (Scan "t1") (List (ColumnRef 0) (Func(Scalar(Random)) (List))))
+                    (List (ColumnRef 1) (BinOp(Add) (ColumnRef 0) (ColumnRef 1))))
+            "#,
+            &[],
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! A test catalog and helpers for testing rules with input and expected output plans in the textual plan format.
+
+use std::{collections::HashMap, sync::Arc};
+
+use optd_core::{cascades::CascadesOptimizer, rel_node::RelNode, rules::Rule};
+
+use crate::{
+    cost::OptCostModel,
+    plan_nodes::{ConstantType, OptRelNodeTyp},
+    properties::schema::{Catalog, Field, Schema},
+};
+
+/// Every table has the integer columns `a`, `b` and `c`, where `a` is a key and not nullable.
+pub(crate) struct TestCatalog;
+
+impl Catalog for TestCatalog {
+    fn get(&self, _name: &str) -> Schema {
+        Schema::new(
+            ["a", "b", "c"]
+                .into_iter()
+                .map(|name| Field {
+                    name: name.to_string(),
+                    typ: ConstantType::Int,
+                    nullable: name != "a",
+                })
+                .collect(),
+        )
+    }
+
+    fn get_keys(&self, _name: &str) -> Vec<Vec<usize>> {
+        vec![vec![0]]
+    }
+}
+
+pub(crate) type TestRule = Arc<dyn Rule<OptRelNodeTyp, CascadesOptimizer<OptRelNodeTyp>>>;
+
+/// Apply `rule` once to the root of the `input` plan, and check that it produces the `expected` plans in order. The
+/// groups in the produced plans are expanded, so the expected plans do not have placeholders.
+pub(crate) fn assert_rule(rule: TestRule, input: &str, expected: &[&str]) {
+    let mut optimizer = CascadesOptimizer::new(
+        vec![rule],
+        Box::new(OptCostModel::new(HashMap::new())),
+        crate::property_builders(Box::new(TestCatalog)),
+    );
+    let input: RelNode<OptRelNodeTyp> = input.parse().unwrap();
+    let output = optimizer
+        .apply_rule_to_root(0, input.into())
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+    // compare the display format for readable diffs, after normalizing the expected plans
+    let expected = expected
+        .iter()
+        .map(|x| x.parse::<RelNode<OptRelNodeTyp>>().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(output, expected);
+}
@@ -17,9 +17,9 @@ static GLOBAL: MiMalloc = MiMalloc;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
 
-#[derive(Default)]
 pub struct DatafusionDb {
     ctx: SessionContext,
+    planner: Arc<OptdQueryPlanner>,
 }
 
 impl DatafusionDb {
@@ -29,17 +29,19 @@ impl DatafusionDb {
         let rn_config = RuntimeConfig::new();
         let runtime_env = RuntimeEnv::new(rn_config.clone())?;
 
+        let planner;
         let ctx = {
             let mut state =
                 SessionState::new_with_config_rt(session_config.clone(), Arc::new(runtime_env));
             let optimizer = DatafusionOptimizer::new_physical(Box::new(DatafusionCatalog::new(
                 state.catalog_list(),
             )));
-            state = state.with_query_planner(Arc::new(OptdQueryPlanner::new(optimizer)));
+            planner = Arc::new(OptdQueryPlanner::new(optimizer));
+            state = state.with_query_planner(planner.clone());
             SessionContext::new_with_state(state)
         };
         ctx.refresh_catalogs().await?;
-        Ok(Self { ctx })
+        Ok(Self { ctx, planner })
     }
 
     async fn execute(&self, sql: &str) -> Result<Vec<Vec<String>>> {
@@ -74,22 +76,24 @@ impl DatafusionDb {
         }
         Ok(result)
     }
-}
-
-#[async_trait]
-impl sqlplannertest::PlannerTestRunner for DatafusionDb {
-    async fn run(&mut self, test_case: &sqlplannertest::ParsedTestCase) -> Result<String> {
-        for before in &test_case.before_sql {
-            self.execute(before)
-                .await
-                .context("before execution error")?;
-        }
 
+    /// Run the tasks of a test case, recording the rules disabled by the test case in `disabled_rules`.
+    async fn run_tasks(
+        &mut self,
+        test_case: &sqlplannertest::ParsedTestCase,
+        disabled_rules: &mut Vec<String>,
+    ) -> Result<String> {
         use std::fmt::Write;
         let mut result = String::new();
         let r = &mut result;
         for task in &test_case.tasks {
-            if task == "execute" {
+            if let Some(rules) = task.strip_prefix("disable_rules:") {
+                for rule in rules.split(',') {
+                    let rule = rule.trim();
+                    self.planner.disable_rule(rule)?;
+                    disabled_rules.push(rule.to_string());
+                }
+            } else if task == "execute" {
                 let result = self.execute(&test_case.sql).await?;
                 writeln!(r, "{}", result.into_iter().map(|x| x.join(" ")).join("\n"))?;
                 writeln!(r)?;
@@ -126,3 +130,22 @@ impl sqlplannertest::PlannerTestRunner for DatafusionDb {
         Ok(result)
     }
 }
+
+#[async_trait]
+impl sqlplannertest::PlannerTestRunner for DatafusionDb {
+    async fn run(&mut self, test_case: &sqlplannertest::ParsedTestCase) -> Result<String> {
+        for before in &test_case.before_sql {
+            self.execute(before)
+                .await
+                .context("before execution error")?;
+        }
+
+        // rules disabled by the test case, enabled again after the test case even if it fails
+        let mut disabled_rules = Vec::new();
+        let result = self.run_tasks(test_case, &mut disabled_rules).await;
+        for rule in disabled_rules {
+            self.planner.enable_rule(&rule)?;
+        }
+        result
+    }
+}
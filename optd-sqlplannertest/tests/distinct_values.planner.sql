@@ -0,0 +1,53 @@
+-- (no id or description)
+create table d(v int);
+insert into d values (1), (1), (2), (2), (3);
+
+/*
+5
+*/
+
+-- DISTINCT removes the duplicate rows.
+select distinct v from d order by v;
+
+/*
+1
+2
+3
+*/
+
+-- The distinct rows are counted once.
+select count(*) from (select distinct v from d);
+
+/*
+3
+*/
+
+-- VALUES with several columns.
+select * from (values (1, 'a'), (2, 'b')) as t(x, y) order by x;
+
+/*
+1 a
+2 b
+*/
+
+-- VALUES with a null.
+select count(*), count(x) from (values (1), (null)) as t(x);
+
+/*
+2 1
+*/
+
+-- A projection over a relation of one empty row.
+select 1 + 2;
+
+/*
+3
+*/
+
+-- A filter that is always false.
+select count(*) from d where false;
+
+/*
+0
+*/
+
@@ -0,0 +1,83 @@
+-- (no id or description)
+create table a(x int, y int);
+create table b(x int, z int);
+insert into a values (1, 10), (2, 20), (3, 30);
+insert into b values (1, 100), (1, 101), (4, 400);
+
+/*
+3
+3
+*/
+
+-- A left join keeps the unmatched rows of the left side.
+select count(*), count(b.z) from a left join b on a.x = b.x;
+
+/*
+4 2
+*/
+
+-- A right join keeps the unmatched rows of the right side.
+select count(*), count(a.y) from a right join b on a.x = b.x;
+
+/*
+3 2
+*/
+
+-- A full join keeps the unmatched rows of both sides.
+select count(*), count(a.y), count(b.z) from a full join b on a.x = b.x;
+
+/*
+5 4 3
+*/
+
+-- The filter of a left join only decides which rows match.
+select count(*), count(b.z) from a left join b on a.x = b.x and b.z > 100;
+
+/*
+3 1
+*/
+
+-- EXISTS is a semi join.
+select x from a where exists (select * from b where b.x = a.x) order by x;
+
+/*
+1
+*/
+
+-- NOT EXISTS is an anti join.
+select x from a where not exists (select * from b where b.x = a.x) order by x;
+
+/*
+2
+3
+*/
+
+-- A cross join.
+select count(*) from a, b;
+
+/*
+9
+*/
+
+-- An inner sort-merge join, with the hash and nested loop joins disabled.
+select a.x, a.y, b.z from a join b on a.x = b.x order by b.z;
+
+/*
+1 10 100
+1 10 101
+*/
+
+-- An inner sort-merge join with a residual condition.
+select a.x, b.z from a join b on a.x = b.x and b.z > a.y * 10;
+
+/*
+1 101
+*/
+
+-- A left outer sort-merge join keeps the unmatched rows of the left side.
+select count(*), count(b.z) from a left join b on a.x = b.x;
+
+/*
+4 2
+*/
+
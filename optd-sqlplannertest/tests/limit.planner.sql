@@ -0,0 +1,38 @@
+-- (no id or description)
+create table t(v int);
+insert into t values (5), (3), (1), (4), (2);
+
+/*
+5
+*/
+
+-- A sort with a limit is a top-n.
+select v from t order by v limit 2;
+
+/*
+1
+2
+*/
+
+-- A top-n skips the offset rows.
+select v from t order by v desc limit 2 offset 1;
+
+/*
+4
+3
+*/
+
+-- A limit without an order.
+select count(*) from (select v from t limit 3);
+
+/*
+3
+*/
+
+-- A limit with fewer rows than it fetches.
+select count(*) from (select v from t limit 10 offset 4);
+
+/*
+1
+*/
+
@@ -0,0 +1,49 @@
+-- (no id or description)
+create table s1(v int);
+create table s2(v int);
+create table s3(v int);
+insert into s1 values (1), (1), (2), (null);
+insert into s2 values (1), (3), (null);
+insert into s3 values (3), (4);
+
+/*
+4
+3
+2
+*/
+
+-- UNION ALL keeps the duplicates.
+select count(*) from (select v from s1 union all select v from s2);
+
+/*
+7
+*/
+
+-- UNION removes the duplicates, and the nulls are equal.
+select count(*) from (select v from s1 union select v from s2);
+
+/*
+4
+*/
+
+-- The union of three inputs.
+select count(*) from (select v from s1 union select v from s2 union select v from s3);
+
+/*
+5
+*/
+
+-- INTERSECT is a semi join on which the nulls are equal.
+select count(*) from (select v from s1 intersect select v from s2);
+
+/*
+2
+*/
+
+-- EXCEPT is an anti join on which the nulls are equal.
+select v from s1 except select v from s2;
+
+/*
+2
+*/
+
@@ -0,0 +1,142 @@
+-- (no id or description)
+create table region(r_regionkey int not null, r_name varchar not null);
+create table nation(n_nationkey int not null, n_name varchar not null, n_regionkey int not null);
+create table supplier(s_suppkey int not null, s_name varchar not null, s_nationkey int not null);
+create table part(p_partkey int not null, p_name varchar not null, p_size int not null, p_type varchar not null);
+create table partsupp(ps_partkey int not null, ps_suppkey int not null, ps_availqty int not null, ps_supplycost int not null);
+create table customer(c_custkey int not null, c_phone varchar not null, c_acctbal int not null);
+create table orders(o_orderkey int not null, o_custkey int not null, o_orderstatus varchar not null, o_orderpriority varchar not null, o_orderdate int not null);
+create table lineitem(l_orderkey int not null, l_partkey int not null, l_suppkey int not null, l_quantity int not null, l_commitdate int not null, l_receiptdate int not null);
+create table t_in(x int);
+create table t_sub(y int);
+create table t_sub_no_null(y int);
+insert into region values (0, 'EUROPE'), (1, 'ASIA');
+insert into nation values (0, 'FRANCE', 0), (1, 'GERMANY', 0), (2, 'JAPAN', 1);
+insert into supplier values (1, 'S1', 0), (2, 'S2', 1), (3, 'S3', 2);
+insert into part values (1, 'green apple', 15, 'BRASS'), (2, 'red box', 15, 'STEEL'), (3, 'green leaf', 20, 'BRASS');
+insert into partsupp values (1, 1, 10, 5), (1, 2, 20, 3), (1, 3, 30, 1), (2, 1, 10, 4), (3, 2, 50, 2), (3, 3, 5, 7);
+insert into customer values (1, '13-111', 500), (2, '31-222', 50), (3, '13-333', 800), (4, '23-444', 900);
+insert into orders values (1, 1, 'F', '1-URGENT', 10), (2, 1, 'O', '2-HIGH', 20), (3, 3, 'F', '1-URGENT', 30);
+insert into lineitem values (1, 1, 1, 5, 5, 6), (1, 2, 2, 10, 5, 4), (2, 1, 2, 20, 9, 10), (2, 3, 3, 4, 9, 9), (3, 3, 2, 2, 5, 7), (3, 1, 1, 30, 5, 4);
+insert into t_in values (1), (2), (null);
+insert into t_sub values (1), (null);
+insert into t_sub_no_null values (1);
+
+/*
+2
+3
+3
+3
+6
+4
+3
+6
+3
+2
+1
+*/
+
+-- TPC-H Q2, with a correlated scalar subquery.
+select s_name, n_name, p_partkey
+from part, supplier, partsupp, nation, region
+where p_partkey = ps_partkey and s_suppkey = ps_suppkey and p_size = 15 and p_type = 'BRASS'
+    and s_nationkey = n_nationkey and n_regionkey = r_regionkey and r_name = 'EUROPE'
+    and ps_supplycost = (
+        select min(ps_supplycost) from partsupp, supplier, nation, region
+        where p_partkey = ps_partkey and s_suppkey = ps_suppkey and s_nationkey = n_nationkey
+            and n_regionkey = r_regionkey and r_name = 'EUROPE'
+    )
+order by s_name, p_partkey;
+
+/*
+S2 GERMANY 1
+*/
+
+-- TPC-H Q4, with a correlated EXISTS subquery.
+select o_orderpriority, count(*) as order_count
+from orders
+where o_orderdate >= 10 and o_orderdate < 30
+    and exists (select * from lineitem where l_orderkey = o_orderkey and l_commitdate < l_receiptdate)
+group by o_orderpriority
+order by o_orderpriority;
+
+/*
+1-URGENT 1
+2-HIGH 1
+*/
+
+-- TPC-H Q17, with a correlated scalar subquery on the same table.
+select sum(l_quantity) as total
+from lineitem, part
+where p_partkey = l_partkey and p_type = 'BRASS'
+    and l_quantity < (select avg(l_quantity) from lineitem where l_partkey = p_partkey);
+
+/*
+7
+*/
+
+-- TPC-H Q20, with nested IN subqueries and a correlated scalar subquery.
+select s_name
+from supplier, nation
+where s_suppkey in (
+        select ps_suppkey from partsupp
+        where ps_partkey in (select p_partkey from part where p_type = 'BRASS')
+            and ps_availqty > (
+                select sum(l_quantity) from lineitem where l_partkey = ps_partkey and l_suppkey = ps_suppkey
+            )
+    )
+    and s_nationkey = n_nationkey and n_name = 'GERMANY'
+order by s_name;
+
+/*
+S2
+*/
+
+-- TPC-H Q21, with correlated EXISTS and NOT EXISTS subqueries.
+select s_name, count(*) as numwait
+from supplier, lineitem l1, orders, nation
+where s_suppkey = l1.l_suppkey and o_orderkey = l1.l_orderkey and o_orderstatus = 'F'
+    and l1.l_receiptdate > l1.l_commitdate
+    and exists (select * from lineitem l2 where l2.l_orderkey = l1.l_orderkey and l2.l_suppkey <> l1.l_suppkey)
+    and not exists (
+        select * from lineitem l3
+        where l3.l_orderkey = l1.l_orderkey and l3.l_suppkey <> l1.l_suppkey and l3.l_receiptdate > l3.l_commitdate
+    )
+    and s_nationkey = n_nationkey
+group by s_name
+order by numwait desc, s_name;
+
+/*
+S1 1
+S2 1
+*/
+
+-- TPC-H Q22, with an uncorrelated scalar subquery and a correlated NOT EXISTS subquery.
+select cntrycode, count(*) as numcust, sum(c_acctbal) as totacctbal
+from (
+    select substr(c_phone, 1, 2) as cntrycode, c_acctbal
+    from customer
+    where c_acctbal > (select avg(c_acctbal) from customer where c_acctbal > 0)
+        and not exists (select * from orders where o_custkey = c_custkey)
+) as custsale
+group by cntrycode
+order by cntrycode;
+
+/*
+23 1 900
+*/
+
+-- NOT IN is false or null for every row if the subquery returns a null.
+select count(*) from t_in where x not in (select y from t_sub);
+
+/*
+0
+*/
+
+-- NOT IN is null for a null on the left side.
+select x from t_in where x not in (select y from t_sub_no_null);
+
+/*
+2
+*/
+
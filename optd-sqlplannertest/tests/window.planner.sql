@@ -0,0 +1,63 @@
+-- (no id or description)
+create table w(g int, v int);
+insert into w values (1, 10), (1, 20), (2, 5), (2, 5), (2, 7);
+
+/*
+5
+*/
+
+-- ROW_NUMBER numbers the rows of each partition.
+select g, v, row_number() over (partition by g order by v) as rn from w order by g, v, rn;
+
+/*
+1 10 1
+1 20 2
+2 5 1
+2 5 2
+2 7 3
+*/
+
+-- RANK gives the peers the same rank.
+select g, v, rank() over (partition by g order by v) as r from w order by g, v, r;
+
+/*
+1 10 1
+1 20 2
+2 5 1
+2 5 1
+2 7 3
+*/
+
+-- An aggregate over a whole partition.
+select g, sum(v) over (partition by g) as s from w order by g, s;
+
+/*
+1 30
+1 30
+2 17
+2 17
+2 17
+*/
+
+-- A running sum, which includes the peers of the current row.
+select g, v, sum(v) over (partition by g order by v) as s from w order by g, v, s;
+
+/*
+1 10 10
+1 20 30
+2 5 10
+2 5 10
+2 7 17
+*/
+
+-- Window functions with different partitions and orders.
+select v, row_number() over (order by v, g) as rn, sum(v) over (partition by g) as s from w order by rn;
+
+/*
+5 1 17
+5 2 17
+7 3 17
+10 4 30
+20 5 30
+*/
+